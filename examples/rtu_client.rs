@@ -14,7 +14,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let serial_builder = tokio_serial::new(tty_path, rate);
     let port = SerialStream::open(&serial_builder).unwrap();
 
-    let mut transport = Framed::new(port, RtuClientCodec);
+    let mut transport = Framed::new(port, RtuClientCodec::default());
 
     let frame = Frame::rtu();
     let request = frame.read_multiple_holding_registers_request(slave, 0x00, 0x02);
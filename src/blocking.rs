@@ -0,0 +1,206 @@
+//! Blocking client for users who don't run an async runtime.
+//!
+//! The client drives the same codecs as the async API over any [`Read`] + [`Write`] stream,
+//! e.g. a [`TcpStream`] or a synchronous serial port.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use easy_modbus::blocking::Client;
+//!
+//! let mut client = Client::connect_tcp("127.0.0.1:502").unwrap();
+//! let registers = client.read_holding_registers(0x01, 0x00, 0x02).unwrap();
+//! println!("{:?}", registers);
+//! ```
+
+use std::io::{Error, ErrorKind, Read, Result, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+
+use bytes::BytesMut;
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::client::unexpected;
+use crate::codec::{RtuClientCodec, TcpClientCodec};
+use crate::error::ModbusError;
+use crate::frame::{request::Request, response::Response, Frame};
+
+/// A blocking Modbus client
+#[derive(Debug)]
+pub struct Client<T, C> {
+    /// Underlying byte stream
+    stream: T,
+
+    /// Codec used to frame requests and responses
+    codec: C,
+
+    /// Request builder, keeps the transaction ids
+    frame: Frame,
+
+    /// Bytes received but not decoded yet
+    buf: BytesMut,
+}
+
+impl Client<TcpStream, TcpClientCodec> {
+    /// Connect to a Modbus TCP server
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use easy_modbus::blocking::Client;
+    /// let client = Client::connect_tcp("127.0.0.1:502").unwrap();
+    /// ```
+    pub fn connect_tcp<A: ToSocketAddrs>(addr: A) -> Result<Self> {
+        Ok(Client::tcp(TcpStream::connect(addr)?))
+    }
+}
+
+impl<T: Read + Write> Client<T, TcpClientCodec> {
+    /// Create a Modbus TCP client over an established stream
+    pub fn tcp(stream: T) -> Self {
+        Client::new(stream, TcpClientCodec::default(), Frame::tcp())
+    }
+}
+
+impl<T: Read + Write> Client<T, RtuClientCodec> {
+    /// Create a Modbus RTU client over a synchronous serial port
+    pub fn rtu(port: T) -> Self {
+        Client::new(port, RtuClientCodec::default(), Frame::rtu())
+    }
+}
+
+impl<T, C> Client<T, C>
+where
+    T: Read + Write,
+    C: Encoder<Request, Error = ModbusError> + Decoder<Item = Response, Error = ModbusError>,
+{
+    fn new(stream: T, codec: C, frame: Frame) -> Self {
+        Client {
+            stream,
+            codec,
+            frame,
+            buf: BytesMut::new(),
+        }
+    }
+
+    /// Send a request and block until its response is received
+    pub fn call(&mut self, request: Request) -> Result<Response> {
+        let mut dst = BytesMut::new();
+        self.codec.encode(request, &mut dst)?;
+        self.stream.write_all(&dst)?;
+        self.stream.flush()?;
+
+        let mut chunk = [0u8; 256];
+        loop {
+            if let Some(response) = self.codec.decode(&mut self.buf)? {
+                return Ok(response);
+            }
+            let n = self.stream.read(&mut chunk)?;
+            if n == 0 {
+                return Err(Error::new(
+                    ErrorKind::UnexpectedEof,
+                    "Connection closed before a complete response",
+                ));
+            }
+            self.buf.extend_from_slice(&chunk[..n]);
+        }
+    }
+
+    /// Read holding registers (Function Code: 0x03)
+    ///
+    /// * `slave` - Server address
+    /// * `address` - Address of first register to read
+    /// * `number` - Number of registers to read
+    pub fn read_holding_registers(
+        &mut self,
+        slave: u8,
+        address: u16,
+        number: u16,
+    ) -> Result<Vec<u16>> {
+        let request = self
+            .frame
+            .read_multiple_holding_registers_request(slave, address, number);
+        let response = match self.call(request)? {
+            Response::ReadMultipleHoldingRegisters(_, response) => response,
+            response => return Err(unexpected(response).into()),
+        };
+        let bytes_number = *response.get_bytes_number() as usize;
+        if bytes_number < number as usize * 2 {
+            return Err(ModbusError::InvalidFrame(format!(
+                "Read holding registers response carries {} bytes for {} registers",
+                bytes_number, number
+            ))
+            .into());
+        }
+        Ok(response.get_registers())
+    }
+}
+
+#[cfg(test)]
+mod blocking_client_test {
+    use std::io::{ErrorKind, Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    use bytes::BytesMut;
+    use tokio_util::codec::{Decoder, Encoder};
+
+    use crate::blocking::Client;
+    use crate::codec::TcpServerCodec;
+    use crate::frame::{Exception, Frame, Function};
+
+    fn mock_server<F>(reply: F) -> String
+    where
+        F: FnOnce(&Frame, u8) -> crate::Response + Send + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut codec = TcpServerCodec;
+            let mut buf = [0u8; 12];
+            stream.read_exact(&mut buf).unwrap();
+            let request = codec
+                .decode(&mut BytesMut::from(&buf[..]))
+                .unwrap()
+                .unwrap();
+            let response = reply(&Frame::tcp(), request.head().uid());
+            let mut dst = BytesMut::new();
+            codec.encode(response, &mut dst).unwrap();
+            stream.write_all(&dst).unwrap();
+        });
+        addr
+    }
+
+    #[test]
+    fn read_holding_registers_test() {
+        let addr = mock_server(|frame, uid| {
+            frame.read_holding_register_response(uid, vec![0x02, 0x2B, 0x01, 0x06])
+        });
+        let mut client = Client::connect_tcp(addr).unwrap();
+        let registers = client.read_holding_registers(0x01, 0x00, 0x02).unwrap();
+        assert_eq!(registers, vec![0x022B, 0x0106]);
+    }
+
+    #[test]
+    fn read_holding_registers_short_test() {
+        let addr =
+            mock_server(|frame, uid| frame.read_holding_register_response(uid, vec![0x02, 0x2B]));
+        let mut client = Client::connect_tcp(addr).unwrap();
+        let err = client.read_holding_registers(0x01, 0x00, 0x02).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn read_holding_registers_exception_test() {
+        let addr = mock_server(|frame, uid| {
+            frame.exception_response(
+                uid,
+                Function::ReadMultipleHoldingRegisters,
+                Exception::IllegalDataAddress,
+            )
+        });
+        let mut client = Client::connect_tcp(addr).unwrap();
+        let err = client.read_holding_registers(0x01, 0x00, 0x02).unwrap_err();
+        assert_eq!(err.kind(), Exception::IllegalDataAddress.as_error_kind());
+    }
+}
@@ -0,0 +1,2775 @@
+//! Async client based on a [`Framed`] transport.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//!
+//! use tokio_serial::SerialStream;
+//!
+//! use easy_modbus::client::Client;
+//! use easy_modbus::Frame;
+//!
+//! #[tokio::main(flavor = "current_thread")]
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let serial_builder = tokio_serial::new("COM4", 9600);
+//!     let port = SerialStream::open(&serial_builder)?;
+//!
+//!     let mut client = Client::rtu(port);
+//!     client.set_min_request_gap(Duration::from_millis(50));
+//!
+//!     let frame = Frame::rtu();
+//!     let request = frame.read_multiple_holding_registers_request(0x01, 0x00, 0x02);
+//!     let response = client.call(request).await?;
+//!     println!("Response:\t{}", response);
+//!     Ok(())
+//! }
+//! ```
+
+use std::collections::hash_map::RandomState;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, ErrorKind};
+use std::ops::{Deref, DerefMut, RangeInclusive};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bytes::BytesMut;
+use futures::{FutureExt, Sink, SinkExt, Stream, StreamExt};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpStream, ToSocketAddrs};
+use tokio::sync::{
+    mpsc, oneshot, Mutex as AsyncMutex, OwnedSemaphorePermit, Semaphore, SemaphorePermit,
+};
+use tokio::time::{self, Instant};
+use tokio_util::codec::{Framed, FramedRead};
+
+use crate::codec::{check_pdu_len, trace_frame, RtuClientCodec, TcpClientCodec};
+use crate::error::{ModbusError, Result};
+use crate::frame::{
+    request::{FileRecord, Request},
+    response::{Response, RunStatus},
+    DiagnosticsSubFunction, Exception, Frame, Version,
+};
+
+/// Most coils a single read coils request may ask for
+const MAX_READ_COILS: u16 = 0x07D0;
+
+/// Largest byte count of a read file record request or response
+const MAX_FILE_RECORD_BYTES: usize = 0xF5;
+
+/// Requests a pipelined client keeps in flight unless configured otherwise
+const DEFAULT_MAX_IN_FLIGHT: usize = 8;
+
+/// Longest delay between retries unless configured otherwise
+const DEFAULT_MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Carries requests to a server and its responses back, for [`Client`]
+///
+/// Any framed transport of requests and responses is one, like the TCP and RTU transports of
+/// [`Client::tcp`] and [`Client::rtu`]. Implement it to run the client over something else,
+/// e.g. TLS, a websocket or an in-memory pipe.
+pub trait Transport {
+    /// Send a request
+    fn send(&mut self, request: Request) -> impl Future<Output = Result<()>>;
+
+    /// Wait for the next response
+    ///
+    /// Fails with [`ErrorKind::UnexpectedEof`] once the transport is closed. The future may be
+    /// dropped before it completes, e.g. by a timeout, without losing a response.
+    fn recv(&mut self) -> impl Future<Output = Result<Response>>;
+}
+
+impl<T> Transport for T
+where
+    T: Sink<Request, Error = ModbusError> + Stream<Item = Result<Response>> + Unpin,
+{
+    async fn send(&mut self, request: Request) -> Result<()> {
+        SinkExt::send(self, request).await
+    }
+
+    async fn recv(&mut self) -> Result<Response> {
+        match self.next().await {
+            Some(response) => response,
+            None => Err(io::Error::new(
+                ErrorKind::UnexpectedEof,
+                "Transport closed before a response was received",
+            )
+            .into()),
+        }
+    }
+}
+
+/// Modbus client sending requests and awaiting their responses one at a time
+#[derive(Debug)]
+pub struct Client<T> {
+    /// Framed transport
+    transport: T,
+
+    /// Builds the requests of the convenience methods
+    frame: Frame,
+
+    /// Minimum delay between a response and the next request
+    min_request_gap: Option<Duration>,
+
+    /// When the previous response was received
+    last_response: Option<Instant>,
+
+    /// Shortest average interval between requests
+    rate_limit: Option<Duration>,
+
+    /// Earliest time the next request may be sent under the rate limit
+    next_send: Option<Instant>,
+
+    /// Longest stall allowed while a partial frame is being received
+    assembly_timeout: Option<Duration>,
+
+    /// Number of bytes received but not decoded yet
+    pending_bytes: fn(&T) -> usize,
+
+    /// Drop the bytes received but not decoded yet
+    discard_pending: fn(&mut T),
+
+    /// Device identification read by [`Client::read_device_info`]
+    device_info: Option<DeviceInfo>,
+
+    /// Error of the last operation, if it failed
+    last_error: Option<ModbusError>,
+
+    /// How failed calls are retried
+    retry_policy: Option<RetryPolicy>,
+
+    /// Transaction id of the next TCP request
+    next_tid: u16,
+
+    /// Longest wait for a response once its request is sent
+    timeout: Option<Duration>,
+
+    /// Whether a request was sent whose response was not received, e.g. by a cancelled call
+    exchange_pending: bool,
+
+    /// Whether the transport failed to give the last response
+    recv_failed: bool,
+}
+
+impl Client<Framed<TcpStream, TcpClientCodec>> {
+    /// Connect to a Modbus TCP server
+    ///
+    /// When `identify` names a unit, its device identification is read right after connecting
+    /// and cached, see [`Client::device_info`].
+    pub async fn connect_tcp(addr: impl ToSocketAddrs, identify: Option<u8>) -> Result<Self> {
+        let mut client = Client::tcp(TcpStream::connect(addr).await?);
+        if let Some(slave) = identify {
+            client.read_device_info(slave).await?;
+        }
+        Ok(client)
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> Client<Framed<S, TcpClientCodec>> {
+    /// Create a Modbus TCP client over a connected stream
+    pub fn tcp(stream: S) -> Self {
+        Client::new(Framed::new(stream, TcpClientCodec::default()), Frame::tcp())
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> Client<Framed<S, RtuClientCodec>> {
+    /// Create a Modbus RTU client over a serial port
+    pub fn rtu(port: S) -> Self {
+        let mut client = Client::new(Framed::new(port, RtuClientCodec::default()), Frame::rtu());
+        client.pending_bytes = |transport| transport.read_buffer().len();
+        client.discard_pending = |transport| transport.read_buffer_mut().clear();
+        client
+    }
+
+    /// Set the longest stall allowed between bytes of a response
+    ///
+    /// A response whose first bytes arrived but whose rest does not follow within `timeout`
+    /// fails with [`ModbusError::FrameAssemblyTimeout`], independently of how long the device
+    /// may take to start answering. The partial response is then discarded, so that it is not
+    /// taken for the start of the next one.
+    pub fn set_assembly_timeout(&mut self, timeout: Duration) {
+        self.assembly_timeout = Some(timeout);
+    }
+
+    /// Discard the bytes received but not decoded yet, e.g. line noise or an abandoned response
+    ///
+    /// Drops the partial frame buffered by the transport along with whatever the port already
+    /// holds, without waiting for more. Call it before a request on a noisy line so the next
+    /// response is not decoded from leftover bytes. Returns the number of bytes discarded.
+    pub fn flush_input(&mut self) -> Result<usize> {
+        let mut discarded = self.transport.read_buffer().len();
+        self.transport.read_buffer_mut().clear();
+
+        let mut buf = [0; 256];
+        while let Some(read) = self.transport.get_mut().read(&mut buf).now_or_never() {
+            match read? {
+                0 => break,
+                n => discarded += n,
+            }
+        }
+        Ok(discarded)
+    }
+}
+
+impl<T: Transport> Client<T> {
+    /// Create a client over any transport
+    ///
+    /// `frame` must match the protocol version spoken over `transport`.
+    pub fn new(transport: T, frame: Frame) -> Self {
+        Client {
+            transport,
+            frame,
+            min_request_gap: None,
+            last_response: None,
+            rate_limit: None,
+            next_send: None,
+            assembly_timeout: None,
+            pending_bytes: |_| 0,
+            discard_pending: |_| {},
+            device_info: None,
+            last_error: None,
+            retry_policy: None,
+            next_tid: 1,
+            timeout: None,
+            exchange_pending: false,
+            recv_failed: false,
+        }
+    }
+
+    /// Set the minimum delay between a response and the next request
+    ///
+    /// Slow RTU devices may miss a request sent right after their previous response. When set,
+    /// [`Client::call`] waits until the gap has elapsed before sending.
+    pub fn set_min_request_gap(&mut self, gap: Duration) {
+        self.min_request_gap = Some(gap);
+    }
+
+    /// Limit the average rate of requests to `requests_per_second`
+    ///
+    /// Unlike the minimum request gap, which runs from each response, the limit spaces the
+    /// requests themselves: a token bucket holding a single request refills at the given rate,
+    /// and [`Client::call`] waits for it before sending.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `requests_per_second` is not positive, or so small that the interval between
+    /// requests cannot be represented as a [`Duration`].
+    pub fn set_rate_limit(&mut self, requests_per_second: f64) {
+        assert!(requests_per_second > 0.0, "the rate limit must be positive");
+        let interval = Duration::try_from_secs_f64(1.0 / requests_per_second)
+            .expect("the rate limit is too small");
+        self.rate_limit = Some(interval);
+    }
+
+    /// Fail calls whose response does not arrive within `timeout` of sending the request
+    ///
+    /// Such calls fail with [`ModbusError::Timeout`] instead of waiting forever for a silent
+    /// device. No timeout by default.
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = Some(timeout);
+    }
+
+    /// Retry calls failing to get a response, as configured by `policy`
+    ///
+    /// Only transient errors, see [`ModbusError::is_transient`], are retried after the delays
+    /// of the policy. The last error is returned once the attempts are exhausted. Exception
+    /// responses are answers and are never retried.
+    pub fn set_retry_policy(&mut self, policy: RetryPolicy) {
+        self.retry_policy = Some(policy);
+    }
+
+    /// Retry calls failing to get a response, see [`Client::set_retry_policy`]
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use std::time::Duration;
+    ///
+    /// use easy_modbus::client::{Client, RetryPolicy};
+    ///
+    /// # fn wrap(port: tokio_serial::SerialStream) {
+    /// let client = Client::rtu(port).with_retry(RetryPolicy::new(3, Duration::from_millis(50)));
+    /// # }
+    /// ```
+    pub fn with_retry(mut self, policy: RetryPolicy) -> Self {
+        self.set_retry_policy(policy);
+        self
+    }
+
+    /// Get the frame building requests for this client, matching its protocol version
+    pub fn frame(&self) -> &Frame {
+        &self.frame
+    }
+
+    /// Get the transaction id the next TCP request will carry
+    ///
+    /// The client numbers its TCP requests itself, replacing the transaction id given by the
+    /// frame, and only accepts the response carrying the id of the request awaiting it.
+    pub fn next_transaction_id(&self) -> u16 {
+        self.next_tid
+    }
+
+    /// Get the error of the last operation, `None` if it succeeded
+    ///
+    /// Every method sending requests sets it when failing and clears it when succeeding.
+    pub fn last_error(&self) -> Option<&ModbusError> {
+        self.last_error.as_ref()
+    }
+
+    /// Send a request and wait for its response
+    ///
+    /// Broadcast requests get no response and fail without being sent, see
+    /// [`Client::broadcast`].
+    pub async fn call(&mut self, request: Request) -> Result<Response> {
+        if request.is_broadcast() {
+            return Err(self.fail(ModbusError::InvalidFrame(
+                "Broadcast requests get no response, send them with Client::broadcast".to_string(),
+            )));
+        }
+        let delays: Vec<Duration> = match &mut self.retry_policy {
+            Some(policy) => policy.delays().collect(),
+            None => Vec::new(),
+        };
+        let mut delays = delays.into_iter();
+        let result = loop {
+            let result = self.exchange(request.clone()).await;
+            match (&result, delays.next()) {
+                (Err(e), Some(delay)) if e.is_transient() => time::sleep(delay).await,
+                _ => break result,
+            }
+        };
+        match &result {
+            Ok(_) => self.last_error = None,
+            Err(e) => self.last_error = Some(e.duplicate()),
+        }
+        result
+    }
+
+    /// Send an RTU write to unit 0, returning as soon as it is written
+    ///
+    /// Every slave applies a broadcast write and none answers, so no response is awaited. The
+    /// minimum request gap still runs from the end of the write, giving slaves time to process
+    /// it. Requests other than broadcasts fail without being sent.
+    pub async fn broadcast(&mut self, request: Request) -> Result<()> {
+        if !request.is_broadcast() {
+            return Err(self.fail(ModbusError::InvalidFrame(format!(
+                "{} to unit {} is not a broadcast",
+                request.head().function.name(),
+                request.head().uid
+            ))));
+        }
+        self.pace().await;
+        match self.transport.send(request).await {
+            Ok(()) => {
+                self.last_response = Some(Instant::now());
+                self.last_error = None;
+                Ok(())
+            }
+            Err(e) => Err(self.fail(e)),
+        }
+    }
+
+    /// Remember `e` as the error of the current operation
+    fn fail(&mut self, e: ModbusError) -> ModbusError {
+        self.last_error = Some(e.duplicate());
+        e
+    }
+
+    /// Wait until the minimum request gap and the rate limit allow the next request
+    async fn pace(&mut self) {
+        if let (Some(gap), Some(last)) = (self.min_request_gap, self.last_response) {
+            time::sleep_until(last + gap).await;
+        }
+        if let Some(interval) = self.rate_limit {
+            if let Some(next) = self.next_send {
+                time::sleep_until(next).await;
+            }
+            self.next_send = Some(Instant::now() + interval);
+        }
+    }
+
+    /// Send a request and wait for its response, leaving the last error alone
+    ///
+    /// A TCP response to an earlier request, e.g. one abandoned after a timeout, is discarded.
+    /// One with a transaction id not sent yet fails with [`ModbusError::TransactionIdMismatch`].
+    async fn exchange(&mut self, mut request: Request) -> Result<Response> {
+        let tid = match request.get_head().version {
+            Version::Tcp => {
+                let tid = self.next_tid;
+                self.next_tid = self.next_tid.wrapping_add(1);
+                let mut head = request.head();
+                head.tid = tid;
+                request.set_head(head);
+                Some(tid)
+            }
+            Version::Rtu => None,
+        };
+
+        self.pace().await;
+        self.exchange_pending = true;
+        self.transport.send(request).await?;
+        let response = match self.timeout {
+            Some(timeout) => time::timeout(timeout, self.await_response(tid))
+                .await
+                .map_err(|_| ModbusError::Timeout)??,
+            None => self.await_response(tid).await?,
+        };
+        self.exchange_pending = false;
+        self.last_response = Some(Instant::now());
+        Ok(response)
+    }
+
+    /// Wait for the response carrying `tid`, or the next one if `None`
+    async fn await_response(&mut self, tid: Option<u16>) -> Result<Response> {
+        loop {
+            let response = self.receive().await?;
+            let actual = response.get_head().tid;
+            match tid {
+                Some(expected) if actual != expected => {
+                    if !(1..0x8000).contains(&expected.wrapping_sub(actual)) {
+                        return Err(ModbusError::TransactionIdMismatch { expected, actual });
+                    }
+                }
+                _ => return Ok(response),
+            }
+        }
+    }
+
+    /// Wait for the next response, failing if a partial frame stalls
+    async fn receive(&mut self) -> Result<Response> {
+        let timeout = match self.assembly_timeout {
+            Some(timeout) => timeout,
+            None => return self.recv().await,
+        };
+
+        loop {
+            let before = (self.pending_bytes)(&self.transport);
+            match time::timeout(timeout, self.recv()).await {
+                Ok(response) => return response,
+                Err(_) => {
+                    let after = (self.pending_bytes)(&self.transport);
+                    if after > 0 && after == before {
+                        (self.discard_pending)(&mut self.transport);
+                        return Err(ModbusError::FrameAssemblyTimeout);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Wait for the next response of the transport
+    ///
+    /// A [`Framed`] transport ends its stream once right after a frame failed to decode, e.g.
+    /// with a CRC mismatch, then resumes with the next frame. That end is skipped instead of
+    /// taken for the transport closing.
+    async fn recv(&mut self) -> Result<Response> {
+        loop {
+            let result = self.transport.recv().await;
+            let closed =
+                matches!(&result, Err(ModbusError::Io(e)) if e.kind() == ErrorKind::UnexpectedEof);
+            if closed && self.recv_failed {
+                self.recv_failed = false;
+                continue;
+            }
+            self.recv_failed = result.is_err();
+            return result;
+        }
+    }
+
+    /// Read coils (Function Code: 0x01)
+    ///
+    /// An exception response fails with [`ModbusError::Exception`].
+    ///
+    /// * `slave` - Server address
+    /// * `address` - Address of first coil to read
+    /// * `number` - Number of coils to read
+    pub async fn read_coils(&mut self, slave: u8, address: u16, number: u16) -> Result<Vec<bool>> {
+        let request = self.frame.read_coils_request(slave, address, number);
+        let body = match self.call(request).await? {
+            Response::ReadCoils(_, body) => body,
+            response => return Err(self.fail(unexpected(response))),
+        };
+        if body.values.len() * 8 < number as usize {
+            return Err(self.fail(ModbusError::InvalidFrame(format!(
+                "Read coils response carries {} bytes for {} coils",
+                body.values.len(),
+                number
+            ))));
+        }
+        Ok(body.get_coils(number))
+    }
+
+    /// Read holding registers (Function Code: 0x03)
+    ///
+    /// An exception response fails with [`ModbusError::Exception`].
+    ///
+    /// * `slave` - Server address
+    /// * `address` - Address of first register to read
+    /// * `number` - Number of registers to read
+    pub async fn read_holding_registers(
+        &mut self,
+        slave: u8,
+        address: u16,
+        number: u16,
+    ) -> Result<Vec<u16>> {
+        let request = self
+            .frame
+            .read_multiple_holding_registers_request(slave, address, number);
+        let registers = match self.call(request).await? {
+            Response::ReadMultipleHoldingRegisters(_, body) => body.get_registers(),
+            response => return Err(self.fail(unexpected(response))),
+        };
+        if registers.len() < number as usize {
+            return Err(self.fail(ModbusError::InvalidFrame(format!(
+                "Read holding registers response carries {} registers for {}",
+                registers.len(),
+                number
+            ))));
+        }
+        Ok(registers)
+    }
+
+    /// Read any number of coils (Function Code: 0x01)
+    ///
+    /// Issues as few reads of at most 2000 coils as needed and concatenates their states.
+    ///
+    /// * `slave` - Server address
+    /// * `address` - Address of first coil to read
+    /// * `total` - Number of coils to read
+    pub async fn read_coils_chunked(
+        &mut self,
+        slave: u8,
+        address: u16,
+        total: u16,
+    ) -> Result<Vec<bool>> {
+        if address as u32 + total as u32 > 0x10000 {
+            return Err(self.fail(ModbusError::InvalidFrame(format!(
+                "{} coils from 0x{:0>4X} exceed the address space",
+                total, address
+            ))));
+        }
+
+        let mut coils = Vec::with_capacity(total as usize);
+        let mut address = address;
+        while coils.len() < total as usize {
+            let number = (total - coils.len() as u16).min(MAX_READ_COILS);
+            let request = self.frame.read_coils_request(slave, address, number);
+            let values = match self.call(request).await? {
+                Response::ReadCoils(_, body) => body.values,
+                response => return Err(self.fail(unexpected(response))),
+            };
+            if values.len() * 8 < number as usize {
+                return Err(self.fail(ModbusError::InvalidFrame(format!(
+                    "Read coils response carries {} bytes for {} coils",
+                    values.len(),
+                    number
+                ))));
+            }
+            coils.extend((0..number as usize).map(|i| values[i / 8] >> (i % 8) & 0x01 == 0x01));
+            address = address.wrapping_add(number);
+        }
+        Ok(coils)
+    }
+
+    /// Write coils (Function Code: 0x0F) and read them back (Function Code: 0x01) to verify
+    ///
+    /// For critical outputs, catches a device acknowledging a write it did not fully apply.
+    /// Fails with [`ModbusError::VerifyMismatch`] listing the addresses of the coils read back
+    /// in another state than written. A write to unit 0 over RTU is broadcast and goes
+    /// unverified, as no slave answers the read back.
+    ///
+    /// * `slave` - Server address
+    /// * `address` - Address of the first coil
+    /// * `coils` - New states of the coils
+    pub async fn write_then_verify_coils(
+        &mut self,
+        slave: u8,
+        address: u16,
+        coils: &[bool],
+    ) -> Result<()> {
+        let mut values = vec![0; coils.len().div_ceil(8)];
+        for (i, _) in coils.iter().enumerate().filter(|(_, &coil)| coil) {
+            values[i / 8] |= 0x01 << (i % 8);
+        }
+        let request =
+            self.frame
+                .write_multiple_coils_request(slave, address, coils.len() as u16, values);
+        if request.is_broadcast() {
+            return self.broadcast(request).await;
+        }
+        match self.call(request).await? {
+            Response::WriteMultipleCoils(_, _) => {}
+            response => return Err(self.fail(unexpected(response))),
+        }
+
+        let read = self
+            .read_coils_chunked(slave, address, coils.len() as u16)
+            .await?;
+        let mismatches: Vec<u16> = (0..coils.len())
+            .filter(|&i| read[i] != coils[i])
+            .map(|i| address.wrapping_add(i as u16))
+            .collect();
+        if !mismatches.is_empty() {
+            return Err(self.fail(ModbusError::VerifyMismatch(mismatches)));
+        }
+        Ok(())
+    }
+
+    /// Write holding registers (Function Code: 0x10)
+    ///
+    /// Returns the number of registers the device acknowledged writing, which a misbehaving
+    /// device may report below `values.len()` after a partial write. A write to unit 0 over RTU
+    /// is broadcast and, with no acknowledgement to go by, reports `values.len()`.
+    ///
+    /// * `slave` - Server address
+    /// * `address` - Address of the first holding register
+    /// * `values` - New values of the holding registers
+    pub async fn write_multiple_registers(
+        &mut self,
+        slave: u8,
+        address: u16,
+        values: &[u16],
+    ) -> Result<u16> {
+        let request = self.frame.write_multiple_holding_registers_request_iter(
+            slave,
+            address,
+            values.iter().copied(),
+        );
+        if request.is_broadcast() {
+            self.broadcast(request).await?;
+            return Ok(values.len() as u16);
+        }
+        match self.call(request).await? {
+            Response::WriteMultipleHoldingRegisters(_, body) => Ok(body.registers_number),
+            response => Err(self.fail(unexpected(response))),
+        }
+    }
+
+    /// Set and clear bits of a holding register in one mask write (Function Code: 0x16)
+    ///
+    /// The server modifies the register itself, so bits outside `set` and `clear` are preserved
+    /// without the race of a read followed by a write. Bits in both `set` and `clear` end up set.
+    /// A write to unit 0 over RTU is broadcast to every slave.
+    ///
+    /// * `slave` - Server address
+    /// * `address` - Address of the holding register
+    /// * `set` - Bits to set
+    /// * `clear` - Bits to clear
+    pub async fn update_register_bits(
+        &mut self,
+        slave: u8,
+        address: u16,
+        set: u16,
+        clear: u16,
+    ) -> Result<()> {
+        // The server computes (current & and_mask) | (or_mask & !and_mask), so the bits to set
+        // must be left out of the AND mask too
+        let and_mask = !(set | clear);
+        let request = self
+            .frame
+            .mask_write_register_request(slave, address, and_mask, set);
+        if request.is_broadcast() {
+            return self.broadcast(request).await;
+        }
+        match self.call(request).await? {
+            Response::MaskWriteRegister(_, _) => Ok(()),
+            response => Err(self.fail(unexpected(response))),
+        }
+    }
+
+    /// Find the units answering on the bus
+    ///
+    /// Sends Report Server ID (0x11) to each unit id of `range`, one after the other, and
+    /// collects the ids answered within `timeout`. An exception response counts as an answer,
+    /// except a gateway reporting that its target device failed to respond.
+    pub async fn scan_bus(&mut self, range: RangeInclusive<u8>, timeout: Duration) -> Vec<u8> {
+        let mut found = Vec::new();
+        for slave in range {
+            let request = self.frame.report_server_id_request(slave);
+            let response = match time::timeout(timeout, self.call(request)).await {
+                Ok(Ok(response)) => response,
+                _ => continue,
+            };
+            let answered = match &response {
+                Response::Exception(_, body) => !matches!(
+                    body.get_exception(),
+                    Exception::GatewayTargetDeviceFailedToRespond
+                ),
+                _ => true,
+            };
+            // A late answer of a previous unit does not reveal this one
+            if answered && response.head().uid() == slave {
+                found.push(slave);
+            }
+        }
+        found
+    }
+
+    /// Check that a server is alive and how busy it has been
+    ///
+    /// Issues Report Server ID (0x11) followed by Get Comm Event Counter (0x0B).
+    pub async fn health_check(&mut self, slave: u8) -> Result<HealthReport> {
+        let request = self.frame.report_server_id_request(slave);
+        let (server_id, run_status) = match self.call(request).await? {
+            Response::ReportServerId(_, body) => {
+                (body.get_server_id().clone(), body.get_run_status())
+            }
+            response => return Err(self.fail(unexpected(response))),
+        };
+
+        let request = self.frame.get_comm_event_counter_request(slave);
+        let (busy, event_count) = match self.call(request).await? {
+            Response::GetCommEventCounter(_, body) => (body.is_busy(), *body.get_event_count()),
+            response => return Err(self.fail(unexpected(response))),
+        };
+
+        Ok(HealthReport {
+            server_id,
+            run_status,
+            busy,
+            event_count,
+        })
+    }
+
+    /// Check the link to a server with a Return Query Data diagnostic (0x08 / 0x0000)
+    ///
+    /// Returns whether the server echoed `data` unchanged.
+    pub async fn loopback_test(&mut self, slave: u8, data: u16) -> Result<bool> {
+        let sub_function = DiagnosticsSubFunction::ReturnQueryData.to_code();
+        let request = self.frame.diagnostics_request(slave, sub_function, data);
+        match self.call(request).await? {
+            Response::Diagnostics(_, body) => {
+                Ok(*body.get_sub_function() == sub_function && *body.get_data() == data)
+            }
+            response => Err(self.fail(unexpected(response))),
+        }
+    }
+
+    /// Read the basic device identification (0x2B / 0x0E) and cache it
+    ///
+    /// Follows the continuation when the objects do not fit in one response.
+    pub async fn read_device_info(&mut self, slave: u8) -> Result<&DeviceInfo> {
+        self.read_device_info_with_progress(slave, |_| {}).await
+    }
+
+    /// Read the basic device identification like [`Client::read_device_info`], reporting progress
+    ///
+    /// `progress` is called with the number of responses parsed so far after each one, e.g. to
+    /// drive a progress bar while a slow device answers over several transactions.
+    pub async fn read_device_info_with_progress(
+        &mut self,
+        slave: u8,
+        mut progress: impl FnMut(usize),
+    ) -> Result<&DeviceInfo> {
+        let mut objects = BTreeMap::new();
+        let mut object_id = 0x00;
+        let mut parsed = 0;
+        loop {
+            let request = self
+                .frame
+                .read_device_identification_request(slave, 0x01, object_id);
+            let body = match self.call(request).await? {
+                Response::ReadDeviceIdentification(_, body) => body,
+                response => return Err(self.fail(unexpected(response))),
+            };
+            objects.extend(body.objects);
+            parsed += 1;
+            progress(parsed);
+            if !body.more_follows {
+                break;
+            }
+            if body.next_object_id <= object_id {
+                return Err(self.fail(ModbusError::InvalidFrame(format!(
+                    "Device identification continues at object 0x{:0>2X} after 0x{:0>2X}",
+                    body.next_object_id, object_id
+                ))));
+            }
+            object_id = body.next_object_id;
+        }
+
+        let mut object = |id| {
+            objects
+                .remove(&id)
+                .map(|value| String::from_utf8_lossy(&value).into_owned())
+                .unwrap_or_default()
+        };
+        let device_info = DeviceInfo {
+            vendor_name: object(0x00),
+            product_code: object(0x01),
+            revision: object(0x02),
+        };
+        Ok(self.device_info.insert(device_info))
+    }
+
+    /// Get the device identification cached by the last [`Client::read_device_info`]
+    pub fn device_info(&self) -> Option<&DeviceInfo> {
+        self.device_info.as_ref()
+    }
+
+    /// Read records of files (Function Code: 0x14)
+    ///
+    /// Returns the registers of each record, in the order of `records`. As many records are
+    /// read per request as fit in a frame.
+    ///
+    /// * `slave` - Server address
+    /// * `records` - Records to read
+    pub async fn read_file_records(
+        &mut self,
+        slave: u8,
+        records: &[FileRecord],
+    ) -> Result<Vec<Vec<u16>>> {
+        self.read_file_records_with_progress(slave, records, |_| {})
+            .await
+    }
+
+    /// Read records of files like [`Client::read_file_records`], reporting progress
+    ///
+    /// `progress` is called with the number of records parsed so far after each sub-response,
+    /// e.g. to drive a progress bar while a large file is read.
+    pub async fn read_file_records_with_progress(
+        &mut self,
+        slave: u8,
+        records: &[FileRecord],
+        mut progress: impl FnMut(usize),
+    ) -> Result<Vec<Vec<u16>>> {
+        let mut values = Vec::with_capacity(records.len());
+        for batch in file_record_batches(records) {
+            let request = self.frame.read_file_record_request(slave, batch.to_vec());
+            let body = match self.call(request).await? {
+                Response::ReadFileRecord(_, body) => body,
+                response => return Err(self.fail(unexpected(response))),
+            };
+            if body.records.len() != batch.len() {
+                return Err(self.fail(ModbusError::InvalidFrame(format!(
+                    "{} file records answered out of {}",
+                    body.records.len(),
+                    batch.len()
+                ))));
+            }
+            for (record, registers) in batch.iter().zip(body.records) {
+                if registers.len() != record.record_length as usize {
+                    return Err(self.fail(ModbusError::InvalidFrame(format!(
+                        "File record of {} registers, expect {}",
+                        registers.len(),
+                        record.record_length
+                    ))));
+                }
+                values.push(registers);
+                progress(values.len());
+            }
+        }
+        Ok(values)
+    }
+}
+
+/// Modbus TCP client connected to a single server
+pub type TcpClient = Client<Framed<TcpStream, TcpClientCodec>>;
+
+/// Pool of connections to one Modbus TCP server
+///
+/// Each connection carries one transaction at a time, so tasks holding different connections
+/// talk to the server concurrently. Connections are opened on demand, up to the pool size, and
+/// kept for reuse once released. A connection closed by the server is replaced when acquired.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use easy_modbus::client::TcpClientPool;
+/// use easy_modbus::Frame;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let pool = TcpClientPool::new("127.0.0.1:502", 4);
+///
+///     let mut client = pool.acquire().await?;
+///     let request = Frame::tcp().read_multiple_holding_registers_request(0x01, 0x00, 0x02);
+///     let response = client.call(request).await?;
+///     println!("Response:\t{}", response);
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct TcpClientPool {
+    /// Address of the server
+    addr: String,
+
+    /// One permit per connection that may be in use
+    permits: Semaphore,
+
+    /// Released connections
+    idle: Mutex<Vec<TcpClient>>,
+}
+
+impl TcpClientPool {
+    /// Create a pool of at most `size` connections to `addr`
+    ///
+    /// No connection is opened until one is acquired.
+    pub fn new(addr: impl Into<String>, size: usize) -> Self {
+        TcpClientPool {
+            addr: addr.into(),
+            permits: Semaphore::new(size),
+            idle: Mutex::new(Vec::with_capacity(size)),
+        }
+    }
+
+    /// Wait for a free connection
+    ///
+    /// Hands out a released connection when one is still alive, otherwise connects anew. The
+    /// connection returns to the pool when the [`PooledClient`] is dropped.
+    pub async fn acquire(&self) -> Result<PooledClient<'_>> {
+        let permit = self
+            .permits
+            .acquire()
+            .await
+            .expect("the pool semaphore is never closed");
+
+        let client = loop {
+            let client = self.idle.lock().unwrap().pop();
+            match client {
+                Some(client) if is_alive(&client) => break client,
+                Some(_) => continue,
+                None => break Client::tcp(TcpStream::connect(self.addr.as_str()).await?),
+            }
+        };
+
+        Ok(PooledClient {
+            pool: self,
+            client: Some(client),
+            _permit: permit,
+        })
+    }
+
+    /// Number of released connections kept for reuse
+    pub fn idle_connections(&self) -> usize {
+        self.idle.lock().unwrap().len()
+    }
+}
+
+/// Check that a released connection is neither closed nor carrying stray bytes
+fn is_alive(client: &TcpClient) -> bool {
+    if !client.transport.read_buffer().is_empty() {
+        return false;
+    }
+    let mut buf = [0; 1];
+    matches!(
+        client.transport.get_ref().try_read(&mut buf),
+        Err(e) if e.kind() == ErrorKind::WouldBlock
+    )
+}
+
+/// Connection borrowed from a [`TcpClientPool`], returned to it on drop
+///
+/// A connection whose last call failed or was cancelled is closed instead, as a response to it
+/// may still arrive and be taken for the answer to the next borrower's request.
+#[derive(Debug)]
+pub struct PooledClient<'a> {
+    pool: &'a TcpClientPool,
+    client: Option<TcpClient>,
+    _permit: SemaphorePermit<'a>,
+}
+
+impl Deref for PooledClient<'_> {
+    type Target = TcpClient;
+
+    fn deref(&self) -> &TcpClient {
+        self.client.as_ref().unwrap()
+    }
+}
+
+impl DerefMut for PooledClient<'_> {
+    fn deref_mut(&mut self) -> &mut TcpClient {
+        self.client.as_mut().unwrap()
+    }
+}
+
+impl Drop for PooledClient<'_> {
+    fn drop(&mut self) {
+        if let Some(client) = self.client.take() {
+            if client.last_error.is_none() && !client.exchange_pending {
+                self.pool.idle.lock().unwrap().push(client);
+            }
+        }
+    }
+}
+
+/// Modbus TCP client keeping several requests in flight on one connection
+///
+/// Requests get their own transaction ids and responses are matched back by them, so they may
+/// arrive in any order. Cloning the client shares the connection.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use tokio::net::TcpStream;
+///
+/// use easy_modbus::client::PipelinedClientBuilder;
+/// use easy_modbus::Frame;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let stream = TcpStream::connect("127.0.0.1:502").await?;
+///     let client = PipelinedClientBuilder::new().max_in_flight(4).tcp(stream);
+///
+///     let frame = Frame::tcp();
+///     let coils = client.call(frame.read_coils_request(0x01, 0x00, 0x08));
+///     let registers = client.call(frame.read_multiple_holding_registers_request(0x01, 0x00, 0x02));
+///     let (coils, registers) = futures::join!(coils, registers);
+///     println!("{} / {}", coils?, registers?);
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct PipelinedClient {
+    /// Feeds the task writing requests
+    requests: mpsc::UnboundedSender<(Request, Waiter)>,
+
+    /// One permit per request that may be in flight
+    permits: Arc<Semaphore>,
+}
+
+/// Builder of a [`PipelinedClient`]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PipelinedClientBuilder {
+    max_in_flight: usize,
+}
+
+/// Requests sent and waiting for their response, by transaction id
+#[derive(Debug, Default)]
+struct InFlight {
+    /// Whether the connection is gone
+    closed: bool,
+
+    /// Where to deliver each response
+    waiting: HashMap<u16, Waiter>,
+}
+
+/// Where to deliver a response, holding the in-flight slot of the request until then
+type Waiter = (oneshot::Sender<Result<Response>>, OwnedSemaphorePermit);
+
+impl Default for PipelinedClientBuilder {
+    fn default() -> Self {
+        PipelinedClientBuilder::new()
+    }
+}
+
+impl PipelinedClientBuilder {
+    /// Start building a client keeping up to 8 requests in flight
+    pub fn new() -> Self {
+        PipelinedClientBuilder {
+            max_in_flight: DEFAULT_MAX_IN_FLIGHT,
+        }
+    }
+
+    /// Set how many requests may await their response at once
+    ///
+    /// Further requests wait for one of them to complete before being sent, bounding memory
+    /// and matching the queue depth of the device.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_in_flight` is 0, as no request could ever be sent.
+    pub fn max_in_flight(mut self, max_in_flight: usize) -> Self {
+        assert!(
+            max_in_flight > 0,
+            "at least one request must be allowed in flight"
+        );
+        self.max_in_flight = max_in_flight;
+        self
+    }
+
+    /// Create the client over a connected stream
+    ///
+    /// Spawns the tasks driving the connection on the current Tokio runtime.
+    pub fn tcp<S>(self, stream: S) -> PipelinedClient
+    where
+        S: AsyncRead + AsyncWrite + Send + 'static,
+    {
+        let (reader, mut writer) = tokio::io::split(stream);
+        let mut stream = FramedRead::new(reader, TcpClientCodec::default());
+        let (requests, mut queue) = mpsc::unbounded_channel::<(Request, Waiter)>();
+        let in_flight = Arc::new(Mutex::new(InFlight::default()));
+
+        let waiting = in_flight.clone();
+        tokio::spawn(async move {
+            let mut tid: u16 = 0;
+            while let Some(first) = queue.recv().await {
+                // Queue whatever else is ready, to send the lot in one write
+                let mut batch = vec![first];
+                while let Ok(next) = queue.try_recv() {
+                    batch.push(next);
+                }
+                let mut sent = Vec::with_capacity(batch.len());
+                for (mut request, waiter) in batch {
+                    if let Err(e) = check_pdu_len(&request.head()) {
+                        let _ = waiter.0.send(Err(e));
+                        continue;
+                    }
+
+                    tid = tid.wrapping_add(1);
+                    let mut head = request.head();
+                    head.tid = tid;
+                    request.set_head(head);
+
+                    if let Err(waiter) = register(&waiting, tid, waiter) {
+                        let _ = waiter.0.send(Err(closed()));
+                        continue;
+                    }
+                    sent.push(request);
+                }
+                if sent.is_empty() {
+                    continue;
+                }
+
+                let mut dst = BytesMut::new();
+                Request::encode_batch(&sent, &mut dst);
+                trace_frame("Sent", &[&dst]);
+                let written = match writer.write_all(&dst).await {
+                    Ok(()) => writer.flush().await,
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = written {
+                    fail_all(&waiting, e.into());
+                }
+            }
+        });
+
+        tokio::spawn(async move {
+            let error = loop {
+                match stream.next().await {
+                    Some(Ok(response)) => {
+                        let tid = response.head().tid;
+                        // A response to no pending request is dropped
+                        if let Some((reply, _permit)) =
+                            in_flight.lock().unwrap().waiting.remove(&tid)
+                        {
+                            let _ = reply.send(Ok(response));
+                        }
+                    }
+                    Some(Err(e)) => break e,
+                    None => break closed(),
+                }
+            };
+            fail_all(&in_flight, error);
+        });
+
+        PipelinedClient {
+            requests,
+            permits: Arc::new(Semaphore::new(self.max_in_flight)),
+        }
+    }
+}
+
+impl PipelinedClient {
+    /// Send a request and wait for its response
+    ///
+    /// Waits first for a free slot when the most requests allowed are in flight. A call dropped
+    /// before its response keeps the slot until the response arrives or the connection is gone,
+    /// so the device never sees more requests than allowed.
+    pub async fn call(&self, request: Request) -> Result<Response> {
+        let permit = self
+            .permits
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("the in-flight semaphore is never closed");
+        let (reply, response) = oneshot::channel();
+        self.requests
+            .send((request, (reply, permit)))
+            .map_err(|_| closed())?;
+        response.await.map_err(|_| closed())?
+    }
+}
+
+/// Error of a request whose connection is gone
+fn closed() -> ModbusError {
+    io::Error::new(
+        ErrorKind::UnexpectedEof,
+        "Connection closed before a response was received",
+    )
+    .into()
+}
+
+/// Await the response to `tid`, handing `waiter` back when the connection is gone
+fn register(
+    in_flight: &Mutex<InFlight>,
+    tid: u16,
+    waiter: Waiter,
+) -> std::result::Result<(), Waiter> {
+    let mut in_flight = in_flight.lock().unwrap();
+    if in_flight.closed {
+        return Err(waiter);
+    }
+    in_flight.waiting.insert(tid, waiter);
+    Ok(())
+}
+
+/// Fail every request in flight with `error` and refuse further ones
+fn fail_all(in_flight: &Mutex<InFlight>, error: ModbusError) {
+    let mut in_flight = in_flight.lock().unwrap();
+    in_flight.closed = true;
+    for (_, (reply, _permit)) in in_flight.waiting.drain() {
+        let _ = reply.send(Err(error.duplicate()));
+    }
+}
+
+/// Modbus RTU client of slaves spread over several serial ports
+///
+/// Each port carries one transaction at a time, while requests to slaves on different ports
+/// proceed concurrently. Requests are routed by their unit id to the port the slave was added
+/// with.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use tokio_serial::SerialStream;
+///
+/// use easy_modbus::client::{Client, MultiPortRtuClient};
+/// use easy_modbus::Frame;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let mut client = MultiPortRtuClient::new();
+///     let port = SerialStream::open(&tokio_serial::new("/dev/ttyUSB0", 9600))?;
+///     client.add_port("/dev/ttyUSB0", Client::rtu(port), [0x01, 0x02]);
+///     let port = SerialStream::open(&tokio_serial::new("/dev/ttyUSB1", 9600))?;
+///     client.add_port("/dev/ttyUSB1", Client::rtu(port), [0x03]);
+///
+///     let frame = Frame::rtu();
+///     let (first, second) = tokio::join!(
+///         client.call(frame.read_multiple_holding_registers_request(0x01, 0x00, 0x02)),
+///         client.call(frame.read_multiple_holding_registers_request(0x03, 0x00, 0x02)),
+///     );
+///     println!("Responses:\t{}\t{}", first?, second?);
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct MultiPortRtuClient<T> {
+    /// Client of each port, by port name
+    ports: HashMap<String, AsyncMutex<Client<T>>>,
+
+    /// Port name of each slave
+    routes: HashMap<u8, String>,
+}
+
+impl<T> Default for MultiPortRtuClient<T> {
+    fn default() -> Self {
+        MultiPortRtuClient {
+            ports: HashMap::new(),
+            routes: HashMap::new(),
+        }
+    }
+}
+
+impl<T: Transport> MultiPortRtuClient<T> {
+    /// Create a client without ports
+    pub fn new() -> Self {
+        MultiPortRtuClient::default()
+    }
+
+    /// Add the client of a port and the slaves it reaches
+    ///
+    /// A slave already reached through another port is moved to this one. Adding a port again
+    /// replaces its client.
+    pub fn add_port(
+        &mut self,
+        port: impl Into<String>,
+        client: Client<T>,
+        slaves: impl IntoIterator<Item = u8>,
+    ) {
+        let port = port.into();
+        for slave in slaves {
+            self.routes.insert(slave, port.clone());
+        }
+        self.ports.insert(port, AsyncMutex::new(client));
+    }
+
+    /// Get the name of the port reaching `slave`
+    pub fn port_of(&self, slave: u8) -> Option<&str> {
+        self.routes.get(&slave).map(String::as_str)
+    }
+
+    /// Send a request through the port of its slave and wait for its response
+    ///
+    /// Waits first for the requests already sent through that port. Fails without sending for
+    /// a slave no port reaches.
+    pub async fn call(&self, request: Request) -> Result<Response> {
+        let slave = request.get_head().uid();
+        let port = self
+            .routes
+            .get(&slave)
+            .and_then(|port| self.ports.get(port))
+            .ok_or_else(|| ModbusError::InvalidFrame(format!("No port reaches unit {}", slave)))?;
+        port.lock().await.call(request).await
+    }
+}
+
+/// Moving average of input registers read over and over, e.g. to smooth noisy analog inputs
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use easy_modbus::client::{Client, SmoothedReader};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let mut client = Client::connect_tcp("127.0.0.1:502", None).await?;
+///     let mut reader = SmoothedReader::new(0x01, 0x0000, 4, 10);
+///     loop {
+///         let averages = reader.read(&mut client).await?;
+///         println!("{:?}", averages);
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SmoothedReader {
+    /// Server address
+    slave: u8,
+
+    /// Address of the first input register
+    address: u16,
+
+    /// Number of input registers, one channel each
+    number: u16,
+
+    /// Number of reads averaged
+    window: usize,
+
+    /// Last values of each channel, oldest first
+    history: Vec<VecDeque<u16>>,
+}
+
+impl SmoothedReader {
+    /// Average the last `window` reads of `number` input registers starting at `address`
+    ///
+    /// A window of 0 is taken as 1, averaging nothing.
+    pub fn new(slave: u8, address: u16, number: u16, window: usize) -> Self {
+        SmoothedReader {
+            slave,
+            address,
+            number,
+            window: window.max(1),
+            history: vec![VecDeque::new(); number as usize],
+        }
+    }
+
+    /// Read the input registers (Function Code: 0x04) and return the average of each channel
+    ///
+    /// Until `window` reads succeeded, the averages cover the reads so far.
+    pub async fn read<T: Transport>(&mut self, client: &mut Client<T>) -> Result<Vec<f64>> {
+        let request =
+            client
+                .frame()
+                .read_input_registers_request(self.slave, self.address, self.number);
+        match client.call(request).await? {
+            Response::ReadInputRegisters(_, body) => Ok(self.push(&body.get_registers())),
+            response => Err(unexpected(response)),
+        }
+    }
+
+    /// Add one read of the channels and return the average of each
+    ///
+    /// Values beyond the number of channels are ignored.
+    pub fn push(&mut self, values: &[u16]) -> Vec<f64> {
+        self.history
+            .iter_mut()
+            .zip(values)
+            .map(|(history, &value)| {
+                if history.len() == self.window {
+                    history.pop_front();
+                }
+                history.push_back(value);
+                history.iter().map(|&value| value as f64).sum::<f64>() / history.len() as f64
+            })
+            .collect()
+    }
+}
+
+/// Basic device identification
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct DeviceInfo {
+    /// Object 0x00
+    pub vendor_name: String,
+
+    /// Object 0x01
+    pub product_code: String,
+
+    /// Object 0x02, the major and minor revision
+    pub revision: String,
+}
+
+/// Outcome of [`Client::health_check`]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct HealthReport {
+    /// Device specific server id
+    pub server_id: Vec<u8>,
+
+    /// Run indicator status, which some devices report outside of the specification
+    pub run_status: RunStatus,
+
+    /// Whether the server is still processing a previous program command
+    pub busy: bool,
+
+    /// Number of messages the server completed successfully
+    pub event_count: u16,
+}
+
+/// Randomization of the delays between retries
+///
+/// Clients retrying a recovered server in lockstep hit it with synchronized bursts. Jitter
+/// spreads their retries apart.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Jitter {
+    /// Exponential backoff, doubling the delay after each attempt
+    #[default]
+    None,
+
+    /// Random delay between zero and the exponential backoff
+    Full,
+
+    /// Random delay between the base delay and three times the previous delay
+    Decorrelated,
+}
+
+/// How many times and how long apart [`Client::call`] retries a failing request
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use easy_modbus::client::{Jitter, RetryPolicy};
+///
+/// let mut policy = RetryPolicy::new(4, Duration::from_millis(100)).with_jitter(Jitter::Full);
+/// let delays: Vec<Duration> = policy.delays().collect();
+/// assert_eq!(delays.len(), 3);
+/// assert!(delays[2] <= Duration::from_millis(400));
+/// ```
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    /// Attempts in total, the first one included
+    max_attempts: u32,
+
+    /// Delay before the first retry
+    base_delay: Duration,
+
+    /// Longest delay between two attempts
+    max_delay: Duration,
+
+    /// Randomization of the delays
+    jitter: Jitter,
+
+    /// State of the generator drawing the jittered delays
+    rng: u64,
+}
+
+impl RetryPolicy {
+    /// Create a policy making up to `max_attempts` attempts, backing off from `base_delay`
+    ///
+    /// The delays double after each attempt, up to 30 seconds, without jitter.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_attempts` is 0.
+    pub fn new(max_attempts: u32, base_delay: Duration) -> Self {
+        assert!(max_attempts > 0, "at least one attempt must be made");
+        RetryPolicy {
+            max_attempts,
+            base_delay,
+            max_delay: DEFAULT_MAX_RETRY_DELAY,
+            jitter: Jitter::None,
+            rng: RandomState::new().build_hasher().finish(),
+        }
+    }
+
+    /// Set the longest delay between two attempts
+    pub fn with_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Set the randomization of the delays
+    pub fn with_jitter(mut self, jitter: Jitter) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Seed the generator drawing the jittered delays, for reproducible delays
+    ///
+    /// Seeded randomly unless set.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = seed;
+        self
+    }
+
+    /// Draw the delays before each retry of a request
+    ///
+    /// Every call draws new delays, so consecutive requests retry at different times.
+    pub fn delays(&mut self) -> impl Iterator<Item = Duration> + '_ {
+        let mut previous = self.base_delay;
+        (0..self.max_attempts - 1).map(move |attempt| {
+            let backoff = self
+                .base_delay
+                .saturating_mul(1 << attempt.min(31))
+                .min(self.max_delay);
+            let delay = match self.jitter {
+                Jitter::None => backoff,
+                Jitter::Full => self.random_between(Duration::ZERO, backoff),
+                Jitter::Decorrelated => {
+                    let upper = previous.saturating_mul(3).min(self.max_delay);
+                    self.random_between(self.base_delay.min(upper), upper)
+                }
+            };
+            previous = delay;
+            delay
+        })
+    }
+
+    /// Draw a delay uniformly between `low` and `high`
+    fn random_between(&mut self, low: Duration, high: Duration) -> Duration {
+        // SplitMix64
+        self.rng = self.rng.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.rng;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        let unit = (z >> 11) as f64 / (1u64 << 53) as f64;
+        low + (high - low).mul_f64(unit)
+    }
+}
+
+/// Split `records` into reads whose request and response both fit in a frame
+///
+/// A record too long to fit on its own is still read alone, for the device to refuse.
+fn file_record_batches(records: &[FileRecord]) -> Vec<&[FileRecord]> {
+    let mut batches = Vec::new();
+    let mut rest = records;
+    while !rest.is_empty() {
+        let (mut request_len, mut response_len) = (0, 0);
+        let count = rest
+            .iter()
+            .take_while(|record| {
+                request_len += 7;
+                response_len += 2 + record.record_length as usize * 2;
+                request_len <= MAX_FILE_RECORD_BYTES && response_len <= MAX_FILE_RECORD_BYTES
+            })
+            .count()
+            .max(1);
+        let (batch, next) = rest.split_at(count);
+        batches.push(batch);
+        rest = next;
+    }
+    batches
+}
+
+/// Turn a response that does not answer the request into an error
+pub(crate) fn unexpected(response: Response) -> ModbusError {
+    match response {
+        Response::Exception(_, body) => ModbusError::Exception(body.get_exception().clone()),
+        response => ModbusError::InvalidFrame(format!("Unexpected response: {}", response)),
+    }
+}
+
+#[cfg(test)]
+mod client_test {
+    use std::collections::VecDeque;
+    use std::io;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use bytes::BytesMut;
+    use futures::{SinkExt, StreamExt};
+    use tokio::io::{duplex, AsyncReadExt, AsyncWriteExt, DuplexStream};
+    use tokio::net::TcpListener;
+    use tokio::sync::{mpsc, Barrier};
+    use tokio::time::{self, Instant};
+    use tokio_util::codec::{Encoder, Framed};
+
+    use crate::client::{
+        Client, DeviceInfo, HealthReport, Jitter, MultiPortRtuClient, PipelinedClientBuilder,
+        RetryPolicy, SmoothedReader, TcpClientPool, Transport,
+    };
+    use crate::codec::{RtuServerCodec, TcpServerCodec};
+    use crate::frame::{request::FileRecord, response::RunStatus, Exception, Frame, Function};
+    use crate::server::Server;
+    use crate::{ModbusError, Request, Response};
+
+    /// Give `response` the transaction id `tid`, as servers echo the one of the request
+    fn with_tid(mut response: Response, tid: u16) -> Response {
+        let mut head = response.head();
+        head.tid = tid;
+        response.set_head(head);
+        response
+    }
+
+    /// Answers every request with two holding registers and reports when each request arrived
+    fn mock_rtu_server(stream: DuplexStream) -> mpsc::UnboundedReceiver<Instant> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let mut transport = Framed::new(stream, RtuServerCodec);
+            let frame = Frame::rtu();
+            while let Some(Ok(request)) = transport.next().await {
+                tx.send(Instant::now()).unwrap();
+                let response =
+                    frame.read_holding_register_response(request.head().uid(), vec![0, 1, 0, 2]);
+                transport.send(response).await.unwrap();
+            }
+        });
+        rx
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn min_request_gap_test() {
+        let (client_io, server_io) = duplex(256);
+        let mut arrivals = mock_rtu_server(server_io);
+
+        let gap = Duration::from_millis(100);
+        let mut client = Client::rtu(client_io);
+        client.set_min_request_gap(gap);
+
+        let frame = Frame::rtu();
+        for _ in 0..2 {
+            let request = frame.read_multiple_holding_registers_request(0x01, 0x00, 0x02);
+            client.call(request).await.unwrap();
+        }
+
+        let first = arrivals.recv().await.unwrap();
+        let second = arrivals.recv().await.unwrap();
+        assert!(second - first >= gap);
+    }
+
+    #[test]
+    #[should_panic(expected = "the rate limit is too small")]
+    fn tiny_rate_limit_test() {
+        let (client_io, _server_io) = duplex(256);
+        let mut client = Client::rtu(client_io);
+        client.set_rate_limit(1e-300);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn rate_limit_test() {
+        let (client_io, server_io) = duplex(256);
+        let mut arrivals = mock_rtu_server(server_io);
+
+        let mut client = Client::rtu(client_io);
+        client.set_rate_limit(5.0);
+
+        let frame = Frame::rtu();
+        for _ in 0..10 {
+            let request = frame.read_multiple_holding_registers_request(0x01, 0x00, 0x02);
+            client.call(request).await.unwrap();
+        }
+
+        let first = arrivals.recv().await.unwrap();
+        let mut last = first;
+        for _ in 1..10 {
+            let arrival = arrivals.recv().await.unwrap();
+            assert!(arrival - last >= Duration::from_millis(200));
+            last = arrival;
+        }
+        let span = last - first;
+        assert!(span >= Duration::from_millis(1800) && span < Duration::from_millis(2100));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn no_request_gap_test() {
+        let (client_io, server_io) = duplex(256);
+        let mut arrivals = mock_rtu_server(server_io);
+
+        let mut client = Client::rtu(client_io);
+        let frame = Frame::rtu();
+        for _ in 0..2 {
+            let request = frame.read_multiple_holding_registers_request(0x01, 0x00, 0x02);
+            client.call(request).await.unwrap();
+        }
+
+        let first = arrivals.recv().await.unwrap();
+        let second = arrivals.recv().await.unwrap();
+        assert_eq!(second, first);
+    }
+
+    /// Answers requests in memory, as a holding register bank counting up from 0
+    struct MemoryTransport {
+        frame: Frame,
+        responses: VecDeque<Response>,
+    }
+
+    impl Transport for MemoryTransport {
+        async fn send(&mut self, request: Request) -> crate::error::Result<()> {
+            let response = match request {
+                Request::ReadMultipleHoldingRegisters(head, body) => {
+                    let first = body.first_address;
+                    let values: Vec<u16> = (first..first + body.registers_number).collect();
+                    let response = self
+                        .frame
+                        .read_holding_register_response_u16(head.uid(), &values);
+                    with_tid(response, head.tid)
+                }
+                request => panic!("unexpected request {:?}", request),
+            };
+            self.responses.push_back(response);
+            Ok(())
+        }
+
+        async fn recv(&mut self) -> crate::error::Result<Response> {
+            self.responses
+                .pop_front()
+                .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof).into())
+        }
+    }
+
+    #[tokio::test]
+    async fn custom_transport_test() {
+        let transport = MemoryTransport {
+            frame: Frame::tcp(),
+            responses: VecDeque::new(),
+        };
+        let mut client = Client::new(transport, Frame::tcp());
+        let request = Frame::tcp().read_multiple_holding_registers_request(0x01, 0x0010, 0x0002);
+        match client.call(request).await.unwrap() {
+            Response::ReadMultipleHoldingRegisters(_, body) => {
+                assert_eq!(body.get_values(), &vec![0x00, 0x10, 0x00, 0x11])
+            }
+            response => panic!("unexpected response {:?}", response),
+        }
+
+        let error = client.receive().await.unwrap_err();
+        assert!(matches!(error, ModbusError::Io(e) if e.kind() == io::ErrorKind::UnexpectedEof));
+    }
+
+    #[tokio::test]
+    async fn health_check_test() {
+        let (client_io, server_io) = duplex(256);
+        tokio::spawn(async move {
+            let mut transport = Framed::new(server_io, RtuServerCodec);
+            let frame = Frame::rtu();
+            while let Some(Ok(request)) = transport.next().await {
+                let response = match request {
+                    Request::ReportServerId(head, _) => {
+                        frame.report_server_id_response(head.uid(), vec![0x2A, 0x01], 0xFF)
+                    }
+                    Request::GetCommEventCounter(head, _) => {
+                        frame.get_comm_event_counter_response(head.uid(), 0x0000, 0x0108)
+                    }
+                    request => panic!("unexpected request {:?}", request),
+                };
+                transport.send(response).await.unwrap();
+            }
+        });
+
+        let mut client = Client::rtu(client_io);
+        let report = client.health_check(0x0B).await.unwrap();
+        assert_eq!(
+            report,
+            HealthReport {
+                server_id: vec![0x2A, 0x01],
+                run_status: RunStatus::On,
+                busy: false,
+                event_count: 0x0108,
+            }
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn assembly_timeout_test() {
+        let (client_io, mut server_io) = duplex(256);
+        tokio::spawn(async move {
+            let mut request = [0; 8];
+            server_io.read_exact(&mut request).await.unwrap();
+            // Send the header of a two registers response, then stall
+            server_io.write_all(&[0x01, 0x03, 0x04]).await.unwrap();
+            std::future::pending::<()>().await;
+        });
+
+        let mut client = Client::rtu(client_io);
+        client.set_assembly_timeout(Duration::from_millis(50));
+        let request = Frame::rtu().read_multiple_holding_registers_request(0x01, 0x00, 0x02);
+        let result = client.call(request).await;
+        assert!(matches!(result, Err(ModbusError::FrameAssemblyTimeout)));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn flush_input_test() {
+        let (client_io, mut server_io) = duplex(256);
+        let (tx, mut noise) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let mut request = [0; 8];
+            server_io.read_exact(&mut request).await.unwrap();
+            // Half a response the client gives up on, then noise
+            server_io.write_all(&[0x01, 0x03, 0x04]).await.unwrap();
+            time::sleep(Duration::from_millis(100)).await;
+            server_io.write_all(&[0xAA, 0x55]).await.unwrap();
+            tx.send(()).unwrap();
+
+            server_io.read_exact(&mut request).await.unwrap();
+            let response = Frame::rtu().read_holding_register_response(0x01, vec![0x00, 0x2A]);
+            let mut dst = BytesMut::new();
+            RtuServerCodec.encode(response, &mut dst).unwrap();
+            server_io.write_all(&dst).await.unwrap();
+        });
+
+        let mut client = Client::rtu(client_io);
+        client.set_assembly_timeout(Duration::from_millis(50));
+        let frame = Frame::rtu();
+        let request = frame.read_multiple_holding_registers_request(0x01, 0x00, 0x01);
+        let result = client.call(request).await;
+        assert!(matches!(result, Err(ModbusError::FrameAssemblyTimeout)));
+
+        // The half response went with the timeout, leaving the noise
+        noise.recv().await.unwrap();
+        assert_eq!(client.flush_input().unwrap(), 2);
+        assert_eq!(client.flush_input().unwrap(), 0);
+
+        let request = frame.read_multiple_holding_registers_request(0x01, 0x00, 0x01);
+        let response = client.call(request).await.unwrap();
+        let expected = Frame::rtu().read_holding_register_response(0x01, vec![0x00, 0x2A]);
+        assert_eq!(response.to_string(), expected.to_string());
+    }
+
+    #[tokio::test]
+    async fn device_info_test() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = Server::new(|request: Request| {
+            let frame = Frame::tcp();
+            let uid = request.head().uid();
+            match request {
+                // Split the objects over two transactions
+                Request::ReadDeviceIdentification(_, body) if body.get_object_id() == &0x00 => {
+                    let objects = vec![(0x00, b"Company".to_vec())];
+                    frame.read_device_identification_response(uid, 0x01, 0x01, Some(0x01), objects)
+                }
+                Request::ReadDeviceIdentification(_, _) => {
+                    let objects = vec![(0x01, b"Product".to_vec()), (0x02, b"V1.2".to_vec())];
+                    frame.read_device_identification_response(uid, 0x01, 0x01, None, objects)
+                }
+                request => panic!("unexpected request {:?}", request),
+            }
+        });
+        tokio::spawn(server.serve_tcp(listener));
+
+        let client = Client::connect_tcp(addr, Some(0x01)).await.unwrap();
+        assert_eq!(
+            client.device_info(),
+            Some(&DeviceInfo {
+                vendor_name: "Company".to_string(),
+                product_code: "Product".to_string(),
+                revision: "V1.2".to_string(),
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn device_info_progress_test() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = Server::new(|request: Request| {
+            let frame = Frame::tcp();
+            let uid = request.head().uid();
+            match request {
+                // One object per transaction
+                Request::ReadDeviceIdentification(_, body) => {
+                    let id = *body.get_object_id();
+                    let objects = vec![(id, format!("Object {}", id).into_bytes())];
+                    let next = (id < 0x02).then_some(id + 1);
+                    frame.read_device_identification_response(uid, 0x01, 0x01, next, objects)
+                }
+                request => panic!("unexpected request {:?}", request),
+            }
+        });
+        tokio::spawn(server.serve_tcp(listener));
+
+        let mut client = Client::connect_tcp(addr, None).await.unwrap();
+        let mut counts = Vec::new();
+        let device_info = client
+            .read_device_info_with_progress(0x01, |parsed| counts.push(parsed))
+            .await
+            .unwrap();
+        assert_eq!(device_info.revision, "Object 2");
+        assert_eq!(counts, vec![1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn read_file_records_test() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        let server = Server::new(move |request: Request| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            let uid = request.head().uid();
+            match request {
+                // Each register holds its number within the file
+                Request::ReadFileRecord(_, body) => {
+                    let records = body
+                        .get_records()
+                        .iter()
+                        .map(|record| {
+                            let start = *record.get_record_number();
+                            (start..start + record.get_record_length()).collect()
+                        })
+                        .collect();
+                    Frame::tcp().read_file_record_response(uid, records)
+                }
+                request => panic!("unexpected request {:?}", request),
+            }
+        });
+        tokio::spawn(server.serve_tcp(listener));
+        let mut client = Client::connect_tcp(addr, None).await.unwrap();
+
+        let records: Vec<FileRecord> = (0..3).map(|i| FileRecord::new(4, i * 2, 2)).collect();
+        let mut counts = Vec::new();
+        let values = client
+            .read_file_records_with_progress(0x01, &records, |parsed| counts.push(parsed))
+            .await
+            .unwrap();
+        assert_eq!(values, vec![vec![0, 1], vec![2, 3], vec![4, 5]]);
+        assert_eq!(counts, vec![1, 2, 3]);
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        // 35 sub-requests fill a request, the 36th goes with the next one
+        let records: Vec<FileRecord> = (0..36).map(|i| FileRecord::new(4, i, 1)).collect();
+        let values = client.read_file_records(0x01, &records).await.unwrap();
+        assert_eq!(values.len(), 36);
+        assert_eq!(values[35], vec![35]);
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn loopback_test() {
+        for (corrupt, echoed) in [(0x0000, true), (0x0100, false)] {
+            let server = Server::new(move |request: Request| match request {
+                Request::Diagnostics(head, body) => Frame::tcp().diagnostics_response(
+                    head.uid(),
+                    *body.get_sub_function(),
+                    *body.get_data() ^ corrupt,
+                ),
+                request => panic!("unexpected request {:?}", request),
+            });
+            let (client_io, server_io) = duplex(256);
+            tokio::spawn(async move { server.serve(Framed::new(server_io, TcpServerCodec)).await });
+
+            let mut client = Client::tcp(client_io);
+            assert_eq!(client.loopback_test(0x01, 0xA537).await.unwrap(), echoed);
+        }
+    }
+
+    #[tokio::test]
+    async fn typed_read_test() {
+        let server = Server::new(|request: Request| {
+            let frame = Frame::tcp();
+            let uid = request.head().uid();
+            match request {
+                Request::ReadCoils(_, _) => frame.read_coils_response(uid, vec![0xCD, 0x6B]),
+                Request::ReadMultipleHoldingRegisters(_, body)
+                    if body.get_first_address() == &0 =>
+                {
+                    frame.read_holding_register_response(uid, vec![0x00, 0x01, 0xAB, 0xCD])
+                }
+                request => crate::server::exception(request.head(), Exception::IllegalDataAddress),
+            }
+        });
+        let (client_io, server_io) = duplex(256);
+        tokio::spawn(async move { server.serve(Framed::new(server_io, TcpServerCodec)).await });
+
+        let mut client = Client::tcp(client_io);
+        let coils = client.read_coils(0x01, 0x0000, 10).await.unwrap();
+        assert_eq!(
+            coils,
+            vec![true, false, true, true, false, false, true, true, true, true]
+        );
+        let registers = client
+            .read_holding_registers(0x01, 0x0000, 2)
+            .await
+            .unwrap();
+        assert_eq!(registers, vec![0x0001, 0xABCD]);
+        let err = client
+            .read_holding_registers(0x01, 0x0100, 2)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            ModbusError::Exception(Exception::IllegalDataAddress)
+        ));
+    }
+
+    #[tokio::test]
+    async fn transaction_id_test() {
+        let (client_io, server_io) = duplex(256);
+        tokio::spawn(async move {
+            let mut transport = Framed::new(server_io, TcpServerCodec);
+            let frame = Frame::tcp();
+            while let Some(Ok(request)) = transport.next().await {
+                let head = request.head();
+                let response = frame.read_holding_register_response(head.uid(), vec![0, 1]);
+                if head.uid() == 0x01 {
+                    // A late answer to the previous request comes first
+                    let stale = with_tid(response.clone(), head.tid.wrapping_sub(1));
+                    transport.send(stale).await.unwrap();
+                    transport.send(with_tid(response, head.tid)).await.unwrap();
+                } else {
+                    let ahead = with_tid(response, head.tid.wrapping_add(5));
+                    transport.send(ahead).await.unwrap();
+                }
+            }
+        });
+
+        let mut client = Client::tcp(client_io);
+        assert_eq!(client.next_transaction_id(), 1);
+        let registers = client
+            .read_holding_registers(0x01, 0x0000, 1)
+            .await
+            .unwrap();
+        assert_eq!(registers, vec![0x0001]);
+        assert_eq!(client.next_transaction_id(), 2);
+        client
+            .read_holding_registers(0x01, 0x0000, 1)
+            .await
+            .unwrap();
+
+        let error = client
+            .read_holding_registers(0x02, 0x0000, 1)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            ModbusError::TransactionIdMismatch {
+                expected: 3,
+                actual: 8
+            }
+        ));
+        assert_eq!(client.next_transaction_id(), 4);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn timeout_test() {
+        let (client_io, mut server_io) = duplex(256);
+        // A silent slave reading requests without ever answering
+        tokio::spawn(async move {
+            let mut buf = [0; 256];
+            while server_io.read(&mut buf).await.unwrap_or(0) > 0 {}
+        });
+
+        let mut client = Client::rtu(client_io);
+        client.set_timeout(Duration::from_millis(500));
+        let start = Instant::now();
+        let error = client.read_coils(0x01, 0x0000, 8).await.unwrap_err();
+        assert!(matches!(error, ModbusError::Timeout));
+        assert_eq!(start.elapsed(), Duration::from_millis(500));
+        assert!(matches!(client.last_error(), Some(ModbusError::Timeout)));
+    }
+
+    /// Answers every request with two holding registers after a delay of 100 ms
+    fn slow_rtu_server(stream: DuplexStream) {
+        tokio::spawn(async move {
+            let mut transport = Framed::new(stream, RtuServerCodec);
+            let frame = Frame::rtu();
+            while let Some(Ok(request)) = transport.next().await {
+                time::sleep(Duration::from_millis(100)).await;
+                let response =
+                    frame.read_holding_register_response(request.head().uid(), vec![0, 1, 0, 2]);
+                transport.send(response).await.unwrap();
+            }
+        });
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn multi_port_rtu_client_test() {
+        let mut client = MultiPortRtuClient::new();
+        for (port, slaves) in [("ttyUSB0", vec![0x01, 0x03]), ("ttyUSB1", vec![0x02])] {
+            let (client_io, server_io) = duplex(256);
+            slow_rtu_server(server_io);
+            client.add_port(port, Client::rtu(client_io), slaves);
+        }
+        assert_eq!(client.port_of(0x03), Some("ttyUSB0"));
+        assert_eq!(client.port_of(0x04), None);
+
+        let frame = Frame::rtu();
+        let read = |slave| frame.read_multiple_holding_registers_request(slave, 0x0000, 0x0002);
+
+        // Different ports proceed concurrently
+        let start = Instant::now();
+        let (first, second) = tokio::join!(client.call(read(0x01)), client.call(read(0x02)));
+        assert_eq!(first.unwrap().head().uid(), 0x01);
+        assert_eq!(second.unwrap().head().uid(), 0x02);
+        assert_eq!(start.elapsed(), Duration::from_millis(100));
+
+        // The same port serializes
+        let start = Instant::now();
+        let (first, second) = tokio::join!(client.call(read(0x01)), client.call(read(0x03)));
+        assert_eq!(first.unwrap().head().uid(), 0x01);
+        assert_eq!(second.unwrap().head().uid(), 0x03);
+        assert_eq!(start.elapsed(), Duration::from_millis(200));
+
+        let error = client.call(read(0x04)).await.unwrap_err();
+        assert!(matches!(error, ModbusError::InvalidFrame(_)));
+    }
+
+    #[test]
+    fn retry_delays_test() {
+        let base = Duration::from_millis(100);
+        let max = Duration::from_secs(2);
+        let policy = RetryPolicy::new(6, base).with_max_delay(max).with_seed(42);
+
+        let delays: Vec<Duration> = policy.clone().delays().collect();
+        let expected: Vec<Duration> = [100, 200, 400, 800, 1600]
+            .into_iter()
+            .map(Duration::from_millis)
+            .collect();
+        assert_eq!(delays, expected);
+
+        let mut full = policy.clone().with_jitter(Jitter::Full);
+        let delays: Vec<Duration> = full.delays().collect();
+        for (attempt, delay) in delays.iter().enumerate() {
+            assert!(*delay <= (base * (1 << attempt)).min(max));
+        }
+        let mut distinct = delays.clone();
+        distinct.sort();
+        distinct.dedup();
+        assert_eq!(distinct.len(), delays.len());
+        // Reproducible from the seed, different for the next request
+        let mut again = policy.clone().with_jitter(Jitter::Full);
+        assert_eq!(again.delays().collect::<Vec<_>>(), delays);
+        assert_ne!(full.delays().collect::<Vec<_>>(), delays);
+
+        let mut decorrelated = policy.with_jitter(Jitter::Decorrelated);
+        let delays: Vec<Duration> = decorrelated.delays().collect();
+        let mut previous = base;
+        for delay in &delays {
+            assert!(*delay >= base && *delay <= (previous * 3).min(max));
+            previous = *delay;
+        }
+        let mut distinct = delays.clone();
+        distinct.sort();
+        distinct.dedup();
+        assert_eq!(distinct.len(), delays.len());
+    }
+
+    /// Loses the first responses to the given errors, then answers from memory
+    struct FlakyTransport {
+        failures: VecDeque<ModbusError>,
+        inner: MemoryTransport,
+    }
+
+    impl Transport for FlakyTransport {
+        async fn send(&mut self, request: Request) -> crate::error::Result<()> {
+            self.inner.send(request).await
+        }
+
+        async fn recv(&mut self) -> crate::error::Result<Response> {
+            let response = self.inner.recv().await;
+            match self.failures.pop_front() {
+                Some(e) => Err(e),
+                None => response,
+            }
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn retry_test() {
+        let crc_mismatch = || ModbusError::CrcMismatch {
+            bytes: vec![],
+            expected: 0x1234,
+            actual: 0x4321,
+        };
+        let transport = |failures: Vec<ModbusError>| FlakyTransport {
+            failures: failures.into(),
+            inner: MemoryTransport {
+                frame: Frame::tcp(),
+                responses: VecDeque::new(),
+            },
+        };
+        let request = Frame::tcp().read_multiple_holding_registers_request(0x01, 0x0010, 0x0002);
+        let policy = RetryPolicy::new(3, Duration::from_millis(100));
+
+        let failures = vec![crc_mismatch(), ModbusError::Timeout];
+        let mut client = Client::new(transport(failures), Frame::tcp()).with_retry(policy.clone());
+        let start = Instant::now();
+        client.call(request.clone()).await.unwrap();
+        assert_eq!(start.elapsed(), Duration::from_millis(300));
+        assert!(client.last_error().is_none());
+
+        let failures = vec![crc_mismatch(), crc_mismatch(), ModbusError::Timeout];
+        let mut client = Client::new(transport(failures), Frame::tcp()).with_retry(policy.clone());
+        let error = client.call(request.clone()).await.unwrap_err();
+        assert!(matches!(error, ModbusError::Timeout));
+
+        // Not transient, failing right away
+        let failures = vec![ModbusError::InvalidFunction(0x99)];
+        let mut client = Client::new(transport(failures), Frame::tcp()).with_retry(policy);
+        let start = Instant::now();
+        let error = client.call(request).await.unwrap_err();
+        assert!(matches!(error, ModbusError::InvalidFunction(0x99)));
+        assert_eq!(start.elapsed(), Duration::ZERO);
+        assert!(!ModbusError::Exception(Exception::IllegalDataAddress).is_transient());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn rtu_retry_test() {
+        let (client_io, server_io) = duplex(256);
+        tokio::spawn(async move {
+            let mut transport = Framed::new(server_io, RtuServerCodec);
+            let frame = Frame::rtu();
+            let mut value = 0;
+            while let Some(Ok(request)) = transport.next().await {
+                value += 1;
+                let response =
+                    frame.read_holding_register_response(request.head().uid(), vec![0, value]);
+                if value == 1 {
+                    // A bit flipped on the line
+                    let mut bytes = BytesMut::new();
+                    RtuServerCodec.encode(response, &mut bytes).unwrap();
+                    bytes[4] ^= 0x01;
+                    transport.get_mut().write_all(&bytes).await.unwrap();
+                } else {
+                    transport.send(response).await.unwrap();
+                }
+            }
+        });
+        let policy = RetryPolicy::new(3, Duration::from_millis(10));
+        let mut client = Client::rtu(client_io).with_retry(policy);
+
+        let values = client
+            .read_holding_registers(0x01, 0x0000, 1)
+            .await
+            .unwrap();
+        assert_eq!(values, vec![2]);
+        let values = client
+            .read_holding_registers(0x01, 0x0000, 1)
+            .await
+            .unwrap();
+        assert_eq!(values, vec![3]);
+    }
+
+    #[test]
+    fn smoothed_reader_test() {
+        let mut reader = SmoothedReader::new(0x01, 0x0000, 2, 3);
+        assert_eq!(reader.push(&[10, 7]), vec![10.0, 7.0]);
+        assert_eq!(reader.push(&[20, 7]), vec![15.0, 7.0]);
+        assert_eq!(reader.push(&[30, 7]), vec![20.0, 7.0]);
+        assert_eq!(reader.push(&[40, 1]), vec![30.0, 5.0]);
+    }
+
+    #[tokio::test]
+    async fn smoothed_reader_read_test() {
+        let reads = AtomicUsize::new(0);
+        let server = Server::new(move |request: Request| {
+            let value = 10 * (reads.fetch_add(1, Ordering::SeqCst) as u8 + 1);
+            Frame::tcp().read_input_register_response(request.head().uid(), vec![0x00, value])
+        });
+        let (client_io, server_io) = duplex(256);
+        tokio::spawn(async move { server.serve(Framed::new(server_io, TcpServerCodec)).await });
+
+        let mut client = Client::tcp(client_io);
+        let mut reader = SmoothedReader::new(0x01, 0x0000, 1, 3);
+        let mut averages = Vec::new();
+        for _ in 0..3 {
+            averages = reader.read(&mut client).await.unwrap();
+        }
+        assert_eq!(averages, vec![20.0]);
+    }
+
+    #[tokio::test]
+    async fn tcp_client_pool_test() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let connections = Arc::new(AtomicUsize::new(0));
+        let accepted = connections.clone();
+        // Hold every response until two requests are in flight, so reads that do not proceed
+        // concurrently never complete
+        let barrier = Arc::new(Barrier::new(2));
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                accepted.fetch_add(1, Ordering::SeqCst);
+                let barrier = barrier.clone();
+                tokio::spawn(async move {
+                    let mut transport = Framed::new(stream, TcpServerCodec);
+                    let frame = Frame::tcp();
+                    while let Some(Ok(request)) = transport.next().await {
+                        let tid = request.get_head().tid;
+                        barrier.wait().await;
+                        let uid = request.head().uid();
+                        let response = frame.read_holding_register_response(uid, vec![0, 1, 0, 2]);
+                        transport.send(with_tid(response, tid)).await.unwrap();
+                    }
+                });
+            }
+        });
+
+        let pool = TcpClientPool::new(addr, 2);
+        let read = || async {
+            let mut client = pool.acquire().await.unwrap();
+            let request = Frame::tcp().read_multiple_holding_registers_request(0x01, 0x00, 0x02);
+            client.call(request).await.unwrap()
+        };
+        for _ in 0..2 {
+            let reads = futures::future::join(read(), read());
+            let (first, second) = tokio::time::timeout(Duration::from_secs(5), reads)
+                .await
+                .unwrap();
+            assert_eq!(first.to_string(), second.to_string());
+            assert_eq!(pool.idle_connections(), 2);
+        }
+        assert_eq!(connections.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn tcp_client_pool_discard_test() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        // Unit 2 never answers
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                tokio::spawn(async move {
+                    let mut transport = Framed::new(stream, TcpServerCodec);
+                    let frame = Frame::tcp();
+                    while let Some(Ok(request)) = transport.next().await {
+                        let head = request.head();
+                        if head.uid() == 0x01 {
+                            let response = frame.read_holding_register_response(0x01, vec![0, 1]);
+                            transport.send(with_tid(response, head.tid)).await.unwrap();
+                        }
+                    }
+                });
+            }
+        });
+
+        let pool = TcpClientPool::new(addr, 1);
+        let request = |uid| Frame::tcp().read_multiple_holding_registers_request(uid, 0x00, 0x01);
+        let mut client = pool.acquire().await.unwrap();
+        client.call(request(0x01)).await.unwrap();
+        drop(client);
+        assert_eq!(pool.idle_connections(), 1);
+
+        // Cancelled mid-exchange
+        let mut client = pool.acquire().await.unwrap();
+        let call = time::timeout(Duration::from_millis(50), client.call(request(0x02))).await;
+        assert!(call.is_err());
+        drop(client);
+        assert_eq!(pool.idle_connections(), 0);
+
+        // Failed
+        let mut client = pool.acquire().await.unwrap();
+        client.set_timeout(Duration::from_millis(50));
+        let error = client.call(request(0x02)).await.unwrap_err();
+        assert!(matches!(error, ModbusError::Timeout));
+        drop(client);
+        assert_eq!(pool.idle_connections(), 0);
+    }
+
+    #[tokio::test]
+    async fn read_coils_chunked_test() {
+        let (client_io, server_io) = duplex(1024);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let mut transport = Framed::new(server_io, TcpServerCodec);
+            let frame = Frame::tcp();
+            while let Some(Ok(request)) = transport.next().await {
+                let tid = request.get_head().tid;
+                let body = match request {
+                    Request::ReadCoils(_, body) => body,
+                    request => panic!("unexpected request {:?}", request),
+                };
+                tx.send((body.first_address, body.coils_number)).unwrap();
+                // Every third coil is on
+                let mut values = vec![0; (body.coils_number as usize).div_ceil(8)];
+                for i in 0..body.coils_number as usize {
+                    if (body.first_address as usize + i).is_multiple_of(3) {
+                        values[i / 8] |= 1 << (i % 8);
+                    }
+                }
+                let response = frame.read_coils_response(0x01, values);
+                transport.send(with_tid(response, tid)).await.unwrap();
+            }
+        });
+
+        let mut client = Client::tcp(client_io);
+        let coils = client.read_coils_chunked(0x01, 0x0000, 5000).await.unwrap();
+        assert_eq!(coils.len(), 5000);
+        assert!(coils
+            .iter()
+            .enumerate()
+            .all(|(i, &coil)| coil == i.is_multiple_of(3)));
+
+        let mut requests = Vec::new();
+        while let Ok(request) = rx.try_recv() {
+            requests.push(request);
+        }
+        assert_eq!(requests, vec![(0, 2000), (2000, 2000), (4000, 1000)]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn short_read_test() {
+        let (client_io, server_io) = duplex(1024);
+        // Answers every read with a single byte of data
+        tokio::spawn(async move {
+            let mut transport = Framed::new(server_io, TcpServerCodec);
+            let frame = Frame::tcp();
+            while let Some(Ok(request)) = transport.next().await {
+                let tid = request.get_head().tid;
+                let response = match request {
+                    Request::ReadCoils(..) => frame.read_coils_response(0x01, vec![0xFF]),
+                    _ => frame.read_holding_register_response(0x01, vec![0x00, 0x01]),
+                };
+                transport.send(with_tid(response, tid)).await.unwrap();
+            }
+        });
+
+        let mut client = Client::tcp(client_io);
+        assert_eq!(client.read_coils(0x01, 0x00, 8).await.unwrap().len(), 8);
+        let result = client.read_coils(0x01, 0x00, 9).await;
+        assert!(matches!(result, Err(ModbusError::InvalidFrame(_))));
+        assert_eq!(
+            client.read_holding_registers(0x01, 0x00, 1).await.unwrap(),
+            vec![1]
+        );
+        let result = client.read_holding_registers(0x01, 0x00, 2).await;
+        assert!(matches!(result, Err(ModbusError::InvalidFrame(_))));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn last_error_test() {
+        let (client_io, mut server_io) = duplex(256);
+        tokio::spawn(async move {
+            let response = Frame::rtu().read_holding_register_response(0x01, vec![0, 1, 0, 2]);
+            let mut bytes = BytesMut::new();
+            RtuServerCodec.encode(response, &mut bytes).unwrap();
+
+            let mut request = [0; 8];
+            server_io.read_exact(&mut request).await.unwrap();
+            // Stall after the header, never completing the response
+            server_io.write_all(&bytes[..3]).await.unwrap();
+            server_io.read_exact(&mut request).await.unwrap();
+            let response = Frame::rtu().read_holding_register_response(0x01, vec![0, 3, 0, 4]);
+            let mut bytes = BytesMut::new();
+            RtuServerCodec.encode(response, &mut bytes).unwrap();
+            server_io.write_all(&bytes).await.unwrap();
+            std::future::pending::<()>().await;
+        });
+
+        let mut client = Client::rtu(client_io);
+        client.set_assembly_timeout(Duration::from_millis(50));
+        assert!(client.last_error().is_none());
+
+        let request = Frame::rtu().read_multiple_holding_registers_request(0x01, 0x00, 0x02);
+        assert!(client.call(request.clone()).await.is_err());
+        assert!(matches!(
+            client.last_error(),
+            Some(ModbusError::FrameAssemblyTimeout)
+        ));
+
+        match client.call(request).await.unwrap() {
+            Response::ReadMultipleHoldingRegisters(_, body) => {
+                assert_eq!(body.values, vec![0, 3, 0, 4])
+            }
+            response => panic!("unexpected response {:?}", response),
+        }
+        assert!(client.last_error().is_none());
+    }
+
+    #[tokio::test]
+    async fn write_multiple_registers_test() {
+        let (client_io, server_io) = duplex(256);
+        tokio::spawn(async move {
+            let mut transport = Framed::new(server_io, TcpServerCodec);
+            let frame = Frame::tcp();
+            while let Some(Ok(request)) = transport.next().await {
+                let tid = request.get_head().tid;
+                let (head, body) = match request {
+                    Request::WriteMultipleHoldingRegisters(head, body) => (head, body),
+                    request => panic!("unexpected request {:?}", request),
+                };
+                // Unit 2 acknowledges no more than 3 registers
+                let mut number = *body.get_registers_number();
+                if head.uid() == 0x02 {
+                    number = number.min(3);
+                }
+                let response = frame.write_multiple_holding_registers_response(
+                    head.uid(),
+                    *body.get_first_address(),
+                    number,
+                );
+                transport.send(with_tid(response, tid)).await.unwrap();
+            }
+        });
+
+        let mut client = Client::tcp(client_io);
+        let values = [0x0001, 0x0002, 0x0003, 0x0004, 0x0005];
+        let written = client.write_multiple_registers(0x01, 0x0010, &values).await;
+        assert_eq!(written.unwrap(), 5);
+        let written = client.write_multiple_registers(0x02, 0x0010, &values).await;
+        assert_eq!(written.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn write_then_verify_coils_test() {
+        let (client_io, server_io) = duplex(256);
+        tokio::spawn(async move {
+            let mut transport = Framed::new(server_io, TcpServerCodec);
+            let frame = Frame::tcp();
+            let mut coils = [false; 16];
+            while let Some(Ok(request)) = transport.next().await {
+                let tid = request.get_head().tid;
+                let response = match request {
+                    Request::WriteMultipleCoils(head, body) => {
+                        let first = body.first_address as usize;
+                        for i in 0..body.coils_number as usize {
+                            // Unit 2 drops the write of coil 3
+                            if head.uid() == 0x02 && first + i == 3 {
+                                continue;
+                            }
+                            coils[first + i] = body.values[i / 8] >> (i % 8) & 0x01 == 0x01;
+                        }
+                        frame.write_multiple_coils_response_from_request(head.uid(), &body)
+                    }
+                    Request::ReadCoils(head, body) => {
+                        let first = body.first_address as usize;
+                        let read = &coils[first..first + body.coils_number as usize];
+                        frame.read_coils_response_bool(head.uid(), read)
+                    }
+                    request => panic!("unexpected request {:?}", request),
+                };
+                transport.send(with_tid(response, tid)).await.unwrap();
+            }
+        });
+
+        let mut client = Client::tcp(client_io);
+        let states = [true, false, true, true, false, true, false, false, true];
+        client
+            .write_then_verify_coils(0x01, 0x0002, &states)
+            .await
+            .unwrap();
+
+        let states = states.map(|state| !state);
+        match client.write_then_verify_coils(0x02, 0x0002, &states).await {
+            Err(ModbusError::VerifyMismatch(addresses)) => assert_eq!(addresses, vec![0x0003]),
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+
+    #[tokio::test]
+    async fn update_register_bits_test() {
+        let (client_io, server_io) = duplex(256);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let mut transport = Framed::new(server_io, TcpServerCodec);
+            let frame = Frame::tcp();
+            while let Some(Ok(request)) = transport.next().await {
+                let tid = request.get_head().tid;
+                let (head, body) = match request {
+                    Request::MaskWriteRegister(head, body) => (head, body),
+                    request => panic!("unexpected request {:?}", request),
+                };
+                let response = frame.mask_write_register_response_from_request(head.uid(), &body);
+                tx.send(body).unwrap();
+                transport.send(with_tid(response, tid)).await.unwrap();
+            }
+        });
+
+        let mut client = Client::tcp(client_io);
+        client
+            .update_register_bits(0x01, 0x0004, 0x0003, 0x0100)
+            .await
+            .unwrap();
+        let body = rx.recv().await.unwrap();
+        assert_eq!(body.get_reference_address(), &0x0004);
+        assert_eq!(body.get_and_mask(), &0xFEFC);
+        assert_eq!(body.get_or_mask(), &0x0003);
+
+        // Whatever the current value, the set bits end up on and the cleared ones off
+        let current: u16 = 0x0F0C;
+        let and_mask = body.get_and_mask();
+        let or_mask = body.get_or_mask();
+        assert_eq!((current & and_mask) | (or_mask & !and_mask), 0x0E0F);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn scan_bus_test() {
+        let (client_io, server_io) = duplex(256);
+        tokio::spawn(async move {
+            let mut transport = Framed::new(server_io, TcpServerCodec);
+            let frame = Frame::tcp();
+            while let Some(Ok(request)) = transport.next().await {
+                let tid = request.get_head().tid;
+                let uid = request.head().uid();
+                let response = match uid {
+                    3 => frame.report_server_id_response(uid, vec![0x03], 0xFF),
+                    7 => frame.exception_response(
+                        uid,
+                        Function::ReportServerId,
+                        Exception::IllegalFunction,
+                    ),
+                    5 => frame.exception_response(
+                        uid,
+                        Function::ReportServerId,
+                        Exception::GatewayTargetDeviceFailedToRespond,
+                    ),
+                    _ => continue,
+                };
+                transport.send(with_tid(response, tid)).await.unwrap();
+            }
+        });
+
+        let mut client = Client::tcp(client_io);
+        let found = client.scan_bus(1..=10, Duration::from_millis(100)).await;
+        assert_eq!(found, vec![3, 7]);
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one request must be allowed in flight")]
+    fn zero_in_flight_test() {
+        PipelinedClientBuilder::new().max_in_flight(0);
+    }
+
+    /// Serve pipelined requests, reporting each arrival and answering the oldest one each time
+    /// one is released
+    fn held_server(
+        server_io: DuplexStream,
+    ) -> (mpsc::UnboundedReceiver<()>, mpsc::UnboundedSender<()>) {
+        let (arrived_tx, arrived) = mpsc::unbounded_channel();
+        let (release_tx, mut release) = mpsc::unbounded_channel::<()>();
+        let (mut sink, mut stream) = Framed::new(server_io, TcpServerCodec).split();
+        let (queue_tx, mut queue) = mpsc::unbounded_channel::<Request>();
+        tokio::spawn(async move {
+            while let Some(Ok(request)) = stream.next().await {
+                arrived_tx.send(()).unwrap();
+                queue_tx.send(request).unwrap();
+            }
+        });
+        // Answer the oldest request each time one is released
+        tokio::spawn(async move {
+            let frame = Frame::tcp();
+            while release.recv().await.is_some() {
+                let request = queue.recv().await.unwrap();
+                let mut response =
+                    frame.read_holding_register_response(request.head().uid(), vec![0, 1]);
+                let mut head = response.head();
+                head.tid = request.head().tid;
+                response.set_head(head);
+                sink.send(response).await.unwrap();
+            }
+        });
+        (arrived, release_tx)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn max_in_flight_test() {
+        let (client_io, server_io) = duplex(1024);
+        let (mut arrived, release_tx) = held_server(server_io);
+        let client = PipelinedClientBuilder::new()
+            .max_in_flight(2)
+            .tcp(client_io);
+        let calls: Vec<_> = (0..3)
+            .map(|_| {
+                let client = client.clone();
+                let request =
+                    Frame::tcp().read_multiple_holding_registers_request(0x01, 0x00, 0x01);
+                tokio::spawn(async move { client.call(request).await })
+            })
+            .collect();
+
+        arrived.recv().await.unwrap();
+        arrived.recv().await.unwrap();
+        let third = time::timeout(Duration::from_millis(100), arrived.recv()).await;
+        assert!(third.is_err());
+
+        release_tx.send(()).unwrap();
+        arrived.recv().await.unwrap();
+        release_tx.send(()).unwrap();
+        release_tx.send(()).unwrap();
+        for call in calls {
+            assert!(call.await.unwrap().is_ok());
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn cancelled_call_test() {
+        let (client_io, server_io) = duplex(1024);
+        let (mut arrived, release_tx) = held_server(server_io);
+        let client = PipelinedClientBuilder::new()
+            .max_in_flight(1)
+            .tcp(client_io);
+        let request = Frame::tcp().read_multiple_holding_registers_request(0x01, 0x00, 0x01);
+
+        let first = time::timeout(Duration::from_millis(50), client.call(request.clone())).await;
+        assert!(first.is_err());
+        arrived.recv().await.unwrap();
+
+        // The request given up on still holds the only slot
+        let second = tokio::spawn({
+            let client = client.clone();
+            async move { client.call(request).await }
+        });
+        let early = time::timeout(Duration::from_millis(100), arrived.recv()).await;
+        assert!(early.is_err());
+
+        release_tx.send(()).unwrap();
+        arrived.recv().await.unwrap();
+        release_tx.send(()).unwrap();
+        assert!(second.await.unwrap().is_ok());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn broadcast_test() {
+        let (client_io, server_io) = duplex(64);
+        let (tx, mut received) = mpsc::unbounded_channel();
+        // Takes requests without ever answering, as slaves do with broadcasts
+        tokio::spawn(async move {
+            let mut transport = Framed::new(server_io, RtuServerCodec);
+            while let Some(Ok(request)) = transport.next().await {
+                tx.send(request).unwrap();
+            }
+        });
+
+        let mut client = Client::rtu(client_io);
+        let frame = Frame::rtu();
+        let request = frame.write_single_holding_register_request(0x00, 0x0001, 0x0003);
+        let result = time::timeout(Duration::from_secs(1), client.broadcast(request.clone())).await;
+        assert!(result.unwrap().is_ok());
+        assert_eq!(received.recv().await.unwrap(), request);
+
+        let result = client.call(request).await;
+        assert!(matches!(result, Err(ModbusError::InvalidFrame(_))));
+        let request = frame.write_single_holding_register_request(0x01, 0x0001, 0x0003);
+        let result = client.broadcast(request).await;
+        assert!(matches!(result, Err(ModbusError::InvalidFrame(_))));
+        assert!(received.try_recv().is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn broadcast_helpers_test() {
+        let (client_io, server_io) = duplex(256);
+        let (tx, mut received) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let mut transport = Framed::new(server_io, RtuServerCodec);
+            while let Some(Ok(request)) = transport.next().await {
+                tx.send(request.head().function).unwrap();
+            }
+        });
+
+        let mut client = Client::rtu(client_io);
+        let timeout = Duration::from_secs(1);
+        let written = time::timeout(
+            timeout,
+            client.write_multiple_registers(0x00, 0x10, &[1, 2]),
+        );
+        assert_eq!(written.await.unwrap().unwrap(), 2);
+        let updated = time::timeout(timeout, client.update_register_bits(0x00, 0x10, 0x01, 0x02));
+        assert!(updated.await.unwrap().is_ok());
+        let verified = time::timeout(timeout, client.write_then_verify_coils(0x00, 0x20, &[true]));
+        assert!(verified.await.unwrap().is_ok());
+
+        assert_eq!(
+            received.recv().await.unwrap(),
+            Function::WriteMultipleHoldingRegisters
+        );
+        assert_eq!(received.recv().await.unwrap(), Function::MaskWriteRegister);
+        assert_eq!(received.recv().await.unwrap(), Function::WriteMultipleCoils);
+        assert!(received.try_recv().is_err());
+    }
+}
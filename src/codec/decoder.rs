@@ -1,149 +1,809 @@
-use std::io::{Error, ErrorKind::InvalidData, Result};
+use std::borrow::Cow;
 
 use bytes::{Buf, BufMut, Bytes, BytesMut};
 use tokio_util::codec::Decoder;
 
-use crate::codec::{RtuClientCodec, RtuServerCodec};
+use crate::codec::{
+    swap_bytes, swap_response_registers, trace_frame, warn_stray_byte, AsciiClientCodec,
+    AsciiServerCodec, ReadValues, RtuClientCodec, RtuServerCodec, RtuStats,
+};
 use crate::frame::{
     Exception,
     Function,
     Head, request::{ReadCoilsRequest, Request}, response::{ReadCoilsResponse, Response}, Version,
+    FILE_RECORD_REFERENCE_TYPE, MAX_FIFO_COUNT, MEI_READ_DEVICE_IDENTIFICATION,
 };
 use crate::frame::request::*;
 use crate::frame::response::*;
-use crate::util::crc;
+use crate::error::{ModbusError, Result};
+use crate::util::crc::{self, Checksum};
+use crate::util::lrc;
 
-use super::{TcpClientCodec, TcpServerCodec};
+use super::{
+    DecodeEvent, PduClientCodec, PduServerCodec, ProgressDecoder, TcpClientCodec, TcpServerCodec,
+};
 
 impl Decoder for RtuClientCodec {
     type Item = Response;
-    type Error = Error;
+    type Error = ModbusError;
 
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Response>> {
         if src.len() < 2 {
             return Ok(None);
         }
 
-        let mut data_bytes = BytesMut::new();
-
-        let head_bytes = src.copy_to_bytes(2);
-        data_bytes.put_slice(&(head_bytes.to_vec()));
-        let mut head = Head::rtu_try_from(head_bytes)?;
-
-        let len: usize = {
-            if head.is_exception {
-                1
-            } else {
-                match head.function {
-                    Function::ReadCoils
-                    | Function::ReadDiscreteInputs
-                    | Function::ReadMultipleHoldingRegisters
-                    | Function::ReadInputRegisters => {
-                        src.get(0).map_or(0, |&bytes_num| bytes_num as usize + 1)
-                    }
-                    Function::WriteSingleCoil
-                    | Function::WriteSingleHoldingRegister
-                    | Function::WriteMultipleCoils
-                    | Function::WriteMultipleHoldingRegisters => 4,
-                }
+        let head = match Head::rtu_try_from(Bytes::copy_from_slice(&src[..2])) {
+            Ok(head) => head,
+            Err(e) => {
+                // Skip the byte that cannot start a frame
+                src.advance(1);
+                self.stats.resyncs += 1;
+                self.stats.bytes_discarded += 1;
+                return Err(e);
             }
         };
 
-        if src.len() < len + 2 {
+        let len = match response_body_len(&head, &src[2..])? {
+            Some(len) => len,
+            None => return Ok(None),
+        };
+
+        let mut response = rtu_response(src, head, len, self.checksum.as_ref(), &mut self.stats)?;
+        if self.swap_register_bytes {
+            response.iter_mut().for_each(swap_response_registers);
+        }
+        Ok(response)
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Response>> {
+        let item = self.decode(src)?;
+        end_of_stream(item, src)
+    }
+}
+
+impl RtuClientCodec {
+    /// Decode a response using the request it answers to frame it.
+    ///
+    /// The body length is taken from the request instead of the byte count in the response, so
+    /// a frame is cut out precisely even when more bytes (e.g. line noise) follow it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bytes::BytesMut;
+    /// use easy_modbus::Frame;
+    /// use easy_modbus::codec::RtuClientCodec;
+    ///
+    /// let request = Frame::rtu().read_input_registers_request(0x0B, 0x000A, 0x0001);
+    /// let mut buf = BytesMut::from(&[0x0B, 0x04, 0x02, 0x10, 0x2F, 0x6D, 0x2D, 0xFF][..]);
+    /// let response = RtuClientCodec::default().decode_with_request(&mut buf, &request).unwrap();
+    /// assert!(response.is_some());
+    /// ```
+    pub fn decode_with_request(
+        &mut self,
+        src: &mut BytesMut,
+        request: &Request,
+    ) -> Result<Option<Response>> {
+        if src.len() < 2 {
             return Ok(None);
         }
 
-        head.body_length(len as u16);
+        let head = Head::rtu_try_from(Bytes::copy_from_slice(&src[..2]))?;
+        let expected = request.head();
+        if head.function != expected.function || head.uid != expected.uid {
+            return Err(ModbusError::InvalidFrame(format!(
+                "Response 0x{:0>2X} {:?} does not answer request 0x{:0>2X} {:?}",
+                head.uid, head.function, expected.uid, expected.function
+            )));
+        }
 
-        let body_bytes = src.copy_to_bytes(len);
-        data_bytes.put_slice(&(body_bytes.to_vec()));
-        let response = get_response(body_bytes, head);
+        let len = match response_len(request).filter(|_| !head.is_exception) {
+            Some(len) => len,
+            None => match response_body_len(&head, &src[2..])? {
+                Some(len) => len,
+                None => return Ok(None),
+            },
+        };
 
-        let crc = src.get_u16();
-        if crc::check(&(data_bytes.to_vec()), crc) {
-            return Ok(Some(response));
+        let mut response = rtu_response(src, head, len, self.checksum.as_ref(), &mut self.stats)?;
+        if self.swap_register_bytes {
+            response.iter_mut().for_each(swap_response_registers);
         }
-        return Err(Error::new(
-            InvalidData,
-            format!("Invalid crc code: 0x{:0>2X}", crc),
-        ));
+        Ok(response)
+    }
+}
+
+/// Body length of the response whose body starts `body`, `None` until enough of it arrived to tell
+fn response_body_len(head: &Head, body: &[u8]) -> Result<Option<usize>> {
+    if head.is_exception {
+        return Ok(Some(1));
+    }
+
+    let len = match head.function {
+        Function::ReadCoils
+        | Function::ReadDiscreteInputs
+        | Function::ReadMultipleHoldingRegisters
+        | Function::ReadInputRegisters
+        | Function::ReadWriteMultipleRegisters
+        | Function::ReportServerId
+        | Function::ReadFileRecord => body.first().map(|&bytes_num| bytes_num as usize + 1),
+        Function::ReadFifoQueue => fifo_byte_count(body).map(|bytes_num| bytes_num + 2),
+        Function::WriteSingleCoil
+        | Function::WriteSingleHoldingRegister
+        | Function::WriteMultipleCoils
+        | Function::WriteMultipleHoldingRegisters
+        | Function::GetCommEventCounter
+        | Function::Diagnostics => Some(4),
+        Function::MaskWriteRegister => Some(6),
+        Function::ReadExceptionStatus => Some(1),
+        Function::ReadDeviceIdentification => {
+            check_mei_type(body.first())?;
+            device_identification_len(body)
+        }
+    };
+    Ok(len)
+}
+
+/// Byte count of a read FIFO queue response body, `None` until it arrived
+fn fifo_byte_count(body: &[u8]) -> Option<usize> {
+    Some(u16::from_be_bytes([*body.first()?, *body.get(1)?]) as usize)
+}
+
+/// Make sure a read FIFO queue response holds at most 31 values, as many as its byte count covers
+fn check_fifo_count(body: &[u8]) -> Result<()> {
+    let fifo_count = u16::from_be_bytes([body[2], body[3]]);
+    if fifo_count > MAX_FIFO_COUNT {
+        return Err(ModbusError::InvalidLength(format!(
+            "FIFO count {} exceeds {}",
+            fifo_count, MAX_FIFO_COUNT
+        )));
+    }
+    if body.len() != 4 + fifo_count as usize * 2 {
+        return Err(ModbusError::InvalidLength(format!(
+            "FIFO count {} does not match {} bytes of values",
+            fifo_count,
+            body.len() - 4
+        )));
+    }
+    Ok(())
+}
+
+/// Length of a read device identification response body, `None` until its object list arrived
+fn device_identification_len(body: &[u8]) -> Option<usize> {
+    let objects = *body.get(5)?;
+    let mut len = 6;
+    for _ in 0..objects {
+        len += 2 + *body.get(len + 1)? as usize;
+    }
+    Some(len)
+}
+
+/// Only Read Device Identification is supported out of the encapsulated interfaces
+fn check_mei_type(mei_type: Option<&u8>) -> Result<()> {
+    match mei_type {
+        Some(&mei_type) if mei_type != MEI_READ_DEVICE_IDENTIFICATION => Err(
+            ModbusError::InvalidFrame(format!("Unsupported MEI type: 0x{:0>2X}", mei_type)),
+        ),
+        _ => Ok(()),
     }
 }
 
+/// Split a RTU response with a `len` bytes body off `src` and check its CRC
+///
+/// A frame failing the check is dropped, resynchronizing on the bytes that follow it.
+fn rtu_response(
+    src: &mut BytesMut,
+    mut head: Head,
+    len: usize,
+    checksum: &dyn Checksum,
+    stats: &mut RtuStats,
+) -> Result<Option<Response>> {
+    if src.len() < len + 4 {
+        return Ok(None);
+    }
+    trace_frame("Received", &[&src[..len + 4]]);
+
+    let data_bytes = src.split_to(len + 2).freeze();
+    let crc = src.get_u16();
+    if !checksum.check(&data_bytes, crc) {
+        stats.crc_errors += 1;
+        stats.resyncs += 1;
+        stats.bytes_discarded += len as u64 + 4;
+        return Err(crc_mismatch(&data_bytes, checksum.compute(&data_bytes), crc));
+    }
+
+    head.body_length(len as u16);
+    let body_bytes = data_bytes.slice(2..);
+    check_response_body(&head, &body_bytes)?;
+    Ok(Some(get_response(body_bytes, head)))
+}
+
+/// Make sure a response body holds exactly what its function and byte count announce
+fn check_response_body(head: &Head, body: &[u8]) -> Result<()> {
+    let expected = if head.is_exception {
+        1
+    } else {
+        match head.function {
+            Function::ReadCoils
+            | Function::ReadDiscreteInputs
+            | Function::ReadMultipleHoldingRegisters
+            | Function::ReadInputRegisters
+            | Function::ReadWriteMultipleRegisters
+            | Function::ReadFileRecord => body.first().map_or(1, |&n| n as usize + 1),
+            // The byte count covers at least the run indicator status
+            Function::ReportServerId => body.first().map_or(2, |&n| n.max(1) as usize + 1),
+            // The byte count covers at least the FIFO count
+            Function::ReadFifoQueue => fifo_byte_count(body).map_or(4, |n| n.max(2) + 2),
+            Function::WriteSingleCoil
+            | Function::WriteSingleHoldingRegister
+            | Function::WriteMultipleCoils
+            | Function::WriteMultipleHoldingRegisters
+            | Function::GetCommEventCounter
+            | Function::Diagnostics => 4,
+            Function::MaskWriteRegister => 6,
+            Function::ReadExceptionStatus => 1,
+            Function::ReadDeviceIdentification => {
+                check_mei_type(body.first())?;
+                device_identification_len(body).unwrap_or(body.len() + 1)
+            }
+        }
+    };
+    if body.len() != expected {
+        return Err(ModbusError::InvalidLength(format!(
+            "{:?} response body of {} bytes, expect {}",
+            head.function,
+            body.len(),
+            expected
+        )));
+    }
+    if head.function == Function::ReadFifoQueue && !head.is_exception {
+        check_fifo_count(body)?;
+    }
+    if head.function == Function::ReadFileRecord && !head.is_exception {
+        check_file_records(body)?;
+    }
+    Ok(())
+}
+
+/// Make sure the sub-responses of a read file record response exactly fill its data
+fn check_file_records(body: &[u8]) -> Result<()> {
+    let mut offset = 1;
+    while offset < body.len() {
+        let len = body[offset] as usize;
+        if len.is_multiple_of(2)
+            || body.get(offset + 1) != Some(&FILE_RECORD_REFERENCE_TYPE)
+            || offset + 1 + len > body.len()
+        {
+            return Err(ModbusError::InvalidFrame(format!(
+                "Invalid file record sub-response at byte {}",
+                offset
+            )));
+        }
+        offset += 1 + len;
+    }
+    Ok(())
+}
+
+/// Body length of the normal response to `request`
+///
+/// `None` when the length is only known from the byte count of the response.
+fn response_len(request: &Request) -> Option<usize> {
+    let len = match request {
+        Request::ReadCoils(_, body) => 1 + (body.coils_number as usize).div_ceil(8),
+        Request::ReadDiscreteInputs(_, body) => {
+            1 + (body.discrete_inputs_number as usize).div_ceil(8)
+        }
+        Request::ReadMultipleHoldingRegisters(_, body) => 1 + body.registers_number as usize * 2,
+        Request::ReadInputRegisters(_, body) => 1 + body.registers_number as usize * 2,
+        Request::ReadWriteMultipleRegisters(_, body) => 1 + body.read_number as usize * 2,
+        Request::ReadFileRecord(_, body) => {
+            let data_len: usize =
+                body.records.iter().map(|record| 2 + record.record_length as usize * 2).sum();
+            1 + data_len
+        }
+        Request::WriteSingleCoil(_, _)
+        | Request::WriteSingleHoldingRegister(_, _)
+        | Request::WriteMultipleCoils(_, _)
+        | Request::WriteMultipleHoldingRegisters(_, _)
+        | Request::GetCommEventCounter(_, _)
+        | Request::Diagnostics(_, _) => 4,
+        Request::MaskWriteRegister(_, _) => 6,
+        Request::ReadExceptionStatus(_, _) => 1,
+        Request::ReportServerId(_, _)
+        | Request::ReadDeviceIdentification(_, _)
+        | Request::ReadFifoQueue(_, _) => return None,
+    };
+    Some(len)
+}
+
 impl Decoder for RtuServerCodec {
     type Item = Request;
-    type Error = Error;
+    type Error = ModbusError;
 
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Request>> {
         if src.len() < 2 {
             return Ok(None);
         }
 
-        let mut data_bytes = BytesMut::new();
-        let head_bytes = src.copy_to_bytes(2);
-        data_bytes.put_slice(&(head_bytes.to_vec()));
-        let mut head = Head::rtu_try_from(head_bytes)?;
-
-        let len: usize = {
-            match head.function {
-                Function::ReadCoils
-                | Function::ReadDiscreteInputs
-                | Function::ReadMultipleHoldingRegisters
-                | Function::ReadInputRegisters
-                | Function::WriteSingleCoil
-                | Function::WriteSingleHoldingRegister => 4,
-                Function::WriteMultipleCoils | Function::WriteMultipleHoldingRegisters => {
-                    src.get(4).map_or(0, |&bytes_num| bytes_num as usize + 5)
-                }
+        let mut head = match Head::rtu_try_from(Bytes::copy_from_slice(&src[..2])) {
+            Ok(head) => head,
+            Err(e) => {
+                // Skip the byte that cannot start a frame
+                src.advance(1);
+                return Err(e);
             }
         };
-        if src.len() < len + 2 {
+
+        // Nothing is consumed until the whole frame arrived, which may take several reads
+        let len = match request_body_len(&head.function, &src[2..]) {
+            Some(len) => len,
+            None => return Ok(None),
+        };
+        if src.len() < len + 4 {
             return Ok(None);
         }
+        trace_frame("Received", &[&src[..len + 4]]);
 
-        head.body_length(len as u16);
-        let body_bytes = src.copy_to_bytes(len);
-        data_bytes.put_slice(&(body_bytes.to_vec()));
-        let request = get_request(body_bytes, head);
+        let data_bytes = src.split_to(len + 2).freeze();
         let crc = src.get_u16();
-        if crc::check(&(data_bytes.to_vec()), crc) {
-            return Ok(Some(request));
+        if !crc::check(&data_bytes, crc) {
+            return Err(crc_mismatch(&data_bytes, crc::compute(&data_bytes), crc));
         }
-        return Err(Error::new(
-            InvalidData,
-            format!("Invalid crc code: 0x{:0>2X}", crc),
-        ));
+        head.body_length(len as u16);
+        Ok(Some(get_request(data_bytes.slice(2..), head)))
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Request>> {
+        let item = self.decode(src)?;
+        end_of_stream(item, src)
+    }
+}
+
+/// Body length of the `function` request whose body starts `body`, `None` until enough of it
+/// arrived to tell
+fn request_body_len(function: &Function, body: &[u8]) -> Option<usize> {
+    let len = match function {
+        Function::ReadCoils
+        | Function::ReadDiscreteInputs
+        | Function::ReadMultipleHoldingRegisters
+        | Function::ReadInputRegisters
+        | Function::WriteSingleCoil
+        | Function::WriteSingleHoldingRegister
+        | Function::Diagnostics => 4,
+        Function::WriteMultipleCoils | Function::WriteMultipleHoldingRegisters => {
+            body.get(4).map(|&bytes_num| bytes_num as usize + 5)?
+        }
+        Function::GetCommEventCounter
+        | Function::ReportServerId
+        | Function::ReadExceptionStatus => 0,
+        Function::MaskWriteRegister => 6,
+        Function::ReadWriteMultipleRegisters => {
+            body.get(8).map(|&bytes_num| bytes_num as usize + 9)?
+        }
+        Function::ReadDeviceIdentification => 3,
+        Function::ReadFifoQueue => 2,
+        Function::ReadFileRecord => body.first().map(|&bytes_num| bytes_num as usize + 1)?,
+    };
+    Some(len)
+}
+
+impl Decoder for PduClientCodec {
+    type Item = Response;
+    type Error = ModbusError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Response>> {
+        if src.is_empty() {
+            return Ok(None);
+        }
+
+        let (function, is_exception) = get_function(src[0])?;
+        let mut head = Head::new(0, 0, function, 0, Version::Tcp, is_exception);
+        let len = match response_body_len(&head, &src[1..])? {
+            Some(len) => len,
+            None => return Ok(None),
+        };
+        if src.len() < len + 1 {
+            return Ok(None);
+        }
+        trace_frame("Received", &[&src[..len + 1]]);
+
+        src.advance(1);
+        let body_bytes = src.split_to(len).freeze();
+        head.body_length(len as u16);
+        check_response_body(&head, &body_bytes)?;
+        Ok(Some(get_response(body_bytes, head)))
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Response>> {
+        let item = self.decode(src)?;
+        end_of_stream(item, src)
+    }
+}
+
+impl Decoder for PduServerCodec {
+    type Item = Request;
+    type Error = ModbusError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Request>> {
+        if src.is_empty() {
+            return Ok(None);
+        }
+
+        let (function, is_exception) = get_function(src[0])?;
+        let len = match request_body_len(&function, &src[1..]) {
+            Some(len) => len,
+            None => return Ok(None),
+        };
+        if src.len() < len + 1 {
+            return Ok(None);
+        }
+        trace_frame("Received", &[&src[..len + 1]]);
+
+        src.advance(1);
+        let body_bytes = src.split_to(len).freeze();
+        let head = Head::new(0, 0, function, len as u16, Version::Tcp, is_exception);
+        Ok(Some(get_request(body_bytes, head)))
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Request>> {
+        let item = self.decode(src)?;
+        end_of_stream(item, src)
+    }
+}
+
+impl Decoder for AsciiClientCodec {
+    type Item = Response;
+    type Error = ModbusError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Response>> {
+        let (mut head, body) = match ascii_frame(src)? {
+            Some(frame) => frame,
+            None => return Ok(None),
+        };
+        head.body_length(body.len() as u16);
+        check_response_body(&head, &body)?;
+        Ok(Some(get_response(body, head)))
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Response>> {
+        let item = self.decode(src)?;
+        end_of_stream(item, src)
+    }
+}
+
+impl Decoder for AsciiServerCodec {
+    type Item = Request;
+    type Error = ModbusError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Request>> {
+        let (mut head, body) = match ascii_frame(src)? {
+            Some(frame) => frame,
+            None => return Ok(None),
+        };
+        if request_body_len(&head.function, &body) != Some(body.len()) {
+            return Err(ModbusError::InvalidLength(format!(
+                "{:?} request body of {} bytes",
+                head.function,
+                body.len()
+            )));
+        }
+        head.body_length(body.len() as u16);
+        Ok(Some(get_request(body, head)))
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Request>> {
+        let item = self.decode(src)?;
+        end_of_stream(item, src)
+    }
+}
+
+/// Split the next Modbus ASCII frame off `src` and check its LRC, returning its head and body
+///
+/// Bytes before the `:` starting the frame are dropped. `None` until the `\r\n` ending it
+/// arrived.
+fn ascii_frame(src: &mut BytesMut) -> Result<Option<(Head, Bytes)>> {
+    match src.iter().position(|&byte| byte == b':') {
+        Some(start) => src.advance(start),
+        None => {
+            src.clear();
+            return Ok(None);
+        }
+    }
+    let end = match src.windows(2).position(|window| window == b"\r\n") {
+        Some(end) => end,
+        None => return Ok(None),
+    };
+    let frame = src.split_to(end + 2);
+    trace_frame("Received", &[&frame]);
+
+    let digits = &frame[1..end];
+    let bytes = match decode_hex(digits) {
+        Some(bytes) if bytes.len() >= 3 => bytes,
+        _ => {
+            return Err(ModbusError::InvalidFrame(format!(
+                "Malformed ASCII frame: {}",
+                String::from_utf8_lossy(&frame[..end])
+            )))
+        }
+    };
+    let (data, lrc) = bytes.split_at(bytes.len() - 1);
+    if !lrc::check(data, lrc[0]) {
+        return Err(ModbusError::LrcMismatch {
+            expected: lrc::compute(data),
+            actual: lrc[0],
+            bytes,
+        });
+    }
+    let head = Head::rtu_try_from(Bytes::copy_from_slice(&data[..2]))?;
+    Ok(Some((head, Bytes::copy_from_slice(&data[2..]))))
+}
+
+/// Decode pairs of hex digits, `None` if any is not one or a digit is left over
+fn decode_hex(digits: &[u8]) -> Option<Vec<u8>> {
+    if !digits.len().is_multiple_of(2) {
+        return None;
+    }
+    digits
+        .chunks_exact(2)
+        .map(|pair| {
+            let high = (pair[0] as char).to_digit(16)?;
+            let low = (pair[1] as char).to_digit(16)?;
+            Some((high << 4 | low) as u8)
+        })
+        .collect()
+}
+
+/// Fail with [`ModbusError::UnexpectedEof`] when the stream ended with part of a frame buffered
+fn end_of_stream<T>(item: Option<T>, src: &BytesMut) -> Result<Option<T>> {
+    match item {
+        None if !src.is_empty() => Err(ModbusError::UnexpectedEof),
+        item => Ok(item),
+    }
+}
+
+impl<D> Decoder for ProgressDecoder<D>
+where
+    D: Decoder<Item = Response, Error = ModbusError>,
+{
+    type Item = DecodeEvent;
+    type Error = ModbusError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<DecodeEvent>> {
+        if !self.header_seen {
+            if src.len() < self.header_len {
+                return Ok(None);
+            }
+            self.header_seen = true;
+            return Ok(Some(DecodeEvent::Header));
+        }
+
+        let decoded = self.inner.decode(src);
+        if !matches!(decoded, Ok(None)) {
+            // The next bytes start a new frame, whether this one was decoded or rejected
+            self.header_seen = false;
+            self.reported = 0;
+        }
+        if let Some(response) = decoded? {
+            return Ok(Some(DecodeEvent::Complete(response)));
+        }
+        if src.len() > self.reported {
+            self.reported = src.len();
+            return Ok(Some(DecodeEvent::Progress(self.reported)));
+        }
+        Ok(None)
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<DecodeEvent>> {
+        let event = self.decode(src)?;
+        end_of_stream(event, src)
+    }
+}
+
+/// Error of a RTU frame whose CRC does not match, carrying the frame for inspection
+fn crc_mismatch(data: &[u8], expected: u16, actual: u16) -> ModbusError {
+    let mut bytes = data.to_vec();
+    bytes.extend_from_slice(&actual.to_be_bytes());
+    ModbusError::CrcMismatch {
+        bytes,
+        expected,
+        actual,
     }
 }
 
 impl Decoder for TcpClientCodec {
     type Item = Response;
-    type Error = Error;
+    type Error = ModbusError;
 
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Response>> {
-        if src.len() < 4 {
+        if src.len() < 8 {
+            return Ok(None);
+        }
+        let length = u16::from_be_bytes([src[4], src[5]]) as usize;
+        if length < 2 {
+            return Err(ModbusError::InvalidLength(format!("0x{:0>4X}", length)));
+        }
+        if src.len() < length + 6 {
             return Ok(None);
         }
-        let head = Head::tcp_try_from(src.copy_to_bytes(8))?;
-        let len = head.length as usize - 2;
-        let response = get_response(src.copy_to_bytes(len), head);
+        trace_frame("Received", &[&src[..length + 6]]);
+
+        let mut frame_bytes = src.split_to(length + 6).freeze();
+        if self.lenient
+            && length > 2
+            && get_function(frame_bytes[7]).is_err()
+            && get_function(frame_bytes[8]).is_ok()
+        {
+            warn_stray_byte(frame_bytes[7]);
+            frame_bytes = drop_stray_byte(&frame_bytes);
+            self.recovered_frames += 1;
+        }
+        let head = Head::tcp_try_from(frame_bytes.split_to(8))?;
+        check_response_body(&head, &frame_bytes)?;
+        let mut response = get_response(frame_bytes, head);
+        if self.swap_register_bytes {
+            swap_response_registers(&mut response);
+        }
         Ok(Some(response))
     }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Response>> {
+        let item = self.decode(src)?;
+        end_of_stream(item, src)
+    }
+}
+
+/// Remove the byte following the unit id of a TCP frame, shortening its MBAP length
+fn drop_stray_byte(frame: &[u8]) -> Bytes {
+    let length = u16::from_be_bytes([frame[4], frame[5]]) - 1;
+    let mut dst = BytesMut::with_capacity(frame.len() - 1);
+    dst.put_slice(&frame[..4]);
+    dst.put_u16(length);
+    dst.put_u8(frame[6]);
+    dst.put_slice(&frame[8..]);
+    dst.freeze()
+}
+
+impl TcpClientCodec {
+    /// Decode a read response, borrowing its values from `src` instead of copying them.
+    ///
+    /// Meant for inspecting responses that do not outlive the receive buffer. The values are
+    /// only owned when register bytes have to be swapped. Returns the response along with the
+    /// number of bytes it took up, which the caller then consumes from its buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::borrow::Cow;
+    /// use easy_modbus::codec::TcpClientCodec;
+    ///
+    /// let v = vec![0x00, 0x01, 0x00, 0x00, 0x00, 0x05, 0x01, 0x04, 0x02, 0x10, 0x2F];
+    /// let codec = TcpClientCodec::default();
+    /// let (response, consumed) = codec.decode_cow(&v).unwrap().unwrap();
+    /// assert_eq!(response.values, Cow::Borrowed(&[0x10, 0x2F][..]));
+    /// assert_eq!(consumed, 11);
+    /// ```
+    pub fn decode_cow<'a>(&self, src: &'a [u8]) -> Result<Option<(ReadValues<'a>, usize)>> {
+        if src.len() < 8 {
+            return Ok(None);
+        }
+        let length = u16::from_be_bytes([src[4], src[5]]) as usize;
+        if length < 2 {
+            return Err(ModbusError::InvalidLength(format!("0x{:0>4X}", length)));
+        }
+        if src.len() < length + 6 {
+            return Ok(None);
+        }
+
+        let head = Head::tcp_try_from(&src[..8])?;
+        let body = &src[8..length + 6];
+        check_response_body(&head, body)?;
+        if head.is_exception {
+            return Err(ModbusError::Exception(Exception::from(body[0])));
+        }
+
+        let registers = match head.function {
+            Function::ReadCoils | Function::ReadDiscreteInputs => false,
+            Function::ReadMultipleHoldingRegisters
+            | Function::ReadInputRegisters
+            | Function::ReadWriteMultipleRegisters => true,
+            _ => {
+                return Err(ModbusError::InvalidFrame(format!(
+                    "{:?} response carries no values",
+                    head.function
+                )));
+            }
+        };
+        let values = if registers && self.swap_register_bytes {
+            let mut values = body[1..].to_vec();
+            swap_bytes(&mut values);
+            Cow::Owned(values)
+        } else {
+            Cow::Borrowed(&body[1..])
+        };
+        Ok(Some((ReadValues { head, values }, length + 6)))
+    }
 }
 
 impl Decoder for TcpServerCodec {
     type Item = Request;
-    type Error = Error;
+    type Error = ModbusError;
 
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Request>> {
         if src.len() < 8 {
             return Ok(None);
         }
-        let head = Head::tcp_try_from(src.copy_to_bytes(8))?;
-        let len = head.length as usize - 2;
-        let request = get_request(src.copy_to_bytes(len), head);
+        let length = u16::from_be_bytes([src[4], src[5]]) as usize;
+        if length < 2 {
+            return Err(ModbusError::InvalidLength(format!("0x{:0>4X}", length)));
+        }
+        if src.len() < length + 6 {
+            return Ok(None);
+        }
+        trace_frame("Received", &[&src[..length + 6]]);
+
+        let mut frame_bytes = src.split_to(length + 6).freeze();
+        let head = Head::tcp_try_from(frame_bytes.split_to(8))?;
+        if request_body_len(&head.function, &frame_bytes) != Some(frame_bytes.len()) {
+            return Err(ModbusError::InvalidLength(format!(
+                "{:?} request body of {} bytes",
+                head.function,
+                frame_bytes.len()
+            )));
+        }
+        let request = get_request(frame_bytes, head);
         Ok(Some(request))
     }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Request>> {
+        let item = self.decode(src)?;
+        end_of_stream(item, src)
+    }
+}
+
+/// Decode a frame and report how many bytes of `src` it consumed.
+///
+/// Useful for proxies that forward the exact bytes of each frame.
+///
+/// # Examples
+///
+/// ```
+/// use bytes::BytesMut;
+/// use easy_modbus::codec::{decode_measured, TcpClientCodec};
+///
+/// let v = vec![0x00, 0x01, 0x00, 0x00, 0x00, 0x05, 0x01, 0x04, 0x02, 0x10, 0x2F];
+/// let mut buf = BytesMut::from(&v[..]);
+/// let mut codec = TcpClientCodec::default();
+/// let (_response, consumed) = decode_measured(&mut codec, &mut buf).unwrap().unwrap();
+/// assert_eq!(consumed, 11);
+/// ```
+pub fn decode_measured<D: Decoder>(
+    codec: &mut D,
+    src: &mut BytesMut,
+) -> std::result::Result<Option<(D::Item, usize)>, D::Error> {
+    let before = src.len();
+    let item = codec.decode(src)?;
+    Ok(item.map(|item| (item, before - src.len())))
+}
+
+/// Total length of a RTU response, CRC included, from its first bytes
+///
+/// `data_peek` holds whatever was received after the function code. Returns `None` for an
+/// unsupported function, or when the length depends on bytes not received yet (e.g. the byte
+/// count of a read response), so a manual reader knows to peek further.
+///
+/// # Examples
+///
+/// ```
+/// use easy_modbus::codec::rtu_expected_len;
+///
+/// assert_eq!(rtu_expected_len(0x0B, 0x01, &[]), None);
+/// assert_eq!(rtu_expected_len(0x0B, 0x01, &[0x04]), Some(9));
+/// ```
+pub fn rtu_expected_len(address: u8, function_byte: u8, data_peek: &[u8]) -> Option<usize> {
+    let (function, is_exception) = get_function(function_byte).ok()?;
+    let head = Head::new(0, address, function, 0, Version::Rtu, is_exception);
+    let len = response_body_len(&head, data_peek).ok()??;
+    Some(len + 4)
 }
 
 fn get_request(src: Bytes, head: Head) -> Request {
@@ -172,6 +832,28 @@ fn get_request(src: Bytes, head: Head) -> Request {
             head,
             WriteMultipleHoldingRegistersRequest::from(src),
         ),
+        Function::GetCommEventCounter => {
+            Request::GetCommEventCounter(head, GetCommEventCounterRequest::from(src))
+        }
+        Function::ReportServerId => Request::ReportServerId(head, ReportServerIdRequest::from(src)),
+        Function::MaskWriteRegister => {
+            Request::MaskWriteRegister(head, MaskWriteRegisterRequest::from(src))
+        }
+        Function::ReadWriteMultipleRegisters => Request::ReadWriteMultipleRegisters(
+            head,
+            ReadWriteMultipleRegistersRequest::from(src),
+        ),
+        Function::ReadDeviceIdentification => {
+            Request::ReadDeviceIdentification(head, ReadDeviceIdentificationRequest::from(src))
+        }
+        Function::ReadExceptionStatus => {
+            Request::ReadExceptionStatus(head, ReadExceptionStatusRequest::from(src))
+        }
+        Function::Diagnostics => Request::Diagnostics(head, DiagnosticsRequest::from(src)),
+        Function::ReadFifoQueue => Request::ReadFifoQueue(head, ReadFifoQueueRequest::from(src)),
+        Function::ReadFileRecord => {
+            Request::ReadFileRecord(head, ReadFileRecordRequest::from(src))
+        }
     }
 }
 
@@ -206,6 +888,33 @@ fn get_response(src: Bytes, head: Head) -> Response {
             head,
             WriteMultipleHoldingRegistersResponse::from(src),
         ),
+        Function::GetCommEventCounter => {
+            Response::GetCommEventCounter(head, GetCommEventCounterResponse::from(src))
+        }
+        Function::ReportServerId => {
+            Response::ReportServerId(head, ReportServerIdResponse::from(src))
+        }
+        Function::MaskWriteRegister => {
+            Response::MaskWriteRegister(head, MaskWriteRegisterResponse::from(src))
+        }
+        Function::ReadWriteMultipleRegisters => Response::ReadWriteMultipleRegisters(
+            head,
+            ReadWriteMultipleRegistersResponse::from(src),
+        ),
+        Function::ReadDeviceIdentification => Response::ReadDeviceIdentification(
+            head,
+            ReadDeviceIdentificationResponse::from(src),
+        ),
+        Function::ReadExceptionStatus => {
+            Response::ReadExceptionStatus(head, ReadExceptionStatusResponse::from(src))
+        }
+        Function::Diagnostics => Response::Diagnostics(head, DiagnosticsResponse::from(src)),
+        Function::ReadFifoQueue => {
+            Response::ReadFifoQueue(head, ReadFifoQueueResponse::from(src))
+        }
+        Function::ReadFileRecord => {
+            Response::ReadFileRecord(head, ReadFileRecordResponse::from(src))
+        }
     }
 }
 
@@ -285,6 +994,89 @@ impl From<Bytes> for WriteMultipleHoldingRegistersRequest {
     }
 }
 
+impl From<Bytes> for GetCommEventCounterRequest {
+    fn from(_: Bytes) -> Self {
+        GetCommEventCounterRequest
+    }
+}
+
+impl From<Bytes> for ReadExceptionStatusRequest {
+    fn from(_: Bytes) -> Self {
+        ReadExceptionStatusRequest
+    }
+}
+
+impl From<Bytes> for DiagnosticsRequest {
+    fn from(mut buf: Bytes) -> Self {
+        DiagnosticsRequest {
+            sub_function: buf.get_u16(),
+            data: buf.get_u16(),
+        }
+    }
+}
+
+impl From<Bytes> for ReadFifoQueueRequest {
+    fn from(mut buf: Bytes) -> Self {
+        ReadFifoQueueRequest {
+            fifo_pointer_address: buf.get_u16(),
+        }
+    }
+}
+
+impl From<Bytes> for ReadFileRecordRequest {
+    fn from(buf: Bytes) -> Self {
+        // Each sub-request starts with the reference type, always 6
+        let records = buf[1..]
+            .chunks_exact(7)
+            .map(|sub_request| FileRecord {
+                file_number: u16::from_be_bytes([sub_request[1], sub_request[2]]),
+                record_number: u16::from_be_bytes([sub_request[3], sub_request[4]]),
+                record_length: u16::from_be_bytes([sub_request[5], sub_request[6]]),
+            })
+            .collect();
+        ReadFileRecordRequest { records }
+    }
+}
+
+impl From<Bytes> for ReportServerIdRequest {
+    fn from(_: Bytes) -> Self {
+        ReportServerIdRequest
+    }
+}
+
+impl From<Bytes> for MaskWriteRegisterRequest {
+    fn from(mut buf: Bytes) -> Self {
+        MaskWriteRegisterRequest {
+            reference_address: buf.get_u16(),
+            and_mask: buf.get_u16(),
+            or_mask: buf.get_u16(),
+        }
+    }
+}
+
+impl From<Bytes> for ReadWriteMultipleRegistersRequest {
+    fn from(mut buf: Bytes) -> Self {
+        ReadWriteMultipleRegistersRequest {
+            read_address: buf.get_u16(),
+            read_number: buf.get_u16(),
+            write_address: buf.get_u16(),
+            write_number: buf.get_u16(),
+            bytes_number: buf.get_u8(),
+            values: buf.to_vec(),
+        }
+    }
+}
+
+impl From<Bytes> for ReadDeviceIdentificationRequest {
+    fn from(mut buf: Bytes) -> Self {
+        buf.advance(1);
+        ReadDeviceIdentificationRequest {
+            read_device_id_code: buf.get_u8(),
+            object_id: buf.get_u8(),
+        }
+    }
+}
+
 impl From<Bytes> for ReadCoilsResponse {
     fn from(mut buf: Bytes) -> Self {
         ReadCoilsResponse {
@@ -357,16 +1149,130 @@ impl From<Bytes> for WriteMultipleHoldingRegistersResponse {
     }
 }
 
+impl From<Bytes> for GetCommEventCounterResponse {
+    fn from(mut buf: Bytes) -> Self {
+        GetCommEventCounterResponse {
+            status: buf.get_u16(),
+            event_count: buf.get_u16(),
+        }
+    }
+}
+
+impl From<Bytes> for ReadExceptionStatusResponse {
+    fn from(mut buf: Bytes) -> Self {
+        ReadExceptionStatusResponse {
+            output_data: buf.get_u8(),
+        }
+    }
+}
+
+impl From<Bytes> for DiagnosticsResponse {
+    fn from(mut buf: Bytes) -> Self {
+        DiagnosticsResponse {
+            sub_function: buf.get_u16(),
+            data: buf.get_u16(),
+        }
+    }
+}
+
+impl From<Bytes> for ReadFifoQueueResponse {
+    fn from(mut buf: Bytes) -> Self {
+        let byte_count = buf.get_u16();
+        let fifo_count = buf.get_u16();
+        let values = buf.chunks_exact(2).map(|v| u16::from_be_bytes([v[0], v[1]])).collect();
+        ReadFifoQueueResponse {
+            byte_count,
+            fifo_count,
+            values,
+        }
+    }
+}
+
+impl From<Bytes> for ReadFileRecordResponse {
+    fn from(mut buf: Bytes) -> Self {
+        buf.advance(1);
+        let mut records = Vec::new();
+        while buf.has_remaining() {
+            let len = (buf.get_u8() as usize).min(buf.len());
+            // Each sub-response starts with the reference type, always 6
+            let sub_response = buf.split_to(len);
+            let values = sub_response.get(1..).unwrap_or_default();
+            records.push(
+                values
+                    .chunks_exact(2)
+                    .map(|v| u16::from_be_bytes([v[0], v[1]]))
+                    .collect(),
+            );
+        }
+        ReadFileRecordResponse { records }
+    }
+}
+
+impl From<Bytes> for ReportServerIdResponse {
+    fn from(mut buf: Bytes) -> Self {
+        let byte_count = buf.get_u8();
+        ReportServerIdResponse {
+            byte_count,
+            // Some devices leave the run indicator status out of the byte count
+            server_id: buf.split_to((byte_count as usize).saturating_sub(1)).to_vec(),
+            run_indicator_status: buf.get_u8(),
+        }
+    }
+}
+
+impl From<Bytes> for MaskWriteRegisterResponse {
+    fn from(mut buf: Bytes) -> Self {
+        MaskWriteRegisterResponse {
+            reference_address: buf.get_u16(),
+            and_mask: buf.get_u16(),
+            or_mask: buf.get_u16(),
+        }
+    }
+}
+
+impl From<Bytes> for ReadWriteMultipleRegistersResponse {
+    fn from(mut buf: Bytes) -> Self {
+        ReadWriteMultipleRegistersResponse {
+            bytes_number: buf.get_u8(),
+            values: buf.to_vec(),
+        }
+    }
+}
+
+impl From<Bytes> for ReadDeviceIdentificationResponse {
+    fn from(mut buf: Bytes) -> Self {
+        buf.advance(1);
+        let read_device_id_code = buf.get_u8();
+        let conformity_level = buf.get_u8();
+        let more_follows = buf.get_u8() == 0xFF;
+        let next_object_id = buf.get_u8();
+        let objects = (0..buf.get_u8())
+            .map(|_| {
+                let id = buf.get_u8();
+                let len = buf.get_u8() as usize;
+                (id, buf.split_to(len).to_vec())
+            })
+            .collect();
+        ReadDeviceIdentificationResponse {
+            read_device_id_code,
+            conformity_level,
+            more_follows,
+            next_object_id,
+            objects,
+        }
+    }
+}
+
 impl From<Bytes> for ExceptionResponse {
     fn from(mut buf: Bytes) -> Self {
         ExceptionResponse {
-            exception: Exception::try_from(buf.get_u8()).unwrap(),
+            exception: Exception::from(buf.get_u8()),
         }
     }
 }
 
 impl Head {
-    fn tcp_try_from(mut buf: Bytes) -> Result<Self> {
+    fn tcp_try_from(mut buf: impl Buf) -> Result<Self> {
         let tid = buf.get_u16();
         let pid = buf.get_u16();
         let length = buf.get_u16();
@@ -383,7 +1289,7 @@ impl Head {
         })
     }
 
-    fn rtu_try_from(mut buf: Bytes) -> Result<Self> {
+    fn rtu_try_from(mut buf: impl Buf) -> Result<Self> {
         let uid = buf.get_u8();
         let (function, is_exception) = get_function(buf.get_u8())?;
         Ok(Head {
@@ -398,24 +1304,14 @@ impl Head {
     }
 }
 
-impl TryFrom<u8> for Exception {
-    type Error = Error;
-
-    fn try_from(value: u8) -> Result<Self> {
-        match Exception::from_code(value) {
-            None => {
-                return Err(Error::new(
-                    InvalidData,
-                    format!("Invalid Exception code: 0x{:0>2X}", value),
-                ));
-            }
-            Some(exception) => Ok(exception),
-        }
+impl From<u8> for Exception {
+    fn from(value: u8) -> Self {
+        Exception::from_code(value).unwrap_or(Exception::Unknown(value))
     }
 }
 
 impl TryFrom<u8> for Function {
-    type Error = Error;
+    type Error = ModbusError;
     fn try_from(value: u8) -> Result<Self> {
         let func = match value {
             0x01 => Function::ReadCoils,
@@ -424,13 +1320,19 @@ impl TryFrom<u8> for Function {
             0x04 => Function::ReadInputRegisters,
             0x05 => Function::WriteSingleCoil,
             0x06 => Function::WriteSingleHoldingRegister,
+            0x07 => Function::ReadExceptionStatus,
+            0x08 => Function::Diagnostics,
+            0x0B => Function::GetCommEventCounter,
             0x0F => Function::WriteMultipleCoils,
             0x10 => Function::WriteMultipleHoldingRegisters,
+            0x11 => Function::ReportServerId,
+            0x14 => Function::ReadFileRecord,
+            0x16 => Function::MaskWriteRegister,
+            0x17 => Function::ReadWriteMultipleRegisters,
+            0x18 => Function::ReadFifoQueue,
+            0x2B => Function::ReadDeviceIdentification,
             _ => {
-                return Err(Error::new(
-                    Exception::IllegalFunction.as_error_kind(),
-                    format!("Invalid function code: 0x{:0>2X}", value),
-                ));
+                return Err(ModbusError::InvalidFunction(value));
             }
         };
         Ok(func)
@@ -452,11 +1354,13 @@ fn get_function(function_code: u8) -> Result<(Function, bool)> {
 #[cfg(test)]
 mod rtu_client_decoder_test {
     use bytes::BytesMut;
-    use tokio_util::codec::Decoder;
+    use tokio_util::codec::{Decoder, Encoder};
 
-    use crate::codec::RtuClientCodec;
-    use crate::frame::{Exception, Function};
-    use crate::Frame;
+    use crate::codec::{rtu_expected_len, RtuClientCodec, RtuOverTcpClientCodec, RtuServerCodec};
+    use crate::error::ModbusError;
+    use crate::frame::{DiagnosticsSubFunction, Exception, Function};
+    use crate::util::crc::Checksum;
+    use crate::{Frame, Response};
 
     #[test]
     fn read_coils_response_test() {
@@ -517,48 +1421,355 @@ mod rtu_client_decoder_test {
     }
 
     #[test]
-    fn write_single_holding_register_response_test() {
+    fn write_single_holding_register_response_test() {
+        let mut codec = RtuClientCodec::default();
+        let v: Vec<u8> = vec![0x0B, 0x006, 0x000, 0x004, 0x0AB, 0x0CD, 0x076, 0x004];
+        let mut buf = BytesMut::from(&v[..]);
+        let response_l = codec.decode(&mut buf).unwrap().unwrap();
+        let frame = Frame::rtu();
+        let response_r = frame.write_single_holding_register_response(0x0B, 0x0004, 0xABCD);
+        assert_eq!(response_l, response_r);
+    }
+
+    #[test]
+    fn mask_write_register_response_test() {
+        let mut codec = RtuClientCodec::default();
+        let v: Vec<u8> = vec![0x0B, 0x16, 0x00, 0x04, 0x00, 0xF2, 0x00, 0x25, 0xE7, 0x91];
+        let mut buf = BytesMut::from(&v[..]);
+        let response_l = codec.decode(&mut buf).unwrap().unwrap();
+        let frame = Frame::rtu();
+        let response_r = frame.mask_write_register_response(0x0B, 0x0004, 0x00F2, 0x0025);
+        assert_eq!(response_l, response_r);
+    }
+
+    #[test]
+    fn read_write_multiple_registers_response_test() {
+        let mut codec = RtuClientCodec::default();
+        let v: Vec<u8> = vec![
+            0x0B, 0x17, 0x0C, 0x00, 0xFE, 0x0A, 0xCD, 0x00, 0x01, 0x00, 0x03, 0x00, 0x0D, 0x00,
+            0xFF, 0x97, 0x7E,
+        ];
+        let mut buf = BytesMut::from(&v[..]);
+        let response_l = codec.decode(&mut buf).unwrap().unwrap();
+        let frame = Frame::rtu();
+        let values = vec![
+            0x00, 0xFE, 0x0A, 0xCD, 0x00, 0x01, 0x00, 0x03, 0x00, 0x0D, 0x00, 0xFF,
+        ];
+        let response_r = frame.read_write_multiple_registers_response(0x0B, values);
+        assert_eq!(response_l, response_r);
+    }
+
+    #[test]
+    fn write_multiple_coils_response_test() {
+        let mut codec = RtuClientCodec::default();
+        let v: Vec<u8> = vec![0x0B, 0x0F, 0x00, 0x1B, 0x00, 0x09, 0xE5, 0x60];
+        let mut buf = BytesMut::from(&v[..]);
+        let response_l = codec.decode(&mut buf).unwrap().unwrap();
+        let frame = Frame::rtu();
+        let response_r = frame.write_multiple_coils_response(0x0B, 0x001B, 0x0009);
+        assert_eq!(response_l, response_r);
+    }
+
+    #[test]
+    fn write_multiple_holding_registers_response_test() {
+        let mut codec = RtuClientCodec::default();
+        let v: Vec<u8> = vec![0x0B, 0x10, 0x00, 0x12, 0x00, 0x02, 0xE1, 0x67];
+        let mut buf = BytesMut::from(&v[..]);
+        let response_l = codec.decode(&mut buf).unwrap().unwrap();
+        let frame = Frame::rtu();
+        let response_r = frame.write_multiple_holding_registers_response(0x0B, 0x0012, 0x0002);
+        assert_eq!(response_l, response_r);
+    }
+
+    #[test]
+    fn get_comm_event_counter_response_test() {
+        let mut codec = RtuClientCodec::default();
+        let v: Vec<u8> = vec![0x0B, 0x0B, 0x00, 0x00, 0x01, 0x08, 0xA4, 0xF7];
+        let mut buf = BytesMut::from(&v[..]);
+        let response_l = codec.decode(&mut buf).unwrap().unwrap();
+        let frame = Frame::rtu();
+        let response_r = frame.get_comm_event_counter_response(0x0B, 0x0000, 0x0108);
+        assert_eq!(response_l, response_r);
+    }
+
+    #[test]
+    fn report_server_id_response_test() {
+        let mut codec = RtuClientCodec::default();
+        let v: Vec<u8> = vec![0x0B, 0x11, 0x03, 0x2A, 0x01, 0xFF, 0x9D, 0x3F];
+        let mut buf = BytesMut::from(&v[..]);
+        let response_l = codec.decode(&mut buf).unwrap().unwrap();
+        let frame = Frame::rtu();
+        let response_r = frame.report_server_id_response(0x0B, vec![0x2A, 0x01], 0xFF);
+        assert_eq!(response_l, response_r);
+    }
+
+    #[test]
+    fn read_exception_status_response_test() {
+        let mut codec = RtuClientCodec::default();
+        let v: Vec<u8> = vec![0x11, 0x07, 0x6D, 0xE2, 0x18];
+        let mut buf = BytesMut::from(&v[..]);
+        let response_l = codec.decode(&mut buf).unwrap().unwrap();
+        let frame = Frame::rtu();
+        let response_r = frame.read_exception_status_response(0x11, 0x6D);
+        assert_eq!(response_l, response_r);
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn rtu_over_tcp_split_response_test() {
+        let mut codec = RtuOverTcpClientCodec::default();
+        let v: Vec<u8> = vec![0x0B, 0x01, 0x04, 0xCD, 0x6B, 0xB2, 0x7F, 0x2B, 0xE1];
+        let mut buf = BytesMut::from(&v[..4]);
+        assert!(codec.decode(&mut buf).unwrap().is_none());
+        assert_eq!(buf.len(), 4);
+        buf.extend_from_slice(&v[4..]);
+        let response_l = codec.decode(&mut buf).unwrap().unwrap();
+        let response_r = Frame::rtu().read_coils_response(0x0B, vec![0xCD, 0x6B, 0xB2, 0x7F]);
+        assert_eq!(response_l, response_r);
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn diagnostics_response_test() {
+        let mut codec = RtuClientCodec::default();
+        let v: Vec<u8> = vec![0x11, 0x08, 0x00, 0x00, 0xA5, 0x37, 0xD8, 0x1D];
+        let mut buf = BytesMut::from(&v[..]);
+        let response_l = codec.decode(&mut buf).unwrap().unwrap();
+        let frame = Frame::rtu();
+        let response_r = frame.diagnostics_response(0x11, 0x0000, 0xA537);
+        assert_eq!(response_l, response_r);
+        match response_l {
+            Response::Diagnostics(_, body) => {
+                assert_eq!(body.sub_function(), DiagnosticsSubFunction::ReturnQueryData);
+                assert_eq!(*body.get_data(), 0xA537);
+            }
+            response => panic!("unexpected response {:?}", response),
+        }
+    }
+
+    #[test]
+    fn oversized_bytes_number_test() {
+        let mut codec = RtuClientCodec::default();
+        let v: Vec<u8> = vec![0x0B, 0x03, 0x0A, 0xAE, 0x41, 0x56, 0x52];
+        let mut buf = BytesMut::from(&v[..]);
+        assert!(codec.decode(&mut buf).unwrap().is_none());
+        assert_eq!(buf.len(), 7);
+    }
+
+    #[test]
+    fn mbap_header_test() {
+        let mut codec = RtuClientCodec::default();
+        let v: Vec<u8> = vec![0x0B, 0x04, 0x02, 0x10, 0x2F, 0x6D, 0x2D];
+        let mut buf = BytesMut::from(&v[..]);
+        let response = codec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(response.mbap_header(), None);
+    }
+
+    #[test]
+    fn decode_with_request_test() {
+        let mut codec = RtuClientCodec::default();
+        let frame = Frame::rtu();
+        let request = frame.read_coils_request(0x0B, 0x001D, 0x001F);
+        let v: Vec<u8> = vec![
+            0x0B, 0x01, 0x04, 0xCD, 0x6B, 0xB2, 0x7F, 0x2B, 0xE1, 0x00, 0xFF, 0x13,
+        ];
+        let mut buf = BytesMut::from(&v[..]);
+        let response_l = codec.decode_with_request(&mut buf, &request).unwrap().unwrap();
+        let response_r = frame.read_coils_response(0x0B, vec![0xCD, 0x6B, 0xB2, 0x7F]);
+        assert_eq!(response_l, response_r);
+        assert_eq!(buf.to_vec(), vec![0x00, 0xFF, 0x13]);
+    }
+
+    #[test]
+    fn decode_with_request_partial_test() {
+        let mut codec = RtuClientCodec::default();
+        let request = Frame::rtu().read_coils_request(0x0B, 0x001D, 0x001F);
+        let v: Vec<u8> = vec![0x0B, 0x01, 0x04, 0xCD, 0x6B];
+        let mut buf = BytesMut::from(&v[..]);
+        assert!(codec.decode_with_request(&mut buf, &request).unwrap().is_none());
+        assert_eq!(buf.len(), 5);
+    }
+
+    #[test]
+    fn decode_with_request_exception_test() {
+        let mut codec = RtuClientCodec::default();
+        let request = Frame::rtu().read_coils_request(0x0A, 0x0000, 0x0008);
+        let v: Vec<u8> = vec![0x0A, 0x81, 0x02, 0xB0, 0x53, 0x00];
+        let mut buf = BytesMut::from(&v[..]);
+        let response_l = codec.decode_with_request(&mut buf, &request).unwrap().unwrap();
+        let response_r = Frame::rtu().exception_response(
+            0x0A,
+            Function::ReadCoils,
+            Exception::IllegalDataAddress,
+        );
+        assert_eq!(response_l, response_r);
+    }
+
+    #[test]
+    fn read_holding_registers_exception_test() {
+        let mut codec = RtuClientCodec::default();
+        let v: Vec<u8> = vec![0x01, 0x83, 0x02, 0xC0, 0xF1];
+        let response = codec.decode(&mut BytesMut::from(&v[..])).unwrap().unwrap();
+        assert_eq!(response.head().function, Function::ReadMultipleHoldingRegisters);
+        assert_eq!(response.as_exception(), Some(&Exception::IllegalDataAddress));
+    }
+
+    #[test]
+    fn exception_response_test() {
+        let mut codec = RtuClientCodec::default();
+        let v: Vec<u8> = vec![0x0A, 0x81, 0x02, 0xB0, 0x53];
+        let mut buf = BytesMut::from(&v[..]);
+        let response_l = codec.decode(&mut buf).unwrap().unwrap();
+        let frame = Frame::rtu();
+        let response_r =
+            frame.exception_response(0x0A, Function::ReadCoils, Exception::IllegalDataAddress);
+        assert_eq!(response_l, response_r);
+    }
+
+    #[test]
+    fn unknown_exception_response_test() {
+        let mut codec = RtuClientCodec::default();
+        let v: Vec<u8> = vec![0x0A, 0x81, 0x0C, 0x31, 0x97];
+        let mut buf = BytesMut::from(&v[..]);
+        let response_l = codec.decode(&mut buf).unwrap().unwrap();
+        let frame = Frame::rtu();
+        let response_r =
+            frame.exception_response(0x0A, Function::ReadCoils, Exception::Unknown(0x0C));
+        assert_eq!(response_l, response_r);
+
+        let mut dst = BytesMut::new();
+        RtuServerCodec.encode(response_l, &mut dst).unwrap();
+        assert_eq!(dst.to_vec(), v);
+    }
+
+    #[test]
+    fn rtu_expected_len_test() {
+        // Read coils needs the byte count
+        assert_eq!(rtu_expected_len(0x0B, 0x01, &[]), None);
+        assert_eq!(rtu_expected_len(0x0B, 0x01, &[0x04, 0xCD]), Some(9));
+        // Write responses echo a fixed length request
+        assert_eq!(rtu_expected_len(0x0B, 0x05, &[]), Some(8));
+        assert_eq!(rtu_expected_len(0x0B, 0x10, &[0x00]), Some(8));
+        assert_eq!(rtu_expected_len(0x0B, 0x81, &[]), Some(5));
+        assert_eq!(rtu_expected_len(0x0B, 0x42, &[0x04]), None);
+    }
+
+    #[test]
+    fn stats_test() {
+        let mut codec = RtuClientCodec::default();
+        let v: Vec<u8> = vec![
+            0x0B, 0x01, 0x04, 0xCD, 0x6B, 0xB2, 0x7E, 0x2B, 0xE1, 0x0B, 0x01, 0x04, 0xCD, 0x6B,
+            0xB2, 0x7F, 0x2B, 0xE1,
+        ];
+        let mut buf = BytesMut::from(&v[..]);
+        assert!(codec.decode(&mut buf).is_err());
+
+        let response_l = codec.decode(&mut buf).unwrap().unwrap();
+        let response_r = Frame::rtu().read_coils_response(0x0B, vec![0xCD, 0x6B, 0xB2, 0x7F]);
+        assert_eq!(response_l, response_r);
+
+        let stats = codec.stats();
+        assert_eq!(stats.crc_errors, 1);
+        assert_eq!(stats.resyncs, 1);
+        assert_eq!(stats.bytes_discarded, 9);
+    }
+
+    #[derive(Debug)]
+    struct ZeroChecksum;
+
+    impl Checksum for ZeroChecksum {
+        fn compute(&self, _data: &[u8]) -> u16 {
+            0x0000
+        }
+    }
+
+    #[test]
+    fn custom_checksum_test() {
+        let v: Vec<u8> = vec![0x0B, 0x01, 0x04, 0xCD, 0x6B, 0xB2, 0x7F, 0x00, 0x00];
+
+        let mut codec = RtuClientCodec::with_checksum(ZeroChecksum);
+        let response_l = codec.decode(&mut BytesMut::from(&v[..])).unwrap().unwrap();
+        let response_r = Frame::rtu().read_coils_response(0x0B, vec![0xCD, 0x6B, 0xB2, 0x7F]);
+        assert_eq!(response_l, response_r);
+
+        let mut codec = RtuClientCodec::default();
+        assert!(codec.decode(&mut BytesMut::from(&v[..])).is_err());
+    }
+
+    #[test]
+    fn crc_mismatch_test() {
         let mut codec = RtuClientCodec::default();
-        let v: Vec<u8> = vec![0x0B, 0x006, 0x000, 0x004, 0x0AB, 0x0CD, 0x076, 0x004];
-        let mut buf = BytesMut::from(&v[..]);
-        let response_l = codec.decode(&mut buf).unwrap().unwrap();
-        let frame = Frame::rtu();
-        let response_r = frame.write_single_holding_register_response(0x0B, 0x0004, 0xABCD);
-        assert_eq!(response_l, response_r);
+        let v: Vec<u8> = vec![0x0B, 0x01, 0x04, 0xCD, 0x6B, 0xB2, 0x7E, 0x2B, 0xE1];
+        let err = codec.decode(&mut BytesMut::from(&v[..])).unwrap_err();
+        match err {
+            ModbusError::CrcMismatch {
+                bytes,
+                expected,
+                actual,
+            } => {
+                assert_eq!(bytes, v);
+                assert_eq!(expected, crate::util::crc::compute(&v[..7]));
+                assert_eq!(actual, 0x2BE1);
+            }
+            e => panic!("unexpected error {:?}", e),
+        }
     }
 
     #[test]
-    fn write_multiple_coils_response_test() {
+    fn crc_mismatch_recovery_test() {
         let mut codec = RtuClientCodec::default();
-        let v: Vec<u8> = vec![0x0B, 0x0F, 0x00, 0x1B, 0x00, 0x09, 0xE5, 0x60];
+        let good: Vec<u8> = vec![0x0B, 0x01, 0x04, 0xCD, 0x6B, 0xB2, 0x7F, 0x2B, 0xE1];
+        let mut v = good.clone();
+        v[5] ^= 0x10;
+        v.extend_from_slice(&good);
         let mut buf = BytesMut::from(&v[..]);
+
+        assert!(codec.decode(&mut buf).is_err());
         let response_l = codec.decode(&mut buf).unwrap().unwrap();
-        let frame = Frame::rtu();
-        let response_r = frame.write_multiple_coils_response(0x0B, 0x001B, 0x0009);
+        let response_r = Frame::rtu().read_coils_response(0x0B, vec![0xCD, 0x6B, 0xB2, 0x7F]);
         assert_eq!(response_l, response_r);
+        assert!(buf.is_empty());
+        assert_eq!(codec.stats().crc_errors, 1);
     }
 
     #[test]
-    fn write_multiple_holding_registers_response_test() {
+    fn equality_ignores_crc_test() {
+        let response = Frame::rtu().read_coils_response(0x0B, vec![0xCD, 0x6B, 0xB2, 0x7F]);
+        let mut v = BytesMut::new();
+        RtuServerCodec.encode(response.clone(), &mut v).unwrap();
+        let mut buf = v.clone();
+        let response_l = RtuClientCodec::default().decode(&mut buf).unwrap().unwrap();
+
+        // Same content closed with the CRC bytes swapped, as sent by legacy peers
+        let len = v.len();
+        v.swap(len - 2, len - 1);
         let mut codec = RtuClientCodec::default();
-        let v: Vec<u8> = vec![0x0B, 0x10, 0x00, 0x12, 0x00, 0x02, 0xE1, 0x67];
-        let mut buf = BytesMut::from(&v[..]);
-        let response_l = codec.decode(&mut buf).unwrap().unwrap();
-        let frame = Frame::rtu();
-        let response_r = frame.write_multiple_holding_registers_response(0x0B, 0x0012, 0x0002);
+        codec.set_legacy_crc_order(true);
+        let response_r = codec.decode(&mut v).unwrap().unwrap();
+
         assert_eq!(response_l, response_r);
+        assert_eq!(response_l, response);
     }
 
     #[test]
-    fn exception_response_test() {
+    fn read_device_identification_response_test() {
+        let objects = vec![(0x00, b"Company".to_vec()), (0x01, b"Product".to_vec())];
+        let response_r =
+            Frame::rtu().read_device_identification_response(0x0B, 0x01, 0x01, Some(0x02), objects);
+        let mut v = BytesMut::new();
+        RtuServerCodec.encode(response_r.clone(), &mut v).unwrap();
+
+        // The frame length is only known once the whole object list arrived
         let mut codec = RtuClientCodec::default();
-        let v: Vec<u8> = vec![0x0A, 0x81, 0x02, 0xB0, 0x53];
-        let mut buf = BytesMut::from(&v[..]);
+        let mut buf = BytesMut::new();
+        for &byte in &v[..v.len() - 1] {
+            buf.extend_from_slice(&[byte]);
+            assert!(codec.decode(&mut buf).unwrap().is_none());
+        }
+        buf.extend_from_slice(&v[v.len() - 1..]);
         let response_l = codec.decode(&mut buf).unwrap().unwrap();
-        let frame = Frame::rtu();
-        let response_r =
-            frame.exception_response(0x0A, Function::ReadCoils, Exception::IllegalDataAddress);
         assert_eq!(response_l, response_r);
+        assert!(buf.is_empty());
     }
 }
 
@@ -567,8 +1778,12 @@ mod tcp_client_decoder_test {
     use bytes::BytesMut;
     use tokio_util::codec::Decoder;
 
-    use crate::{codec::TcpClientCodec, Frame};
-    use crate::frame::{Exception, Function};
+    use std::borrow::Cow;
+
+    use crate::{codec::TcpClientCodec, Frame, Response};
+    use crate::codec::decode_measured;
+    use crate::error::ModbusError;
+    use crate::frame::{Exception, Function, MbapHeader};
 
     #[test]
     fn read_coils_response_test() {
@@ -676,6 +1891,15 @@ mod tcp_client_decoder_test {
         assert_eq!(response_l, response_r);
     }
 
+    #[test]
+    fn read_holding_registers_exception_test() {
+        let mut codec = TcpClientCodec::default();
+        let v: Vec<u8> = vec![0x00, 0x01, 0x00, 0x00, 0x00, 0x03, 0x01, 0x83, 0x02];
+        let response = codec.decode(&mut BytesMut::from(&v[..])).unwrap().unwrap();
+        assert_eq!(response.head().function, Function::ReadMultipleHoldingRegisters);
+        assert_eq!(response.as_exception(), Some(&Exception::IllegalDataAddress));
+    }
+
     #[test]
     fn exception_response_test() {
         let mut codec = TcpClientCodec::default();
@@ -687,15 +1911,301 @@ mod tcp_client_decoder_test {
             frame.exception_response(0x0A, Function::ReadCoils, Exception::IllegalDataAddress);
         assert_eq!(response_l, response_r);
     }
+
+    #[test]
+    fn report_server_id_response_test() {
+        let mut codec = TcpClientCodec::default();
+        let v: Vec<u8> = vec![
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x06, 0x01, 0x11, 0x03, 0x2A, 0x01, 0xFF,
+        ];
+        let mut buf = BytesMut::from(&v[..8]);
+        assert!(codec.decode(&mut buf).unwrap().is_none());
+        buf.extend_from_slice(&v[8..]);
+        let response_l = codec.decode(&mut buf).unwrap().unwrap();
+        let frame = Frame::tcp();
+        let response_r = frame.report_server_id_response(0x01, vec![0x2A, 0x01], 0xFF);
+        assert_eq!(response_l, response_r);
+    }
+
+    #[test]
+    fn report_server_id_zero_byte_count_test() {
+        let mut codec = TcpClientCodec::default();
+        let v: Vec<u8> = vec![0x00, 0x01, 0x00, 0x00, 0x00, 0x04, 0x01, 0x11, 0x00, 0xFF];
+        let mut buf = BytesMut::from(&v[..]);
+        match codec.decode(&mut buf).unwrap().unwrap() {
+            Response::ReportServerId(_, body) => {
+                assert!(body.get_server_id().is_empty());
+                assert_eq!(*body.get_run_indicator_status(), 0xFF);
+            }
+            response => panic!("unexpected response {:?}", response),
+        }
+    }
+
+    #[test]
+    fn read_fifo_queue_response_test() {
+        let mut codec = TcpClientCodec::default();
+        let v: Vec<u8> = vec![
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x0A, 0x01, 0x18, 0x00, 0x06, 0x00, 0x02, 0x01, 0xB8,
+            0x12, 0x84,
+        ];
+        let mut buf = BytesMut::from(&v[..]);
+        let response_l = codec.decode(&mut buf).unwrap().unwrap();
+        let frame = Frame::tcp();
+        let response_r = frame.read_fifo_queue_response(0x01, vec![0x01B8, 0x1284]);
+        assert_eq!(response_l, response_r);
+        match response_l {
+            Response::ReadFifoQueue(_, body) => {
+                assert_eq!(*body.get_fifo_count(), 2);
+                assert_eq!(body.get_values(), &vec![0x01B8, 0x1284]);
+            }
+            response => panic!("unexpected response {:?}", response),
+        }
+    }
+
+    #[test]
+    fn read_file_record_response_test() {
+        let mut codec = TcpClientCodec::default();
+        let v: Vec<u8> = vec![
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x0F, 0x01, 0x14, 0x0C, 0x05, 0x06, 0x0D, 0xFE, 0x00,
+            0x20, 0x05, 0x06, 0x33, 0xCD, 0x00, 0x40,
+        ];
+        let mut buf = BytesMut::from(&v[..]);
+        let response_l = codec.decode(&mut buf).unwrap().unwrap();
+        let records = vec![vec![0x0DFE, 0x0020], vec![0x33CD, 0x0040]];
+        let response_r = Frame::tcp().read_file_record_response(0x01, records.clone());
+        assert_eq!(response_l, response_r);
+        match response_l {
+            Response::ReadFileRecord(_, body) => assert_eq!(body.get_records(), &records),
+            response => panic!("unexpected response {:?}", response),
+        }
+
+        // The first sub-response claims 4 bytes, leaving half a register to the second
+        let mut v = v;
+        v[9] = 0x04;
+        let mut buf = BytesMut::from(&v[..]);
+        let err = codec.decode(&mut buf).unwrap_err();
+        assert!(matches!(err, ModbusError::InvalidFrame(_)));
+    }
+
+    #[test]
+    fn read_fifo_queue_overflow_test() {
+        let mut codec = TcpClientCodec::default();
+        // 32 values, one more than the specification allows
+        let mut v: Vec<u8> = vec![
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x46, 0x01, 0x18, 0x00, 0x42, 0x00, 0x20,
+        ];
+        v.extend_from_slice(&[0x00; 64]);
+        let mut buf = BytesMut::from(&v[..]);
+        let err = codec.decode(&mut buf).unwrap_err();
+        assert!(matches!(err, ModbusError::InvalidLength(_)));
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn typed_error_test() {
+        let mut codec = TcpClientCodec::default();
+        let v: Vec<u8> = vec![0x00, 0x01, 0x00, 0x00, 0x00, 0x03, 0x01, 0x0C, 0x00];
+        let err = codec.decode(&mut BytesMut::from(&v[..])).unwrap_err();
+        assert!(matches!(err, ModbusError::InvalidFunction(0x0C)));
+
+        let v: Vec<u8> = vec![0x00, 0x01, 0x00, 0x00, 0x00, 0x05, 0x01, 0x04, 0x02];
+        let mut buf = BytesMut::from(&v[..]);
+        assert!(codec.decode(&mut buf).unwrap().is_none());
+        let err = codec.decode_eof(&mut buf).unwrap_err();
+        assert!(matches!(err, ModbusError::UnexpectedEof));
+        assert!(codec.decode_eof(&mut BytesMut::new()).unwrap().is_none());
+    }
+
+    #[test]
+    fn read_device_identification_response_test() {
+        let mut codec = TcpClientCodec::default();
+        let mut v: Vec<u8> = vec![
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x1A, 0x01, 0x2B, 0x0E, 0x01, 0x01, 0xFF, 0x02, 0x02,
+        ];
+        v.extend_from_slice(&[0x00, 0x07]);
+        v.extend_from_slice(b"Company");
+        v.extend_from_slice(&[0x01, 0x07]);
+        v.extend_from_slice(b"Product");
+        let mut buf = BytesMut::from(&v[..]);
+        let response_l = codec.decode(&mut buf).unwrap().unwrap();
+        let objects = vec![(0x00, b"Company".to_vec()), (0x01, b"Product".to_vec())];
+        let frame = Frame::tcp();
+        let response_r =
+            frame.read_device_identification_response(0x01, 0x01, 0x01, Some(0x02), objects);
+        assert_eq!(response_l, response_r);
+        match response_l {
+            Response::ReadDeviceIdentification(_, body) => {
+                assert!(*body.get_more_follows());
+                assert_eq!(*body.get_next_object_id(), 0x02);
+            }
+            response => panic!("unexpected response {:?}", response),
+        }
+    }
+
+    #[test]
+    fn swap_register_bytes_test() {
+        let mut codec = TcpClientCodec::default();
+        codec.set_swap_register_bytes(true);
+        let v: Vec<u8> = vec![
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x07, 0x01, 0x03, 0x04, 0x12, 0x34, 0x56, 0x78,
+        ];
+        let mut buf = BytesMut::from(&v[..]);
+        let response_l = codec.decode(&mut buf).unwrap().unwrap();
+        let frame = Frame::tcp();
+        let response_r = frame.read_holding_register_response(0x01, vec![0x34, 0x12, 0x78, 0x56]);
+        assert_eq!(response_l, response_r);
+    }
+
+    #[test]
+    fn oversized_bytes_number_test() {
+        let mut codec = TcpClientCodec::default();
+        let v: Vec<u8> = vec![
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x07, 0x01, 0x03, 0x0A, 0xAE, 0x41, 0x56, 0x52,
+        ];
+        let mut buf = BytesMut::from(&v[..]);
+        assert!(codec.decode(&mut buf).is_err());
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn concatenated_frames_test() {
+        let mut codec = TcpClientCodec::default();
+        let v: Vec<u8> = vec![
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x05, 0x01, 0x04, 0x02, 0x10, 0x2F, 0x00, 0x02, 0x00,
+            0x00, 0x00, 0x07, 0x01, 0x03, 0x04, 0x00, 0x01, 0x00, 0x02, 0x00, 0x03, 0x00, 0x00,
+            0x00, 0x04, 0x01, 0x01, 0x01, 0x05,
+        ];
+        let mut buf = BytesMut::from(&v[..]);
+
+        let response = codec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(response.to_string(), "00 01 00 00 00 05 01 04 02 10 2F");
+        assert_eq!(buf.len(), 23);
+
+        let response = codec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(
+            response.to_string(),
+            "00 02 00 00 00 07 01 03 04 00 01 00 02"
+        );
+        assert_eq!(buf.len(), 10);
+
+        let response = codec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(response.to_string(), "00 03 00 00 00 04 01 01 01 05");
+        assert!(buf.is_empty());
+
+        assert!(codec.decode(&mut buf).unwrap().is_none());
+    }
+
+    #[test]
+    fn incomplete_frame_test() {
+        let mut codec = TcpClientCodec::default();
+        let v: Vec<u8> = vec![
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x0D, 0x01, 0x03, 0x0A, 0xAE, 0x41, 0x56, 0x52,
+        ];
+        let mut buf = BytesMut::from(&v[..]);
+        assert!(codec.decode(&mut buf).unwrap().is_none());
+        assert_eq!(buf.len(), 13);
+    }
+
+    #[test]
+    fn mbap_header_test() {
+        let mut codec = TcpClientCodec::default();
+        let v: Vec<u8> = vec![
+            0x12, 0x34, 0x00, 0x00, 0x00, 0x05, 0x0B, 0x04, 0x02, 0x10, 0x2F,
+        ];
+        let mut buf = BytesMut::from(&v[..]);
+        let response = codec.decode(&mut buf).unwrap().unwrap();
+        let mbap = MbapHeader {
+            transaction_id: 0x1234,
+            protocol_id: 0x0000,
+            length: 0x0005,
+            unit_id: 0x0B,
+        };
+        assert_eq!(response.mbap_header(), Some(mbap));
+    }
+
+    #[test]
+    fn decode_measured_test() {
+        let mut codec = TcpClientCodec::default();
+        let v: Vec<u8> = vec![
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x09, 0x01, 0x03, 0x06, 0xAE, 0x41, 0x56, 0x52, 0x43,
+            0x40, 0x00, 0x02,
+        ];
+        let mut buf = BytesMut::from(&v[..]);
+        let (response_l, consumed) = decode_measured(&mut codec, &mut buf).unwrap().unwrap();
+        let frame = Frame::tcp();
+        let response_r =
+            frame.read_holding_register_response(0x01, vec![0xAE, 0x41, 0x56, 0x52, 0x43, 0x40]);
+        assert_eq!(response_l, response_r);
+        // MBAP length field (0x09) counts the unit id and the PDU
+        assert_eq!(consumed, 6 + 0x09);
+        assert_eq!(buf.to_vec(), vec![0x00, 0x02]);
+    }
+
+    #[test]
+    fn decode_cow_test() {
+        let v: Vec<u8> = vec![
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x07, 0x01, 0x03, 0x04, 0x02, 0x2B, 0x01, 0x06, 0x00,
+        ];
+
+        let codec = TcpClientCodec::default();
+        let (response, consumed) = codec.decode_cow(&v).unwrap().unwrap();
+        assert_eq!(consumed, 13);
+        assert_eq!(response.head.uid(), 0x01);
+        match response.values {
+            Cow::Borrowed(values) => assert!(std::ptr::eq(values, &v[9..13])),
+            Cow::Owned(_) => panic!("values copied out of the buffer"),
+        }
+
+        let mut codec = TcpClientCodec::default();
+        codec.set_swap_register_bytes(true);
+        let (response, consumed) = codec.decode_cow(&v).unwrap().unwrap();
+        assert_eq!(consumed, 13);
+        assert!(matches!(response.values, Cow::Owned(_)));
+        assert_eq!(response.values[..], [0x2B, 0x02, 0x06, 0x01]);
+
+        assert!(codec.decode_cow(&v[..12]).unwrap().is_none());
+
+        let v = [0x00, 0x01, 0x00, 0x00, 0x00, 0x03, 0x01, 0x83, 0x02];
+        assert!(matches!(
+            codec.decode_cow(&v),
+            Err(ModbusError::Exception(Exception::IllegalDataAddress))
+        ));
+    }
+
+    #[test]
+    fn lenient_test() {
+        // The gateway repeats the slave address 0x21 after the unit id
+        let v: Vec<u8> = vec![
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x06, 0x01, 0x21, 0x03, 0x02, 0x00, 0x2A,
+        ];
+        let mut codec = TcpClientCodec::default();
+        assert!(codec.decode(&mut BytesMut::from(&v[..])).is_err());
+
+        codec.set_lenient(true);
+        let response = codec.decode(&mut BytesMut::from(&v[..])).unwrap().unwrap();
+        let expected = Frame::tcp().read_holding_register_response(0x01, vec![0x00, 0x2A]);
+        assert_eq!(response, expected);
+        assert_eq!(codec.recovered_frames(), 1);
+
+        // Well formed responses are left alone
+        let v: Vec<u8> = vec![
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x05, 0x01, 0x03, 0x02, 0x00, 0x2A,
+        ];
+        let response = codec.decode(&mut BytesMut::from(&v[..])).unwrap().unwrap();
+        assert_eq!(response, expected);
+        assert_eq!(codec.recovered_frames(), 1);
+    }
 }
 
 #[cfg(test)]
 mod rtu_server_decoder_test {
     use bytes::BytesMut;
-    use tokio_util::codec::Decoder;
+    use tokio_util::codec::{Decoder, Encoder};
 
-    use crate::codec::RtuServerCodec;
+    use crate::codec::{RtuOverTcpServerCodec, RtuServerCodec};
+    use crate::error::ModbusError;
     use crate::frame::Frame;
+    use crate::Request;
 
     #[test]
     fn read_coils_request_test() {
@@ -709,6 +2219,53 @@ mod rtu_server_decoder_test {
         assert_eq!(request_l, request_r);
     }
 
+    #[test]
+    fn split_and_coalesced_frames_test() {
+        let mut codec = RtuOverTcpServerCodec::default();
+        let frame = Frame::rtu();
+        let v: Vec<u8> = vec![
+            0x0B, 0x01, 0x00, 0x1D, 0x00, 0x1F, 0xED, 0x6E, 0x11, 0x0F, 0x00, 0x13, 0x00, 0x0A,
+            0x02, 0xCD, 0x01, 0xBF, 0x0B,
+        ];
+
+        // The first frame trickles in byte by byte
+        let mut buf = BytesMut::new();
+        for &byte in &v[..7] {
+            buf.extend_from_slice(&[byte]);
+            assert!(codec.decode(&mut buf).unwrap().is_none());
+        }
+        // The rest of it arrives along with part of the second frame
+        buf.extend_from_slice(&v[7..12]);
+        let request = codec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(request, frame.read_coils_request(0x0B, 0x001D, 0x001F));
+        assert!(codec.decode(&mut buf).unwrap().is_none());
+
+        buf.extend_from_slice(&v[12..]);
+        let request = codec.decode(&mut buf).unwrap().unwrap();
+        let expected = frame.write_multiple_coils_request(0x11, 0x0013, 0x000A, vec![0xCD, 0x01]);
+        assert_eq!(request, expected);
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn crc_mismatch_test() {
+        let mut codec = RtuServerCodec;
+        let v: Vec<u8> = vec![0x0B, 0x01, 0x00, 0x1D, 0x00, 0x1F, 0xED, 0x6F];
+        let err = codec.decode(&mut BytesMut::from(&v[..])).unwrap_err();
+        match err {
+            ModbusError::CrcMismatch {
+                bytes,
+                expected,
+                actual,
+            } => {
+                assert_eq!(bytes, v);
+                assert_eq!(expected, 0xED6E);
+                assert_eq!(actual, 0xED6F);
+            }
+            err => panic!("unexpected error {:?}", err),
+        }
+    }
+
     #[test]
     fn read_discrete_inputs_test() {
         let mut codec = RtuServerCodec::default();
@@ -793,6 +2350,53 @@ mod rtu_server_decoder_test {
         );
         assert_eq!(request_l, request_r);
     }
+
+    #[test]
+    fn report_server_id_test() {
+        let mut codec = RtuServerCodec;
+        let v: Vec<u8> = vec![0x0B, 0x11, 0xC6, 0x8C];
+        let mut buf = BytesMut::from(&v[..]);
+        let request_l = codec.decode(&mut buf).unwrap().unwrap();
+
+        let frame = Frame::rtu();
+        let request_r = frame.report_server_id_request(0x0B);
+        assert_eq!(request_l, request_r);
+    }
+
+    #[test]
+    fn read_exception_status_test() {
+        let mut codec = RtuServerCodec;
+        let v: Vec<u8> = vec![0x11, 0x07, 0x4C, 0x22];
+        let mut buf = BytesMut::from(&v[..]);
+        let request_l = codec.decode(&mut buf).unwrap().unwrap();
+
+        let frame = Frame::rtu();
+        let request_r = frame.read_exception_status_request(0x11);
+        assert_eq!(request_l, request_r);
+    }
+
+    #[test]
+    fn diagnostics_test() {
+        let mut codec = RtuServerCodec;
+        let v: Vec<u8> = vec![0x11, 0x08, 0x00, 0x00, 0xA5, 0x37, 0xD8, 0x1D];
+        let mut buf = BytesMut::from(&v[..]);
+        let request_l = codec.decode(&mut buf).unwrap().unwrap();
+
+        let frame = Frame::rtu();
+        let request_r = frame.diagnostics_request(0x11, 0x0000, 0xA537);
+        assert_eq!(request_l, request_r);
+
+        // Return Query Data echoes the request
+        let response = match &request_l {
+            Request::Diagnostics(head, body) => {
+                frame.diagnostics_response_from_request(head.uid(), body)
+            }
+            request => panic!("unexpected request {:?}", request),
+        };
+        let mut dst = BytesMut::new();
+        RtuServerCodec.encode(response, &mut dst).unwrap();
+        assert_eq!(dst.to_vec(), v);
+    }
 }
 
 #[cfg(test)]
@@ -801,6 +2405,8 @@ mod tcp_server_decoder_test {
     use tokio_util::codec::Decoder;
 
     use crate::codec::TcpServerCodec;
+    use crate::error::ModbusError;
+    use crate::frame::request::FileRecord;
     use crate::frame::Frame;
 
     #[test]
@@ -913,4 +2519,104 @@ mod tcp_server_decoder_test {
         );
         assert_eq!(request_l, request_r);
     }
+
+    #[test]
+    fn incomplete_frame_test() {
+        let mut codec = TcpServerCodec;
+        let v: Vec<u8> = vec![0x00, 0x01, 0x00, 0x00, 0x00, 0x06, 0x01, 0x01, 0x00, 0x02];
+        let mut buf = BytesMut::from(&v[..]);
+        assert!(codec.decode(&mut buf).unwrap().is_none());
+        assert_eq!(buf.len(), 10);
+
+        buf.extend_from_slice(&[0x00, 0x08, 0x00, 0x02]);
+        let request_l = codec.decode(&mut buf).unwrap().unwrap();
+        let request_r = Frame::tcp().read_coils_request(0x01, 0x02, 0x08);
+        assert_eq!(request_l, request_r);
+        assert_eq!(buf.len(), 2);
+    }
+
+    #[test]
+    fn truncated_request_test() {
+        let mut codec = TcpServerCodec;
+        // Write single coil whose MBAP length only leaves one byte of body
+        let v: Vec<u8> = vec![0xD1, 0x12, 0x00, 0x00, 0x00, 0x03, 0x6C, 0x05, 0xD7];
+        let mut buf = BytesMut::from(&v[..]);
+        let err = codec.decode(&mut buf).unwrap_err();
+        assert!(matches!(err, ModbusError::InvalidLength(_)));
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn read_file_record_request_test() {
+        let mut codec = TcpServerCodec;
+        let v: Vec<u8> = vec![
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x11, 0x01, 0x14, 0x0E, 0x06, 0x00, 0x04, 0x00, 0x01,
+            0x00, 0x02, 0x06, 0x00, 0x03, 0x00, 0x09, 0x00, 0x02,
+        ];
+        let mut buf = BytesMut::from(&v[..]);
+        let request_l = codec.decode(&mut buf).unwrap().unwrap();
+
+        let records = vec![
+            FileRecord::new(0x0004, 0x0001, 0x0002),
+            FileRecord::new(0x0003, 0x0009, 0x0002),
+        ];
+        let request_r = Frame::tcp().read_file_record_request(0x01, records);
+        assert_eq!(request_l, request_r);
+    }
+}
+
+#[cfg(test)]
+mod progress_decoder_test {
+    use bytes::BytesMut;
+    use futures::StreamExt;
+    use tokio::io::{duplex, AsyncWriteExt};
+    use tokio_util::codec::{Decoder, Encoder, FramedRead};
+
+    use crate::codec::{DecodeEvent, ProgressDecoder, TcpClientCodec, TcpServerCodec};
+    use crate::frame::Frame;
+
+    #[tokio::test]
+    async fn fragmented_response_test() {
+        let values: Vec<u8> = (0..250).map(|i| i as u8).collect();
+        let response = Frame::tcp().read_holding_register_response(0x01, values);
+        let mut bytes = BytesMut::new();
+        TcpServerCodec.encode(response.clone(), &mut bytes).unwrap();
+        assert_eq!(bytes.len(), 259);
+
+        let (mut writer, reader) = duplex(1024);
+        let mut events = FramedRead::new(reader, ProgressDecoder::tcp(TcpClientCodec::default()));
+        let mut chunks = bytes.chunks(100);
+
+        writer.write_all(chunks.next().unwrap()).await.unwrap();
+        assert_eq!(events.next().await.unwrap().unwrap(), DecodeEvent::Header);
+        assert_eq!(events.next().await.unwrap().unwrap(), DecodeEvent::Progress(100));
+        writer.write_all(chunks.next().unwrap()).await.unwrap();
+        assert_eq!(events.next().await.unwrap().unwrap(), DecodeEvent::Progress(200));
+        writer.write_all(chunks.next().unwrap()).await.unwrap();
+        assert_eq!(
+            events.next().await.unwrap().unwrap(),
+            DecodeEvent::Complete(response)
+        );
+
+        drop(writer);
+        assert!(events.next().await.is_none());
+    }
+
+    #[test]
+    fn invalid_frame_test() {
+        let mut decoder = ProgressDecoder::tcp(TcpClientCodec::default());
+        let mut src = BytesMut::from(&[0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x01, 0x03][..]);
+        assert_eq!(decoder.decode(&mut src).unwrap(), Some(DecodeEvent::Header));
+        assert!(decoder.decode(&mut src).is_err());
+
+        // The frame following the rejected one gets its own header event
+        let response = Frame::tcp().read_holding_register_response(0x01, vec![0x00, 0x0A]);
+        src.clear();
+        TcpServerCodec.encode(response.clone(), &mut src).unwrap();
+        assert_eq!(decoder.decode(&mut src).unwrap(), Some(DecodeEvent::Header));
+        assert_eq!(
+            decoder.decode(&mut src).unwrap(),
+            Some(DecodeEvent::Complete(response))
+        );
+    }
 }
@@ -1,66 +1,178 @@
-use std::io::{Error, Result};
-
-use bytes::BytesMut;
+use bytes::{BufMut, BytesMut};
 use tokio_util::codec::Encoder;
 
-use crate::codec::{RtuClientCodec, RtuServerCodec, TcpClientCodec};
+use crate::codec::{
+    swap_request_registers, trace_frame, AsciiClientCodec, AsciiServerCodec, PduClientCodec,
+    PduServerCodec, RtuClientCodec, RtuServerCodec, TcpClientCodec,
+};
+use crate::error::{ModbusError, Result};
 use crate::frame::request::*;
 use crate::frame::response::*;
 use crate::frame::response::Response;
+use crate::frame::{Head, Version};
+use crate::util::lrc;
 
 use super::TcpServerCodec;
 
+/// Largest PDU (function code and data) allowed by the Modbus specification
+const MAX_PDU_LEN: usize = 253;
+
 impl Encoder<Request> for RtuClientCodec {
-    type Error = Error;
+    type Error = ModbusError;
 
-    fn encode(
-        &mut self,
-        item: Request,
-        dst: &mut BytesMut,
-    ) -> std::result::Result<(), Self::Error> {
-        request_to_bytesmut(item, dst);
+    fn encode(&mut self, mut item: Request, dst: &mut BytesMut) -> Result<()> {
+        check_pdu_len(&item.head())?;
+        if self.swap_register_bytes {
+            swap_request_registers(&mut item);
+        }
+        let start = dst.len();
+        request_to_bytesmut_with(item, dst, self.checksum.as_ref());
+        trace_frame("Sent", &[&dst[start..]]);
         Ok(())
     }
 }
 
 impl Encoder<Response> for RtuServerCodec {
-    type Error = Error;
+    type Error = ModbusError;
 
-    fn encode(
-        &mut self,
-        item: Response,
-        dst: &mut BytesMut,
-    ) -> std::result::Result<(), Self::Error> {
+    fn encode(&mut self, item: Response, dst: &mut BytesMut) -> Result<()> {
+        check_pdu_len(&item.head())?;
+        let start = dst.len();
         response_to_bytesmut(item, dst);
+        trace_frame("Sent", &[&dst[start..]]);
         Ok(())
     }
 }
 
 impl Encoder<Request> for TcpClientCodec {
-    type Error = Error;
+    type Error = ModbusError;
 
-    fn encode(&mut self, item: Request, dst: &mut BytesMut) -> Result<()> {
+    fn encode(&mut self, mut item: Request, dst: &mut BytesMut) -> Result<()> {
+        check_pdu_len(&item.head())?;
+        if self.swap_register_bytes {
+            swap_request_registers(&mut item);
+        }
+        let start = dst.len();
         request_to_bytesmut(item, dst);
+        trace_frame("Sent", &[&dst[start..]]);
         Ok(())
     }
 }
 
 impl Encoder<Response> for TcpServerCodec {
-    type Error = Error;
+    type Error = ModbusError;
 
     fn encode(&mut self, item: Response, dst: &mut BytesMut) -> Result<()> {
+        check_pdu_len(&item.head())?;
+        let start = dst.len();
         response_to_bytesmut(item, dst);
+        trace_frame("Sent", &[&dst[start..]]);
+        Ok(())
+    }
+}
+
+impl Encoder<Request> for PduClientCodec {
+    type Error = ModbusError;
+
+    fn encode(&mut self, item: Request, dst: &mut BytesMut) -> Result<()> {
+        let head = item.head();
+        check_pdu_len(&head)?;
+        let start = dst.len();
+        put_pdu(&head, dst, |frame| request_to_bytesmut(item, frame));
+        trace_frame("Sent", &[&dst[start..]]);
+        Ok(())
+    }
+}
+
+impl Encoder<Response> for PduServerCodec {
+    type Error = ModbusError;
+
+    fn encode(&mut self, item: Response, dst: &mut BytesMut) -> Result<()> {
+        let head = item.head();
+        check_pdu_len(&head)?;
+        let start = dst.len();
+        put_pdu(&head, dst, |frame| response_to_bytesmut(item, frame));
+        trace_frame("Sent", &[&dst[start..]]);
+        Ok(())
+    }
+}
+
+impl Encoder<Request> for AsciiClientCodec {
+    type Error = ModbusError;
+
+    fn encode(&mut self, item: Request, dst: &mut BytesMut) -> Result<()> {
+        let head = item.head();
+        check_pdu_len(&head)?;
+        let start = dst.len();
+        put_ascii(&head, dst, |frame| request_to_bytesmut(item, frame));
+        trace_frame("Sent", &[&dst[start..]]);
+        Ok(())
+    }
+}
+
+impl Encoder<Response> for AsciiServerCodec {
+    type Error = ModbusError;
+
+    fn encode(&mut self, item: Response, dst: &mut BytesMut) -> Result<()> {
+        let head = item.head();
+        check_pdu_len(&head)?;
+        let start = dst.len();
+        put_ascii(&head, dst, |frame| response_to_bytesmut(item, frame));
+        trace_frame("Sent", &[&dst[start..]]);
         Ok(())
     }
 }
 
+/// Append the frame written by `encode` to `dst` as a Modbus ASCII frame: the unit id, PDU and
+/// LRC in hex digits between `:` and `\r\n`
+fn put_ascii(head: &Head, dst: &mut BytesMut, encode: impl FnOnce(&mut BytesMut)) {
+    const HEX_DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+
+    let mut frame = BytesMut::new();
+    encode(&mut frame);
+    let adu = match head.version {
+        Version::Tcp => &frame[6..],
+        Version::Rtu => &frame[..frame.len() - 2],
+    };
+    dst.put_u8(b':');
+    for &byte in adu.iter().chain(&[lrc::compute(adu)]) {
+        dst.put_u8(HEX_DIGITS[(byte >> 4) as usize]);
+        dst.put_u8(HEX_DIGITS[(byte & 0x0F) as usize]);
+    }
+    dst.put_slice(b"\r\n");
+}
+
+/// Append the PDU of the frame written by `encode` to `dst`, dropping the MBAP header or the
+/// address and CRC
+fn put_pdu(head: &Head, dst: &mut BytesMut, encode: impl FnOnce(&mut BytesMut)) {
+    let mut frame = BytesMut::new();
+    encode(&mut frame);
+    let pdu = match head.version {
+        Version::Tcp => &frame[7..],
+        Version::Rtu => &frame[1..frame.len() - 2],
+    };
+    dst.extend_from_slice(pdu);
+}
+
+/// Refuse frames no device is required to accept
+pub(crate) fn check_pdu_len(head: &Head) -> Result<()> {
+    // The length counts the unit id, function code and data
+    let len = head.length as usize - 1;
+    if len > MAX_PDU_LEN {
+        return Err(ModbusError::FrameTooLarge(len));
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod rtu_client_encoder_test {
     use bytes::BytesMut;
-    use tokio_util::codec::Encoder;
+    use tokio_util::codec::{Decoder, Encoder};
 
     use crate::codec::RtuClientCodec;
+    use crate::error::ModbusError;
     use crate::frame::Frame;
+    use crate::util::crc::Checksum;
 
     #[test]
     fn read_coils_request_test() {
@@ -75,6 +187,32 @@ mod rtu_client_encoder_test {
         assert_eq!(vec_l, vec_r);
     }
 
+    #[test]
+    fn read_exception_status_request_test() {
+        let mut codec = RtuClientCodec::default();
+        let frame = Frame::rtu();
+        let request = frame.read_exception_status_request(0x11);
+        let mut dst = BytesMut::new();
+        let res = codec.encode(request, &mut dst);
+        assert!(res.is_ok());
+        let vec_l = dst.to_vec();
+        let vec_r = vec![0x11, 0x07, 0x4C, 0x22];
+        assert_eq!(vec_l, vec_r);
+    }
+
+    #[test]
+    fn diagnostics_request_test() {
+        let mut codec = RtuClientCodec::default();
+        let frame = Frame::rtu();
+        let request = frame.diagnostics_request(0x11, 0x0000, 0xA537);
+        let mut dst = BytesMut::new();
+        let res = codec.encode(request, &mut dst);
+        assert!(res.is_ok());
+        let vec_l = dst.to_vec();
+        let vec_r = vec![0x11, 0x08, 0x00, 0x00, 0xA5, 0x37, 0xD8, 0x1D];
+        assert_eq!(vec_l, vec_r);
+    }
+
     #[test]
     fn read_discrete_inputs_request_test() {
         let mut codec = RtuClientCodec::default();
@@ -140,6 +278,37 @@ mod rtu_client_encoder_test {
         assert_eq!(vec_l, vec_r);
     }
 
+    #[test]
+    fn mask_write_register_request_test() {
+        let mut codec = RtuClientCodec::default();
+        let frame = Frame::rtu();
+        let request = frame.mask_write_register_request(0x0B, 0x0004, 0x00F2, 0x0025);
+        let mut dst = BytesMut::new();
+        let res = codec.encode(request, &mut dst);
+        assert!(res.is_ok());
+        let vec_l = dst.to_vec();
+        let vec_r = vec![0x0B, 0x16, 0x00, 0x04, 0x00, 0xF2, 0x00, 0x25, 0xE7, 0x91];
+        assert_eq!(vec_l, vec_r);
+    }
+
+    #[test]
+    fn read_write_multiple_registers_request_test() {
+        let mut codec = RtuClientCodec::default();
+        let frame = Frame::rtu();
+        let values = vec![0x00, 0xFF, 0x00, 0xFF, 0x00, 0xFF];
+        let request =
+            frame.read_write_multiple_registers_request(0x0B, 0x0003, 0x0006, 0x000E, values);
+        let mut dst = BytesMut::new();
+        let res = codec.encode(request, &mut dst);
+        assert!(res.is_ok());
+        let vec_l = dst.to_vec();
+        let vec_r = vec![
+            0x0B, 0x17, 0x00, 0x03, 0x00, 0x06, 0x00, 0x0E, 0x00, 0x03, 0x06, 0x00, 0xFF, 0x00,
+            0xFF, 0x00, 0xFF, 0x60, 0x33,
+        ];
+        assert_eq!(vec_l, vec_r);
+    }
+
     #[test]
     fn write_multiple_coils_request_test() {
         let mut codec = RtuClientCodec::default();
@@ -173,6 +342,78 @@ mod rtu_client_encoder_test {
         ];
         assert_eq!(vec_l, vec_r);
     }
+
+    #[test]
+    fn frame_too_large_test() {
+        let mut codec = RtuClientCodec::default();
+        let frame = Frame::rtu();
+        let request = frame.write_multiple_holding_registers_request(0x0B, 0x0012, vec![0; 250]);
+        let mut dst = BytesMut::new();
+        let res = codec.encode(request, &mut dst);
+        assert!(matches!(res, Err(ModbusError::FrameTooLarge(256))));
+        assert!(dst.is_empty());
+    }
+
+    #[test]
+    fn swap_register_bytes_test() {
+        let mut codec = RtuClientCodec::default();
+        codec.set_swap_register_bytes(true);
+        let frame = Frame::rtu();
+        let request = frame.write_multiple_holding_registers_request(
+            0x0B,
+            0x0012,
+            vec![0x0B, 0x0A, 0xC1, 0x02],
+        );
+        let mut dst = BytesMut::new();
+        let res = codec.encode(request, &mut dst);
+        assert!(res.is_ok());
+        let vec_l = dst.to_vec();
+        let vec_r = vec![
+            0x0B, 0x10, 0x00, 0x12, 0x00, 0x02, 0x04, 0x0A, 0x0B, 0x02, 0xC1, 0xE0, 0x48,
+        ];
+        assert_eq!(vec_l, vec_r);
+    }
+
+    #[derive(Debug)]
+    struct ZeroChecksum;
+
+    impl Checksum for ZeroChecksum {
+        fn compute(&self, _data: &[u8]) -> u16 {
+            0x0000
+        }
+    }
+
+    #[test]
+    fn custom_checksum_test() {
+        let mut codec = RtuClientCodec::with_checksum(ZeroChecksum);
+        let request = Frame::rtu().read_coils_request(0x0B, 0x001D, 0x001F);
+        let mut dst = BytesMut::new();
+        codec.encode(request, &mut dst).unwrap();
+        assert_eq!(dst.to_vec(), vec![0x0B, 0x01, 0x00, 0x1D, 0x00, 0x1F, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn legacy_crc_order_test() {
+        let mut codec = RtuClientCodec::default();
+        let frame = Frame::rtu();
+        let mut dst = BytesMut::new();
+        codec.encode(frame.read_coils_request(0x0B, 0x001D, 0x001F), &mut dst).unwrap();
+        assert_eq!(&dst[6..], &[0xED, 0x6E]);
+
+        codec.set_legacy_crc_order(true);
+        let mut dst = BytesMut::new();
+        codec.encode(frame.read_coils_request(0x0B, 0x001D, 0x001F), &mut dst).unwrap();
+        assert_eq!(&dst[6..], &[0x6E, 0xED]);
+
+        let v: Vec<u8> = vec![0x0B, 0x01, 0x04, 0xCD, 0x6B, 0xB2, 0x7F, 0xE1, 0x2B];
+        let response = codec.decode(&mut BytesMut::from(&v[..])).unwrap().unwrap();
+        assert_eq!(response, frame.read_coils_response(0x0B, vec![0xCD, 0x6B, 0xB2, 0x7F]));
+
+        codec.set_legacy_crc_order(false);
+        let mut dst = BytesMut::new();
+        codec.encode(frame.read_coils_request(0x0B, 0x001D, 0x001F), &mut dst).unwrap();
+        assert_eq!(&dst[6..], &[0xED, 0x6E]);
+    }
 }
 
 #[cfg(test)]
@@ -181,8 +422,24 @@ mod tcp_client_decoder_test {
     use tokio_util::codec::Encoder;
 
     use crate::codec::TcpClientCodec;
+    use crate::frame::request::FileRecord;
     use crate::frame::Frame;
 
+    #[test]
+    fn little_endian_write_test() {
+        let frame = Frame::tcp();
+        let request = frame.write_single_holding_register_request(0x01, 0x0001, 0x1234);
+        let mut dst = BytesMut::new();
+        TcpClientCodec::default().encode(request.clone(), &mut dst).unwrap();
+        assert_eq!(dst[10..].to_vec(), vec![0x12, 0x34]);
+
+        let mut codec = TcpClientCodec::default();
+        codec.set_swap_register_bytes(true);
+        let mut dst = BytesMut::new();
+        codec.encode(request, &mut dst).unwrap();
+        assert_eq!(dst[10..].to_vec(), vec![0x34, 0x12]);
+    }
+
     #[test]
     fn read_coils_request_test() {
         let mut codec = TcpClientCodec::default();
@@ -305,6 +562,85 @@ mod tcp_client_decoder_test {
         ];
         assert_eq!(vec_l, vec_r);
     }
+
+    #[test]
+    fn mask_write_register_request_test() {
+        let mut codec = TcpClientCodec::default();
+        let frame = Frame::tcp();
+        let request = frame.mask_write_register_request(0x01, 0x0004, 0x00F2, 0x0025);
+        let mut dst = BytesMut::new();
+        let res = codec.encode(request, &mut dst);
+        assert!(res.is_ok());
+        let vec_l = dst.to_vec();
+        let vec_r = vec![
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x08, 0x01, 0x16, 0x00, 0x04, 0x00, 0xF2, 0x00, 0x25,
+        ];
+        assert_eq!(vec_l, vec_r);
+    }
+
+    #[test]
+    fn read_device_identification_request_test() {
+        let mut codec = TcpClientCodec::default();
+        let frame = Frame::tcp();
+        let request = frame.read_device_identification_request(0x01, 0x01, 0x00);
+        let mut dst = BytesMut::new();
+        let res = codec.encode(request, &mut dst);
+        assert!(res.is_ok());
+        let vec_l = dst.to_vec();
+        let vec_r = vec![0x00, 0x01, 0x00, 0x00, 0x00, 0x05, 0x01, 0x2B, 0x0E, 0x01, 0x00];
+        assert_eq!(vec_l, vec_r);
+    }
+
+    #[test]
+    fn read_fifo_queue_request_test() {
+        let mut codec = TcpClientCodec::default();
+        let frame = Frame::tcp();
+        let request = frame.read_fifo_queue_request(0x01, 0x04DE);
+        let mut dst = BytesMut::new();
+        let res = codec.encode(request, &mut dst);
+        assert!(res.is_ok());
+        let vec_l = dst.to_vec();
+        let vec_r = vec![0x00, 0x01, 0x00, 0x00, 0x00, 0x04, 0x01, 0x18, 0x04, 0xDE];
+        assert_eq!(vec_l, vec_r);
+    }
+
+    #[test]
+    fn read_file_record_request_test() {
+        let mut codec = TcpClientCodec::default();
+        let frame = Frame::tcp();
+        let records = vec![
+            FileRecord::new(0x0004, 0x0001, 0x0002),
+            FileRecord::new(0x0003, 0x0009, 0x0002),
+        ];
+        let request = frame.read_file_record_request(0x01, records);
+        let mut dst = BytesMut::new();
+        let res = codec.encode(request, &mut dst);
+        assert!(res.is_ok());
+        let vec_l = dst.to_vec();
+        let vec_r = vec![
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x11, 0x01, 0x14, 0x0E, 0x06, 0x00, 0x04, 0x00, 0x01,
+            0x00, 0x02, 0x06, 0x00, 0x03, 0x00, 0x09, 0x00, 0x02,
+        ];
+        assert_eq!(vec_l, vec_r);
+    }
+
+    #[test]
+    fn read_write_multiple_registers_request_test() {
+        let mut codec = TcpClientCodec::default();
+        let frame = Frame::tcp();
+        let values = vec![0x00, 0xFF];
+        let request =
+            frame.read_write_multiple_registers_request(0x01, 0x0003, 0x0001, 0x000E, values);
+        let mut dst = BytesMut::new();
+        let res = codec.encode(request, &mut dst);
+        assert!(res.is_ok());
+        let vec_l = dst.to_vec();
+        let vec_r = vec![
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x0D, 0x01, 0x17, 0x00, 0x03, 0x00, 0x01, 0x00, 0x0E,
+            0x00, 0x01, 0x02, 0x00, 0xFF,
+        ];
+        assert_eq!(vec_l, vec_r);
+    }
 }
 
 #[cfg(test)]
@@ -585,3 +921,143 @@ mod rtu_server_decoder_test {
         assert_eq!(vec_l, vec_r);
     }
 }
+
+#[cfg(test)]
+mod pdu_codec_test {
+    use bytes::BytesMut;
+    use tokio_util::codec::{Decoder, Encoder};
+
+    use crate::codec::{PduClientCodec, PduServerCodec};
+    use crate::frame::{Exception, Function};
+    use crate::{Frame, Response};
+
+    #[test]
+    fn read_request_round_trip_test() {
+        let mut buf = BytesMut::new();
+        let request = Frame::tcp().read_multiple_holding_registers_request(0x01, 0x006B, 0x0003);
+        PduClientCodec.encode(request, &mut buf).unwrap();
+        assert_eq!(&buf[..], [0x03, 0x00, 0x6B, 0x00, 0x03]);
+
+        let request = PduServerCodec.decode(&mut buf).unwrap().unwrap();
+        let head = request.head();
+        assert_eq!((head.tid, head.uid()), (0x00, 0x00));
+        assert_eq!(head.function, Function::ReadMultipleHoldingRegisters);
+        let mut expected =
+            Frame::tcp().read_multiple_holding_registers_request(0x00, 0x006B, 0x0003);
+        expected.set_head(head);
+        assert_eq!(request, expected);
+        assert!(buf.is_empty());
+
+        let mut buf = BytesMut::new();
+        let request = Frame::rtu().read_coils_request(0x0B, 0x001D, 0x001F);
+        PduClientCodec.encode(request, &mut buf).unwrap();
+        assert_eq!(&buf[..], [0x01, 0x00, 0x1D, 0x00, 0x1F]);
+    }
+
+    #[test]
+    fn response_round_trip_test() {
+        let mut buf = BytesMut::new();
+        let response = Frame::rtu().read_holding_register_response(0x01, vec![0x02, 0x2B]);
+        PduServerCodec.encode(response, &mut buf).unwrap();
+        assert_eq!(&buf[..], [0x03, 0x02, 0x02, 0x2B]);
+
+        let response = Frame::tcp().exception_response(
+            0x01,
+            Function::ReadCoils,
+            Exception::IllegalDataAddress,
+        );
+        PduServerCodec.encode(response, &mut buf).unwrap();
+        assert_eq!(&buf[4..], [0x81, 0x02]);
+
+        let response = PduClientCodec.decode(&mut buf).unwrap().unwrap();
+        let values = match response {
+            Response::ReadMultipleHoldingRegisters(_, body) => body.get_values().clone(),
+            response => panic!("unexpected response {:?}", response),
+        };
+        assert_eq!(values, vec![0x02, 0x2B]);
+        let response = PduClientCodec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(response.as_exception(), Some(&Exception::IllegalDataAddress));
+        assert!(PduClientCodec.decode(&mut buf).unwrap().is_none());
+    }
+}
+
+#[cfg(test)]
+mod ascii_codec_test {
+    use bytes::BytesMut;
+    use tokio_util::codec::{Decoder, Encoder};
+
+    use crate::codec::{AsciiClientCodec, AsciiServerCodec};
+    use crate::error::ModbusError;
+    use crate::frame::{Exception, Function};
+    use crate::Frame;
+
+    #[test]
+    fn request_round_trip_test() {
+        let mut buf = BytesMut::new();
+        let request = Frame::rtu().read_multiple_holding_registers_request(0x11, 0x006B, 0x0003);
+        AsciiClientCodec.encode(request.clone(), &mut buf).unwrap();
+        assert_eq!(&buf[..], b":1103006B00037E\r\n");
+
+        // Line noise before the frame is skipped
+        let mut src = BytesMut::from(&b"\x00\xFF"[..]);
+        src.extend_from_slice(&buf[..8]);
+        assert!(AsciiServerCodec.decode(&mut src).unwrap().is_none());
+        src.extend_from_slice(&buf[8..]);
+        assert_eq!(AsciiServerCodec.decode(&mut src).unwrap(), Some(request));
+        assert!(src.is_empty());
+
+        let mut buf = BytesMut::new();
+        let request = Frame::tcp().read_coils_request(0x0B, 0x001D, 0x001F);
+        AsciiClientCodec.encode(request, &mut buf).unwrap();
+        assert_eq!(&buf[..], b":0B01001D001FB8\r\n");
+    }
+
+    #[test]
+    fn response_round_trip_test() {
+        let mut buf = BytesMut::new();
+        let values = vec![0x02, 0x2B, 0x00, 0x00, 0x00, 0x64];
+        let response = Frame::rtu().read_holding_register_response(0x11, values);
+        AsciiServerCodec.encode(response.clone(), &mut buf).unwrap();
+        assert_eq!(&buf[..], b":110306022B0000006455\r\n");
+
+        let exception = Frame::rtu().exception_response(
+            0x11,
+            Function::ReadCoils,
+            Exception::IllegalDataAddress,
+        );
+        AsciiServerCodec.encode(exception, &mut buf).unwrap();
+
+        assert_eq!(AsciiClientCodec.decode(&mut buf).unwrap(), Some(response));
+        let exception = AsciiClientCodec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(exception.as_exception(), Some(&Exception::IllegalDataAddress));
+        assert!(AsciiClientCodec.decode(&mut buf).unwrap().is_none());
+    }
+
+    #[test]
+    fn lrc_mismatch_test() {
+        let mut buf = BytesMut::from(&b":1103006B00037F\r\n"[..]);
+        let err = AsciiServerCodec.decode(&mut buf).unwrap_err();
+        match err {
+            ModbusError::LrcMismatch {
+                bytes,
+                expected,
+                actual,
+            } => {
+                assert_eq!(bytes, vec![0x11, 0x03, 0x00, 0x6B, 0x00, 0x03, 0x7F]);
+                assert_eq!((expected, actual), (0x7E, 0x7F));
+            }
+            err => panic!("unexpected error {:?}", err),
+        }
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn malformed_hex_test() {
+        for frame in [&b":1103006B0G037E\r\n"[..], b":1103006B00037\r\n", b":7E\r\n"] {
+            let mut buf = BytesMut::from(frame);
+            let err = AsciiClientCodec.decode(&mut buf).unwrap_err();
+            assert!(matches!(err, ModbusError::InvalidFrame(_)));
+            assert!(buf.is_empty());
+        }
+    }
+}
@@ -1,20 +1,461 @@
 //! Codec based [tokio-util](https://docs.rs/tokio-util/latest/tokio_util/codec/index.html)
 
+use std::borrow::Cow;
+
+use crate::util::crc::{Checksum, ModbusCrc};
+
+pub use decoder::{decode_measured, rtu_expected_len};
+pub(crate) use encoder::check_pdu_len;
+
+use crate::frame::{request::Request, response::Response, Head};
+
 mod decoder;
 mod encoder;
 
 /// Mutual convert TCP Client frames and buffers.
 #[derive(Debug, Default)]
-pub struct TcpClientCodec;
+pub struct TcpClientCodec {
+    /// Swap the two bytes of each register read or written
+    swap_register_bytes: bool,
+
+    /// Recover responses whose unit id is followed by a stray byte
+    lenient: bool,
+
+    /// Responses recovered in lenient mode
+    recovered_frames: u64,
+}
 
 /// Mutual convert TCP Server frames and buffers.
 #[derive(Debug, Default)]
 pub struct TcpServerCodec;
 
 /// Mutual convert RTU Client frames and buffers.
-#[derive(Debug, Default)]
-pub struct RtuClientCodec;
+#[derive(Debug)]
+pub struct RtuClientCodec {
+    /// Swap the two bytes of each register read or written
+    swap_register_bytes: bool,
+
+    /// Framing statistics
+    stats: RtuStats,
+
+    /// Closes the frames sent and verifies the frames received
+    checksum: Box<dyn Checksum>,
+}
+
+impl Default for RtuClientCodec {
+    fn default() -> Self {
+        RtuClientCodec::with_checksum(ModbusCrc)
+    }
+}
+
+/// Framing statistics of a RTU codec, quantifying how noisy the line is
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RtuStats {
+    /// Frames dropped because of a CRC mismatch
+    pub crc_errors: u64,
+
+    /// Times bytes were dropped to find the start of the next frame
+    pub resyncs: u64,
+
+    /// Bytes dropped while resynchronizing
+    pub bytes_discarded: u64,
+}
 
 /// Mutual convert RTU Server frames and buffers.
 #[derive(Debug, Default)]
 pub struct RtuServerCodec;
+
+/// Mutual convert client RTU frames tunnelled over TCP, without MBAP header, and buffers.
+///
+/// The RTU codecs frame by length and CRC only, so they work on any byte stream: a frame split
+/// across TCP segments is buffered until it is complete, coalesced frames are decoded in turn.
+pub type RtuOverTcpClientCodec = RtuClientCodec;
+
+/// Mutual convert server RTU frames tunnelled over TCP, without MBAP header, and buffers.
+///
+/// See [`RtuOverTcpClientCodec`].
+pub type RtuOverTcpServerCodec = RtuServerCodec;
+
+/// Mutual convert client PDUs and buffers, for tunnelling Modbus over other transports.
+///
+/// Only the function code and data go over the wire, without unit id, MBAP header or CRC. The
+/// transport must keep PDUs apart, e.g. one per message. Decoded responses get a synthetic TCP
+/// head with unit id and transaction id 0.
+#[derive(Debug, Default)]
+pub struct PduClientCodec;
+
+/// Mutual convert server PDUs and buffers, for tunnelling Modbus over other transports.
+///
+/// Only the function code and data go over the wire, without unit id, MBAP header or CRC.
+/// Decoded requests get a synthetic TCP head with unit id and transaction id 0.
+#[derive(Debug, Default)]
+pub struct PduServerCodec;
+
+/// Mutual convert Modbus ASCII client frames and buffers.
+///
+/// Frames start with `:`, carry the unit id, PDU and LRC as uppercase hex digits and end with
+/// `\r\n`. Bytes before a `:` are skipped. Decoded responses get a RTU head.
+#[derive(Debug, Default)]
+pub struct AsciiClientCodec;
+
+/// Mutual convert Modbus ASCII server frames and buffers.
+///
+/// Frames start with `:`, carry the unit id, PDU and LRC as uppercase hex digits and end with
+/// `\r\n`. Bytes before a `:` are skipped. Decoded requests get a RTU head.
+#[derive(Debug, Default)]
+pub struct AsciiServerCodec;
+
+/// Step of the decoding of a response, yielded by [`ProgressDecoder`]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum DecodeEvent {
+    /// The header of the next response, up to its function code, has arrived
+    Header,
+
+    /// Bytes of the response received so far
+    Progress(usize),
+
+    /// The response is complete
+    Complete(Response),
+}
+
+/// Client decoder reporting the progress of large responses as they arrive.
+///
+/// Wraps a client codec and yields [`DecodeEvent`]s instead of bare responses, so a UI can
+/// react before a slow or fragmented response is complete. Turn any reader into a stream of
+/// events with [`FramedRead`](tokio_util::codec::FramedRead).
+#[derive(Debug)]
+pub struct ProgressDecoder<D> {
+    /// Codec decoding the responses
+    inner: D,
+
+    /// Bytes of a response up to its function code
+    header_len: usize,
+
+    /// Whether the header of the current response was reported
+    header_seen: bool,
+
+    /// Bytes of the current response reported so far
+    reported: usize,
+}
+
+impl<D> ProgressDecoder<D> {
+    fn new(inner: D, header_len: usize) -> Self {
+        ProgressDecoder {
+            inner,
+            header_len,
+            header_seen: false,
+            reported: 0,
+        }
+    }
+
+    /// Get the wrapped codec
+    pub fn get_ref(&self) -> &D {
+        &self.inner
+    }
+
+    /// Release the wrapped codec
+    pub fn into_inner(self) -> D {
+        self.inner
+    }
+}
+
+impl ProgressDecoder<TcpClientCodec> {
+    /// Report the progress of the responses decoded by a TCP codec
+    pub fn tcp(codec: TcpClientCodec) -> Self {
+        ProgressDecoder::new(codec, 8)
+    }
+}
+
+impl ProgressDecoder<RtuClientCodec> {
+    /// Report the progress of the responses decoded by a RTU codec
+    pub fn rtu(codec: RtuClientCodec) -> Self {
+        ProgressDecoder::new(codec, 2)
+    }
+}
+
+/// Read response decoded by [`TcpClientCodec::decode_cow`]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ReadValues<'a> {
+    /// Header of the response
+    pub head: Head,
+
+    /// Coil, discrete input or register bytes, borrowed from the input buffer when possible
+    pub values: Cow<'a, [u8]>,
+}
+
+impl ReadValues<'_> {
+    /// Copy the values if borrowed, releasing the input buffer
+    pub fn into_owned(self) -> ReadValues<'static> {
+        ReadValues {
+            head: self.head,
+            values: Cow::Owned(self.values.into_owned()),
+        }
+    }
+}
+
+impl TcpClientCodec {
+    /// Swap the two bytes of each register in read responses and write requests
+    ///
+    /// An escape hatch for gateways and devices expecting little endian register data. Off by
+    /// default, registers being big endian per the specification.
+    pub fn set_swap_register_bytes(&mut self, swap: bool) {
+        self.swap_register_bytes = swap;
+    }
+
+    /// Recover responses carrying a stray byte between the unit id and the function code
+    ///
+    /// Some gateways forward the RTU slave address along with the MBAP header. When the byte
+    /// after the unit id is no function code but the next one is, lenient mode drops the stray
+    /// byte instead of failing, logging a warning with the `tracing` feature. Off by default,
+    /// see [`TcpClientCodec::recovered_frames`].
+    pub fn set_lenient(&mut self, lenient: bool) {
+        self.lenient = lenient;
+    }
+
+    /// Get the number of responses recovered in lenient mode since the codec was created
+    ///
+    /// A growing count points to a misbehaving gateway.
+    pub fn recovered_frames(&self) -> u64 {
+        self.recovered_frames
+    }
+}
+
+impl RtuClientCodec {
+    /// Create a codec computing frame checksums with `checksum` instead of the Modbus CRC
+    pub fn with_checksum(checksum: impl Checksum + 'static) -> Self {
+        RtuClientCodec {
+            swap_register_bytes: false,
+            stats: RtuStats::default(),
+            checksum: Box::new(checksum),
+        }
+    }
+
+    /// Swap the two bytes of each register in read responses and write requests
+    ///
+    /// An escape hatch for gateways and devices expecting little endian register data. Off by
+    /// default, registers being big endian per the specification.
+    pub fn set_swap_register_bytes(&mut self, swap: bool) {
+        self.swap_register_bytes = swap;
+    }
+
+    /// Send and expect the CRC high byte first
+    ///
+    /// For peers that swap the two CRC bytes on the wire, sending and expecting the high byte
+    /// first. Replaces any checksum given to [`RtuClientCodec::with_checksum`]. Off by default,
+    /// the CRC going low byte first per the specification.
+    pub fn set_legacy_crc_order(&mut self, legacy: bool) {
+        self.checksum = if legacy {
+            Box::new(LegacyCrcOrder)
+        } else {
+            Box::new(ModbusCrc)
+        };
+    }
+
+    /// Get the framing statistics gathered since the codec was created
+    pub fn stats(&self) -> &RtuStats {
+        &self.stats
+    }
+}
+
+/// Modbus CRC with its two bytes swapped, see [`RtuClientCodec::set_legacy_crc_order`]
+#[derive(Debug)]
+struct LegacyCrcOrder;
+
+impl Checksum for LegacyCrcOrder {
+    fn compute(&self, data: &[u8]) -> u16 {
+        ModbusCrc.compute(data).swap_bytes()
+    }
+}
+
+/// Swap the two bytes of each register carried by a read response
+fn swap_response_registers(response: &mut Response) {
+    match response {
+        Response::ReadMultipleHoldingRegisters(_, body) => swap_bytes(&mut body.values),
+        Response::ReadInputRegisters(_, body) => swap_bytes(&mut body.values),
+        Response::ReadWriteMultipleRegisters(_, body) => swap_bytes(&mut body.values),
+        Response::ReadFifoQueue(_, body) => {
+            body.values.iter_mut().for_each(|value| *value = value.swap_bytes())
+        }
+        Response::ReadFileRecord(_, body) => {
+            for record in &mut body.records {
+                record.iter_mut().for_each(|value| *value = value.swap_bytes())
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Swap the two bytes of each register carried by a write request
+fn swap_request_registers(request: &mut Request) {
+    match request {
+        Request::WriteSingleHoldingRegister(_, body) => body.value = body.value.swap_bytes(),
+        Request::WriteMultipleHoldingRegisters(_, body) => swap_bytes(&mut body.values),
+        Request::ReadWriteMultipleRegisters(_, body) => swap_bytes(&mut body.values),
+        Request::MaskWriteRegister(_, body) => {
+            body.and_mask = body.and_mask.swap_bytes();
+            body.or_mask = body.or_mask.swap_bytes();
+        }
+        _ => {}
+    }
+}
+
+fn swap_bytes(values: &mut [u8]) {
+    for register in values.chunks_exact_mut(2) {
+        register.swap(0, 1);
+    }
+}
+
+/// Dump the bytes of a frame sent or received, split into `parts`, at trace level
+///
+/// Only formatted when a subscriber is interested, so full payloads stay out of debug logs.
+#[cfg(feature = "tracing")]
+pub(crate) fn trace_frame(direction: &str, parts: &[&[u8]]) {
+    tracing::trace!("{} {}", direction, HexBytes(parts));
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn trace_frame(_direction: &str, _parts: &[&[u8]]) {}
+
+/// Warn that lenient mode dropped `byte` from a response, see [`TcpClientCodec::set_lenient`]
+#[cfg(feature = "tracing")]
+fn warn_stray_byte(byte: u8) {
+    tracing::warn!("Dropped stray byte {:02X} after the unit id of a response", byte);
+}
+
+#[cfg(not(feature = "tracing"))]
+fn warn_stray_byte(_byte: u8) {}
+
+/// Space separated hex bytes, as printed for requests and responses
+#[cfg(feature = "tracing")]
+struct HexBytes<'a>(&'a [&'a [u8]]);
+
+#[cfg(feature = "tracing")]
+impl std::fmt::Display for HexBytes<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, byte) in self.0.iter().flat_map(|part| part.iter()).enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{:02X}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod trace_frame_test {
+    use std::fmt::{Debug, Write};
+    use std::sync::{Arc, Mutex};
+
+    use bytes::BytesMut;
+    use tokio_util::codec::{Decoder, Encoder};
+    use tracing::field::{Field, Visit};
+    use tracing::level_filters::LevelFilter;
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Level, Metadata, Subscriber};
+
+    use crate::codec::TcpClientCodec;
+    use crate::frame::Frame;
+
+    /// Subscriber keeping the messages of the events up to `max_level`
+    struct Capture {
+        max_level: Level,
+        messages: Arc<Mutex<Vec<String>>>,
+    }
+
+    struct Message<'a>(&'a mut String);
+
+    impl Visit for Message<'_> {
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            if field.name() == "message" {
+                write!(self.0, "{:?}", value).unwrap();
+            }
+        }
+    }
+
+    impl Subscriber for Capture {
+        fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+            *metadata.level() <= self.max_level
+        }
+
+        fn max_level_hint(&self) -> Option<LevelFilter> {
+            Some(LevelFilter::from_level(self.max_level))
+        }
+
+        fn new_span(&self, _: &Attributes<'_>) -> Id {
+            Id::from_u64(1)
+        }
+
+        fn record(&self, _: &Id, _: &Record<'_>) {}
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let mut message = String::new();
+            event.record(&mut Message(&mut message));
+            self.messages.lock().unwrap().push(message);
+        }
+
+        fn enter(&self, _: &Id) {}
+
+        fn exit(&self, _: &Id) {}
+    }
+
+    /// Send a request and receive its response, returning the messages logged up to `max_level`
+    fn exchange(max_level: Level) -> Vec<String> {
+        let messages = Arc::new(Mutex::new(Vec::new()));
+        let subscriber = Capture {
+            max_level,
+            messages: messages.clone(),
+        };
+        tracing::subscriber::with_default(subscriber, || {
+            let mut codec = TcpClientCodec::default();
+            let request = Frame::tcp().read_multiple_holding_registers_request(0x01, 0x00, 0x01);
+            codec.encode(request, &mut BytesMut::new()).unwrap();
+            let v: Vec<u8> = vec![
+                0x00, 0x01, 0x00, 0x00, 0x00, 0x05, 0x01, 0x03, 0x02, 0x12, 0x34,
+            ];
+            codec.decode(&mut BytesMut::from(&v[..])).unwrap().unwrap();
+        });
+        let messages = messages.lock().unwrap().clone();
+        messages
+    }
+
+    #[test]
+    fn trace_level_test() {
+        let messages = exchange(Level::TRACE);
+        assert_eq!(
+            messages,
+            vec![
+                "Sent 00 01 00 00 00 06 01 03 00 00 00 01",
+                "Received 00 01 00 00 00 05 01 03 02 12 34",
+            ]
+        );
+    }
+
+    #[test]
+    fn debug_level_test() {
+        assert!(exchange(Level::DEBUG).is_empty());
+    }
+
+    #[test]
+    fn lenient_warning_test() {
+        let messages = Arc::new(Mutex::new(Vec::new()));
+        let subscriber = Capture {
+            max_level: Level::WARN,
+            messages: messages.clone(),
+        };
+        tracing::subscriber::with_default(subscriber, || {
+            let mut codec = TcpClientCodec::default();
+            codec.set_lenient(true);
+            let v: Vec<u8> = vec![
+                0x00, 0x01, 0x00, 0x00, 0x00, 0x06, 0x01, 0x21, 0x03, 0x02, 0x00, 0x2A,
+            ];
+            codec.decode(&mut BytesMut::from(&v[..])).unwrap().unwrap();
+        });
+        assert_eq!(
+            *messages.lock().unwrap(),
+            vec!["Dropped stray byte 21 after the unit id of a response"]
+        );
+    }
+}
@@ -0,0 +1,430 @@
+//! Conformance checks exercising the function codes of a device.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use easy_modbus::client::Client;
+//! use easy_modbus::conformance::run_read_write_suite;
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let mut client = Client::connect_tcp("127.0.0.1:502", None).await?;
+//!     let report = run_read_write_suite(&mut client, 0x01, 0x0000).await;
+//!     for result in &report.results {
+//!         println!("{:?}\t{:?}", result.function, result.outcome);
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+use crate::client::{unexpected, Client, Transport};
+use crate::frame::{request::Request, response::Response, Exception, Function};
+
+/// Outcome of a single conformance test
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Outcome {
+    /// The device answered as specified
+    Pass,
+
+    /// The request failed or the device answered wrongly, with the reason
+    Fail(String),
+
+    /// The device answered with an exception, e.g. for a function it does not implement
+    Exception(Exception),
+
+    /// Not run, because the test writing a value back had no value read first
+    Skipped,
+}
+
+/// Outcome of the test of one function code
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ConformanceResult {
+    /// Function exercised
+    pub function: Function,
+
+    /// How the device answered
+    pub outcome: Outcome,
+}
+
+/// Outcomes of a conformance suite, in the order the tests ran
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ConformanceReport {
+    /// Outcome of each function code
+    pub results: Vec<ConformanceResult>,
+}
+
+impl ConformanceReport {
+    /// Whether every test passed
+    pub fn is_clean(&self) -> bool {
+        self.results
+            .iter()
+            .all(|result| result.outcome == Outcome::Pass)
+    }
+
+    fn record(&mut self, function: Function, outcome: Outcome) {
+        self.results.push(ConformanceResult { function, outcome });
+    }
+}
+
+/// Exercise every read and write function code against coil, input and register `address`
+///
+/// Reads one coil, discrete input, holding register and input register, then writes the coil
+/// and holding register back through each write function (0x05, 0x06, 0x0F, 0x10, 0x16 and
+/// 0x17), so the device is left as it was found. A write whose value could not be read first is
+/// skipped. Every response must come from `slave` and writes must echo the request.
+pub async fn run_read_write_suite<T: Transport>(
+    client: &mut Client<T>,
+    slave: u8,
+    address: u16,
+) -> ConformanceReport {
+    let mut report = ConformanceReport::default();
+
+    let request = client.frame().read_coils_request(slave, address, 1);
+    let (outcome, coil) = check(client, request, |response| match response {
+        Response::ReadCoils(_, body) => first_bit(body.get_values()),
+        response => Err(unexpected(response).to_string()),
+    })
+    .await;
+    report.record(Function::ReadCoils, outcome);
+
+    let request = client.frame().read_discrete_request(slave, address, 1);
+    let (outcome, _) = check(client, request, |response| match response {
+        Response::ReadDiscreteInputs(_, body) => first_bit(body.get_values()),
+        response => Err(unexpected(response).to_string()),
+    })
+    .await;
+    report.record(Function::ReadDiscreteInputs, outcome);
+
+    let request = client
+        .frame()
+        .read_multiple_holding_registers_request(slave, address, 1);
+    let (outcome, register) = check(client, request, |response| match response {
+        Response::ReadMultipleHoldingRegisters(_, body) => first_register(body.get_values()),
+        response => Err(unexpected(response).to_string()),
+    })
+    .await;
+    report.record(Function::ReadMultipleHoldingRegisters, outcome);
+
+    let request = client
+        .frame()
+        .read_input_registers_request(slave, address, 1);
+    let (outcome, _) = check(client, request, |response| match response {
+        Response::ReadInputRegisters(_, body) => first_register(body.get_values()),
+        response => Err(unexpected(response).to_string()),
+    })
+    .await;
+    report.record(Function::ReadInputRegisters, outcome);
+
+    let outcome = match coil {
+        Some(coil) => {
+            let value = if coil { 0xFF00 } else { 0x0000 };
+            let request = client
+                .frame()
+                .write_single_coil_request(slave, address, value);
+            let (outcome, _) = check(client, request, |response| match response {
+                Response::WriteSingleCoil(_, body) => echo(
+                    (*body.get_coil_address(), *body.get_value()),
+                    (address, value),
+                ),
+                response => Err(unexpected(response).to_string()),
+            })
+            .await;
+            outcome
+        }
+        None => Outcome::Skipped,
+    };
+    report.record(Function::WriteSingleCoil, outcome);
+
+    let outcome = match register {
+        Some(register) => {
+            let request = client
+                .frame()
+                .write_single_holding_register_request(slave, address, register);
+            let (outcome, _) = check(client, request, |response| match response {
+                Response::WriteSingleHoldingRegister(_, body) => echo(
+                    (*body.get_register_address(), *body.get_value()),
+                    (address, register),
+                ),
+                response => Err(unexpected(response).to_string()),
+            })
+            .await;
+            outcome
+        }
+        None => Outcome::Skipped,
+    };
+    report.record(Function::WriteSingleHoldingRegister, outcome);
+
+    let outcome = match coil {
+        Some(coil) => {
+            let values = vec![coil as u8];
+            let request = client
+                .frame()
+                .write_multiple_coils_request(slave, address, 1, values);
+            let (outcome, _) = check(client, request, |response| match response {
+                Response::WriteMultipleCoils(_, body) => echo(
+                    (*body.get_first_address(), *body.get_coils_number()),
+                    (address, 1),
+                ),
+                response => Err(unexpected(response).to_string()),
+            })
+            .await;
+            outcome
+        }
+        None => Outcome::Skipped,
+    };
+    report.record(Function::WriteMultipleCoils, outcome);
+
+    let outcome = match register {
+        Some(register) => {
+            let request = client.frame().write_multiple_holding_registers_request(
+                slave,
+                address,
+                register.to_be_bytes().to_vec(),
+            );
+            let (outcome, _) = check(client, request, |response| match response {
+                Response::WriteMultipleHoldingRegisters(_, body) => echo(
+                    (*body.get_first_address(), *body.get_registers_number()),
+                    (address, 1),
+                ),
+                response => Err(unexpected(response).to_string()),
+            })
+            .await;
+            outcome
+        }
+        None => Outcome::Skipped,
+    };
+    report.record(Function::WriteMultipleHoldingRegisters, outcome);
+
+    let outcome = match register {
+        // Keeping every bit leaves the register unchanged
+        Some(_) => {
+            let request = client
+                .frame()
+                .mask_write_register_request(slave, address, 0xFFFF, 0x0000);
+            let (outcome, _) = check(client, request, |response| match response {
+                Response::MaskWriteRegister(_, body) => echo(
+                    (
+                        *body.get_reference_address(),
+                        *body.get_and_mask(),
+                        *body.get_or_mask(),
+                    ),
+                    (address, 0xFFFF, 0x0000),
+                ),
+                response => Err(unexpected(response).to_string()),
+            })
+            .await;
+            outcome
+        }
+        None => Outcome::Skipped,
+    };
+    report.record(Function::MaskWriteRegister, outcome);
+
+    let outcome = match register {
+        Some(register) => {
+            let request = client.frame().read_write_multiple_registers_request(
+                slave,
+                address,
+                1,
+                address,
+                register.to_be_bytes().to_vec(),
+            );
+            let (outcome, _) = check(client, request, |response| match response {
+                Response::ReadWriteMultipleRegisters(_, body) => {
+                    echo(first_register(body.get_values())?, register)
+                }
+                response => Err(unexpected(response).to_string()),
+            })
+            .await;
+            outcome
+        }
+        None => Outcome::Skipped,
+    };
+    report.record(Function::ReadWriteMultipleRegisters, outcome);
+
+    report
+}
+
+/// Send `request` and judge the response with `verify`, which may extract a value from it
+async fn check<T: Transport, V>(
+    client: &mut Client<T>,
+    request: Request,
+    verify: impl FnOnce(Response) -> Result<V, String>,
+) -> (Outcome, Option<V>) {
+    let slave = request.head().uid();
+    let response = match client.call(request).await {
+        Ok(response) => response,
+        Err(e) => return (Outcome::Fail(e.to_string()), None),
+    };
+    if response.head().uid() != slave {
+        let reason = format!("Answered by unit {}", response.head().uid());
+        return (Outcome::Fail(reason), None);
+    }
+    if let Some(exception) = response.as_exception() {
+        return (Outcome::Exception(exception.clone()), None);
+    }
+    match verify(response) {
+        Ok(value) => (Outcome::Pass, Some(value)),
+        Err(reason) => (Outcome::Fail(reason), None),
+    }
+}
+
+fn first_bit(values: &[u8]) -> Result<bool, String> {
+    match values.first() {
+        Some(byte) => Ok(byte & 0x01 == 0x01),
+        None => Err("No coil or discrete input state in response".to_string()),
+    }
+}
+
+fn first_register(values: &[u8]) -> Result<u16, String> {
+    match values {
+        [high, low, ..] => Ok(u16::from_be_bytes([*high, *low])),
+        _ => Err("No register value in response".to_string()),
+    }
+}
+
+fn echo<E: PartialEq + std::fmt::Debug>(actual: E, expected: E) -> Result<(), String> {
+    if actual == expected {
+        Ok(())
+    } else {
+        Err(format!("Expected {:?}, got {:?}", expected, actual))
+    }
+}
+
+#[cfg(test)]
+mod conformance_test {
+    use std::sync::{Arc, Mutex};
+
+    use tokio::io::duplex;
+    use tokio_util::codec::Framed;
+
+    use crate::client::Client;
+    use crate::codec::TcpServerCodec;
+    use crate::conformance::{run_read_write_suite, Outcome};
+    use crate::frame::{Exception, Frame, Function};
+    use crate::server::Server;
+    use crate::{Request, Response};
+
+    /// Coils, discrete inputs, holding registers and input registers of a simulated device
+    struct RegisterBank {
+        coils: Vec<bool>,
+        discrete_inputs: Vec<bool>,
+        holding_registers: Vec<u16>,
+        input_registers: Vec<u16>,
+    }
+
+    impl RegisterBank {
+        fn handle(&mut self, request: Request) -> Response {
+            let frame = Frame::tcp();
+            let uid = request.head().uid();
+            match request {
+                Request::ReadCoils(_, body) => {
+                    let coils = &self.coils[body.first_address as usize..][..1];
+                    frame.read_coils_response_bool(uid, coils)
+                }
+                Request::ReadDiscreteInputs(_, body) => {
+                    let input = self.discrete_inputs[body.first_address as usize];
+                    frame.read_discrete_response(uid, vec![input as u8])
+                }
+                Request::ReadMultipleHoldingRegisters(_, body) => {
+                    let values = &self.holding_registers[body.first_address as usize..][..1];
+                    frame.read_holding_register_response_u16(uid, values)
+                }
+                Request::ReadInputRegisters(_, body) => {
+                    let value = self.input_registers[body.first_address as usize];
+                    frame.read_input_register_response(uid, value.to_be_bytes().to_vec())
+                }
+                Request::WriteSingleCoil(_, body) => {
+                    self.coils[body.coil_address as usize] = body.value == 0xFF00;
+                    frame.write_single_coil_response_from_request(uid, &body)
+                }
+                Request::WriteSingleHoldingRegister(_, body) => {
+                    self.holding_registers[body.register_address as usize] = body.value;
+                    frame.write_single_holding_register_response_from_request(uid, &body)
+                }
+                Request::WriteMultipleCoils(_, body) => {
+                    self.coils[body.first_address as usize] = body.values[0] & 0x01 == 0x01;
+                    frame.write_multiple_coils_response_from_request(uid, &body)
+                }
+                Request::WriteMultipleHoldingRegisters(_, body) => {
+                    let value = u16::from_be_bytes([body.values[0], body.values[1]]);
+                    self.holding_registers[body.first_address as usize] = value;
+                    frame.write_multiple_holding_registers_response_from_request(uid, &body)
+                }
+                Request::MaskWriteRegister(_, body) => {
+                    let register = &mut self.holding_registers[body.reference_address as usize];
+                    *register = (*register & body.and_mask) | (body.or_mask & !body.and_mask);
+                    frame.mask_write_register_response_from_request(uid, &body)
+                }
+                Request::ReadWriteMultipleRegisters(_, body) => {
+                    let value = u16::from_be_bytes([body.values[0], body.values[1]]);
+                    self.holding_registers[body.write_address as usize] = value;
+                    let value = self.holding_registers[body.read_address as usize];
+                    frame.read_write_multiple_registers_response(uid, value.to_be_bytes().to_vec())
+                }
+                request => frame.exception_response(
+                    uid,
+                    request.head().function,
+                    Exception::IllegalFunction,
+                ),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn read_write_suite_test() {
+        let bank = Arc::new(Mutex::new(RegisterBank {
+            coils: vec![false, true],
+            discrete_inputs: vec![true, false],
+            holding_registers: vec![0x0000, 0x1234],
+            input_registers: vec![0x0000, 0xABCD],
+        }));
+
+        let (client_io, server_io) = duplex(256);
+        let server_bank = bank.clone();
+        tokio::spawn(async move {
+            let server = Server::new(move |request| server_bank.lock().unwrap().handle(request));
+            server.serve(Framed::new(server_io, TcpServerCodec)).await
+        });
+
+        let mut client = Client::tcp(client_io);
+        let report = run_read_write_suite(&mut client, 0x01, 0x0001).await;
+        assert!(report.is_clean(), "{:?}", report);
+        assert_eq!(report.results.len(), 10);
+
+        let bank = bank.lock().unwrap();
+        assert_eq!(bank.coils, vec![false, true]);
+        assert_eq!(bank.holding_registers, vec![0x0000, 0x1234]);
+    }
+
+    #[tokio::test]
+    async fn read_write_suite_exception_test() {
+        let (client_io, server_io) = duplex(256);
+        tokio::spawn(async move {
+            let server = Server::new(|request: Request| {
+                Frame::tcp().exception_response(
+                    request.head().uid(),
+                    request.head().function.clone(),
+                    Exception::IllegalDataAddress,
+                )
+            });
+            server.serve(Framed::new(server_io, TcpServerCodec)).await
+        });
+
+        let mut client = Client::tcp(client_io);
+        let report = run_read_write_suite(&mut client, 0x01, 0x0001).await;
+        assert!(!report.is_clean());
+
+        let outcome = |function| {
+            let result = report.results.iter().find(|r| r.function == function);
+            result.unwrap().outcome.clone()
+        };
+        let exception = Outcome::Exception(Exception::IllegalDataAddress);
+        assert_eq!(outcome(Function::ReadCoils), exception);
+        assert_eq!(outcome(Function::ReadInputRegisters), exception);
+        assert_eq!(outcome(Function::WriteSingleCoil), Outcome::Skipped);
+        assert_eq!(
+            outcome(Function::ReadWriteMultipleRegisters),
+            Outcome::Skipped
+        );
+    }
+}
@@ -0,0 +1,233 @@
+//! Error type of Easy Modbus.
+
+use std::fmt::{self, Formatter};
+use std::io;
+
+use crate::frame::Exception;
+
+/// Result with a [`ModbusError`]
+pub type Result<T> = std::result::Result<T, ModbusError>;
+
+/// Errors raised by Easy Modbus
+#[derive(Debug)]
+pub enum ModbusError {
+    /// The underlying transport failed
+    Io(io::Error),
+
+    /// Part of a RTU frame arrived but the rest of it stalled
+    FrameAssemblyTimeout,
+
+    /// A frame to encode has a PDU of more than 253 bytes
+    FrameTooLarge(usize),
+
+    /// A frame violates the Modbus protocol
+    InvalidFrame(String),
+
+    /// A frame carries a function code Easy Modbus does not know
+    InvalidFunction(u8),
+
+    /// An exception code the Modbus specification does not define
+    InvalidExceptionCode(u8),
+
+    /// The length of a frame, or a count it announces, does not match its content
+    InvalidLength(String),
+
+    /// The stream ended in the middle of a frame
+    UnexpectedEof,
+
+    /// The server answered with an exception
+    Exception(Exception),
+
+    /// The CRC of a RTU frame does not match its content
+    CrcMismatch {
+        /// The whole frame, CRC included
+        bytes: Vec<u8>,
+
+        /// CRC computed over the frame
+        expected: u16,
+
+        /// CRC received
+        actual: u16,
+    },
+
+    /// The LRC of an ASCII frame does not match its content
+    LrcMismatch {
+        /// The decoded bytes of the frame, LRC included
+        bytes: Vec<u8>,
+
+        /// LRC computed over the frame
+        expected: u8,
+
+        /// LRC received
+        actual: u8,
+    },
+
+    /// No response arrived in time
+    Timeout,
+
+    /// Values read back after a write differ from the ones written, at the listed addresses
+    VerifyMismatch(Vec<u16>),
+
+    /// A TCP response carries the transaction id of no request sent
+    TransactionIdMismatch {
+        /// Transaction id of the request awaiting its response
+        expected: u16,
+
+        /// Transaction id of the response
+        actual: u16,
+    },
+}
+
+impl ModbusError {
+    /// Whether the error may not happen again when the request is repeated
+    ///
+    /// [`ModbusError::Timeout`], [`ModbusError::FrameAssemblyTimeout`],
+    /// [`ModbusError::CrcMismatch`] and [`ModbusError::LrcMismatch`] are, as a frame lost or
+    /// corrupted on a noisy line. Anything else, like an exception answered by the device, is
+    /// not.
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            ModbusError::Timeout
+                | ModbusError::FrameAssemblyTimeout
+                | ModbusError::CrcMismatch { .. }
+                | ModbusError::LrcMismatch { .. }
+        )
+    }
+
+    /// Copy the error, keeping only the kind and message of an I/O error
+    #[cfg(feature = "codec")]
+    pub(crate) fn duplicate(&self) -> ModbusError {
+        match self {
+            ModbusError::Io(e) => ModbusError::Io(io::Error::new(e.kind(), e.to_string())),
+            ModbusError::FrameAssemblyTimeout => ModbusError::FrameAssemblyTimeout,
+            ModbusError::FrameTooLarge(len) => ModbusError::FrameTooLarge(*len),
+            ModbusError::InvalidFrame(message) => ModbusError::InvalidFrame(message.clone()),
+            ModbusError::InvalidFunction(code) => ModbusError::InvalidFunction(*code),
+            ModbusError::InvalidExceptionCode(code) => ModbusError::InvalidExceptionCode(*code),
+            ModbusError::InvalidLength(message) => ModbusError::InvalidLength(message.clone()),
+            ModbusError::UnexpectedEof => ModbusError::UnexpectedEof,
+            ModbusError::Exception(exception) => ModbusError::Exception(exception.clone()),
+            ModbusError::Timeout => ModbusError::Timeout,
+            ModbusError::CrcMismatch {
+                bytes,
+                expected,
+                actual,
+            } => ModbusError::CrcMismatch {
+                bytes: bytes.clone(),
+                expected: *expected,
+                actual: *actual,
+            },
+            ModbusError::LrcMismatch {
+                bytes,
+                expected,
+                actual,
+            } => ModbusError::LrcMismatch {
+                bytes: bytes.clone(),
+                expected: *expected,
+                actual: *actual,
+            },
+            ModbusError::VerifyMismatch(addresses) => {
+                ModbusError::VerifyMismatch(addresses.clone())
+            }
+            ModbusError::TransactionIdMismatch { expected, actual } => {
+                ModbusError::TransactionIdMismatch {
+                    expected: *expected,
+                    actual: *actual,
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for ModbusError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ModbusError::Io(e) => write!(f, "{}", e),
+            ModbusError::FrameAssemblyTimeout => write!(f, "Timed out assembling a frame"),
+            ModbusError::FrameTooLarge(len) => {
+                write!(f, "Frame too large: PDU of {} bytes exceeds 253", len)
+            }
+            ModbusError::InvalidFrame(message) => write!(f, "Invalid frame: {}", message),
+            ModbusError::InvalidFunction(code) => {
+                write!(f, "Invalid function code: 0x{:0>2X}", code)
+            }
+            ModbusError::InvalidExceptionCode(code) => {
+                write!(f, "Invalid exception code: 0x{:0>2X}", code)
+            }
+            ModbusError::InvalidLength(message) => write!(f, "Invalid length: {}", message),
+            ModbusError::UnexpectedEof => write!(f, "Stream ended in the middle of a frame"),
+            ModbusError::Exception(exception) => write!(f, "Exception response: {:?}", exception),
+            ModbusError::Timeout => write!(f, "Timed out waiting for a response"),
+            ModbusError::CrcMismatch {
+                expected, actual, ..
+            } => write!(
+                f,
+                "CRC mismatch: expected 0x{:0>4X}, got 0x{:0>4X}",
+                expected, actual
+            ),
+            ModbusError::LrcMismatch {
+                expected, actual, ..
+            } => write!(
+                f,
+                "LRC mismatch: expected 0x{:0>2X}, got 0x{:0>2X}",
+                expected, actual
+            ),
+            ModbusError::VerifyMismatch(addresses) => {
+                write!(f, "Read back differs from the write at")?;
+                for address in addresses {
+                    write!(f, " 0x{:0>4X}", address)?;
+                }
+                Ok(())
+            }
+            ModbusError::TransactionIdMismatch { expected, actual } => write!(
+                f,
+                "Transaction id mismatch: expected 0x{:0>4X}, got 0x{:0>4X}",
+                expected, actual
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ModbusError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ModbusError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ModbusError {
+    /// Wrap `e`, or take back the error of the crate it carries
+    fn from(e: io::Error) -> Self {
+        match e.get_ref().map(|inner| inner.is::<ModbusError>()) {
+            Some(true) => *e.into_inner().unwrap().downcast::<ModbusError>().unwrap(),
+            _ => ModbusError::Io(e),
+        }
+    }
+}
+
+impl From<ModbusError> for io::Error {
+    fn from(e: ModbusError) -> Self {
+        match e {
+            ModbusError::Io(e) => e,
+            ModbusError::FrameAssemblyTimeout | ModbusError::Timeout => {
+                io::Error::new(io::ErrorKind::TimedOut, e)
+            }
+            ModbusError::FrameTooLarge(_) => io::Error::new(io::ErrorKind::InvalidInput, e),
+            ModbusError::InvalidFunction(_) => io::Error::new(io::ErrorKind::Unsupported, e),
+            ModbusError::UnexpectedEof => io::Error::new(io::ErrorKind::UnexpectedEof, e),
+            ModbusError::Exception(ref exception) => io::Error::new(exception.as_error_kind(), e),
+            ModbusError::InvalidFrame(_)
+            | ModbusError::InvalidExceptionCode(_)
+            | ModbusError::InvalidLength(_)
+            | ModbusError::CrcMismatch { .. }
+            | ModbusError::LrcMismatch { .. }
+            | ModbusError::VerifyMismatch(_)
+            | ModbusError::TransactionIdMismatch { .. } => {
+                io::Error::new(io::ErrorKind::InvalidData, e)
+            }
+        }
+    }
+}
@@ -0,0 +1,382 @@
+use crate::error::{ModbusError, Result};
+use crate::frame::response::Response;
+use crate::frame::{Frame, Function, MAX_FIFO_COUNT};
+
+/// Most coils or discrete inputs a read response may carry
+const MAX_READ_BITS_BYTES: usize = 250;
+
+/// Most registers a read response may carry
+const MAX_READ_REGISTERS: usize = 125;
+
+/// Most coils a write multiple coils request may set
+const MAX_WRITE_COILS: u16 = 0x07B0;
+
+/// Most registers a write multiple registers request may set
+const MAX_WRITE_REGISTERS: u16 = 0x007B;
+
+/// Response builder checking the protocol invariants before a response is created
+///
+/// Where [`Frame`] derives what it can (e.g. the byte count) from the values, the builder takes
+/// every field as given and refuses inconsistent ones, to catch server bugs early.
+///
+/// # Examples
+///
+/// ```
+/// use easy_modbus::{Frame, Function, ResponseBuilder};
+///
+/// let frame = Frame::tcp();
+/// let response = ResponseBuilder::new(0x01, Function::ReadCoils)
+///     .byte_count(2)
+///     .values(vec![0x00, 0x01])
+///     .build(&frame);
+/// assert!(response.is_ok());
+///
+/// let response = ResponseBuilder::new(0x01, Function::ReadCoils)
+///     .byte_count(3)
+///     .values(vec![0x00, 0x01])
+///     .build(&frame);
+/// assert!(response.is_err());
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ResponseBuilder {
+    unit_id: u8,
+    function: Function,
+    byte_count: Option<u8>,
+    values: Option<Vec<u8>>,
+    address: Option<u16>,
+    quantity: Option<u16>,
+    value: Option<u16>,
+    status: Option<u16>,
+    event_count: Option<u16>,
+    run_indicator_status: Option<u8>,
+    and_mask: Option<u16>,
+    or_mask: Option<u16>,
+    output_data: Option<u8>,
+    sub_function: Option<u16>,
+}
+
+impl ResponseBuilder {
+    /// Start building a normal response of `function`
+    pub fn new(unit_id: u8, function: Function) -> Self {
+        ResponseBuilder {
+            unit_id,
+            function,
+            byte_count: None,
+            values: None,
+            address: None,
+            quantity: None,
+            value: None,
+            status: None,
+            event_count: None,
+            run_indicator_status: None,
+            and_mask: None,
+            or_mask: None,
+            output_data: None,
+            sub_function: None,
+        }
+    }
+
+    /// Byte count of a read or report server id response, the length of the values by default
+    pub fn byte_count(mut self, byte_count: u8) -> Self {
+        self.byte_count = Some(byte_count);
+        self
+    }
+
+    /// Values of a read or read FIFO queue response, or the server id of a report server id
+    /// response
+    pub fn values(mut self, values: Vec<u8>) -> Self {
+        self.values = Some(values);
+        self
+    }
+
+    /// Address of the first coil or register of a write response
+    pub fn address(mut self, address: u16) -> Self {
+        self.address = Some(address);
+        self
+    }
+
+    /// Number of coils or registers of a write multiple response
+    pub fn quantity(mut self, quantity: u16) -> Self {
+        self.quantity = Some(quantity);
+        self
+    }
+
+    /// Value of a write single response, or data of a diagnostics response
+    pub fn value(mut self, value: u16) -> Self {
+        self.value = Some(value);
+        self
+    }
+
+    /// Status word of a get comm event counter response
+    pub fn status(mut self, status: u16) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    /// Event count of a get comm event counter response
+    pub fn event_count(mut self, event_count: u16) -> Self {
+        self.event_count = Some(event_count);
+        self
+    }
+
+    /// Run indicator status of a report server id response
+    pub fn run_indicator_status(mut self, run_indicator_status: u8) -> Self {
+        self.run_indicator_status = Some(run_indicator_status);
+        self
+    }
+
+    /// AND mask of a mask write register response
+    pub fn and_mask(mut self, and_mask: u16) -> Self {
+        self.and_mask = Some(and_mask);
+        self
+    }
+
+    /// OR mask of a mask write register response
+    pub fn or_mask(mut self, or_mask: u16) -> Self {
+        self.or_mask = Some(or_mask);
+        self
+    }
+
+    /// Output data of a read exception status response
+    pub fn output_data(mut self, output_data: u8) -> Self {
+        self.output_data = Some(output_data);
+        self
+    }
+
+    /// Sub-function of a diagnostics response
+    pub fn sub_function(mut self, sub_function: u16) -> Self {
+        self.sub_function = Some(sub_function);
+        self
+    }
+
+    /// Check the fields and create the response
+    pub fn build(self, frame: &Frame) -> Result<Response> {
+        let unit_id = self.unit_id;
+        let function = self.function.clone();
+        let response = match function {
+            Function::ReadCoils | Function::ReadDiscreteInputs => {
+                let values = self.checked_values(&function)?;
+                if values.is_empty() || values.len() > MAX_READ_BITS_BYTES {
+                    return Err(invalid(format!(
+                        "{:?} response carries {} bytes, expect 1 to {}",
+                        function,
+                        values.len(),
+                        MAX_READ_BITS_BYTES
+                    )));
+                }
+                if function == Function::ReadCoils {
+                    frame.read_coils_response(unit_id, values)
+                } else {
+                    frame.read_discrete_response(unit_id, values)
+                }
+            }
+            Function::ReadMultipleHoldingRegisters
+            | Function::ReadInputRegisters
+            | Function::ReadWriteMultipleRegisters => {
+                let values = self.checked_values(&function)?;
+                if values.is_empty() || values.len() % 2 != 0 {
+                    return Err(invalid(format!(
+                        "{:?} response carries {} bytes, expect whole registers",
+                        function,
+                        values.len()
+                    )));
+                }
+                if values.len() / 2 > MAX_READ_REGISTERS {
+                    return Err(invalid(format!(
+                        "{:?} response carries {} registers, expect at most {}",
+                        function,
+                        values.len() / 2,
+                        MAX_READ_REGISTERS
+                    )));
+                }
+                match function {
+                    Function::ReadInputRegisters => {
+                        frame.read_input_register_response(unit_id, values)
+                    }
+                    Function::ReadWriteMultipleRegisters => {
+                        frame.read_write_multiple_registers_response(unit_id, values)
+                    }
+                    _ => frame.read_holding_register_response(unit_id, values),
+                }
+            }
+            Function::WriteSingleCoil => {
+                let address = required(self.address, "address")?;
+                let value = required(self.value, "value")?;
+                if value != 0x0000 && value != 0xFF00 {
+                    return Err(invalid(format!(
+                        "Coil value 0x{:0>4X} is neither 0x0000 nor 0xFF00",
+                        value
+                    )));
+                }
+                frame.write_single_coil_response(unit_id, address, value)
+            }
+            Function::WriteSingleHoldingRegister => {
+                let address = required(self.address, "address")?;
+                let value = required(self.value, "value")?;
+                frame.write_single_holding_register_response(unit_id, address, value)
+            }
+            Function::WriteMultipleCoils => {
+                let address = required(self.address, "address")?;
+                let quantity = check_quantity(&function, self.quantity, MAX_WRITE_COILS)?;
+                frame.write_multiple_coils_response(unit_id, address, quantity)
+            }
+            Function::WriteMultipleHoldingRegisters => {
+                let address = required(self.address, "address")?;
+                let quantity = check_quantity(&function, self.quantity, MAX_WRITE_REGISTERS)?;
+                frame.write_multiple_holding_registers_response(unit_id, address, quantity)
+            }
+            Function::GetCommEventCounter => {
+                let status = required(self.status, "status")?;
+                let event_count = required(self.event_count, "event count")?;
+                if status != 0x0000 && status != 0xFFFF {
+                    return Err(invalid(format!(
+                        "Status 0x{:0>4X} is neither 0x0000 nor 0xFFFF",
+                        status
+                    )));
+                }
+                frame.get_comm_event_counter_response(unit_id, status, event_count)
+            }
+            Function::ReportServerId => {
+                let run_indicator_status =
+                    required(self.run_indicator_status, "run indicator status")?;
+                let server_id = self.values.unwrap_or_default();
+                let expected = server_id.len() + 1;
+                if let Some(byte_count) = self.byte_count {
+                    if byte_count as usize != expected {
+                        return Err(invalid(format!(
+                            "Byte count {} does not match {} bytes of data",
+                            byte_count, expected
+                        )));
+                    }
+                }
+                if run_indicator_status != 0x00 && run_indicator_status != 0xFF {
+                    return Err(invalid(format!(
+                        "Run indicator status 0x{:0>2X} is neither 0x00 nor 0xFF",
+                        run_indicator_status
+                    )));
+                }
+                frame.report_server_id_response(unit_id, server_id, run_indicator_status)
+            }
+            Function::MaskWriteRegister => {
+                let address = required(self.address, "address")?;
+                let and_mask = required(self.and_mask, "AND mask")?;
+                let or_mask = required(self.or_mask, "OR mask")?;
+                frame.mask_write_register_response(unit_id, address, and_mask, or_mask)
+            }
+            Function::ReadExceptionStatus => {
+                let output_data = required(self.output_data, "output data")?;
+                frame.read_exception_status_response(unit_id, output_data)
+            }
+            Function::Diagnostics => {
+                let sub_function = required(self.sub_function, "sub-function")?;
+                let data = required(self.value, "value")?;
+                frame.diagnostics_response(unit_id, sub_function, data)
+            }
+            Function::ReadFifoQueue => {
+                let values = self.checked_values(&function)?;
+                if values.len() % 2 != 0 || values.len() / 2 > MAX_FIFO_COUNT as usize {
+                    return Err(invalid(format!(
+                        "{:?} response carries {} bytes, expect at most {} whole registers",
+                        function,
+                        values.len(),
+                        MAX_FIFO_COUNT
+                    )));
+                }
+                let values = values
+                    .chunks_exact(2)
+                    .map(|v| u16::from_be_bytes([v[0], v[1]]))
+                    .collect();
+                frame.read_fifo_queue_response(unit_id, values)
+            }
+            Function::ReadDeviceIdentification | Function::ReadFileRecord => {
+                return Err(invalid(format!("{:?} is not supported", function)));
+            }
+        };
+        Ok(response)
+    }
+
+    /// Take the values of a read response, checking them against the byte count
+    fn checked_values(self, function: &Function) -> Result<Vec<u8>> {
+        let values = required(self.values, "values")?;
+        if let Some(byte_count) = self.byte_count {
+            if byte_count as usize != values.len() {
+                return Err(invalid(format!(
+                    "{:?} byte count {} does not match {} bytes of values",
+                    function,
+                    byte_count,
+                    values.len()
+                )));
+            }
+        }
+        Ok(values)
+    }
+}
+
+fn required<T>(field: Option<T>, name: &str) -> Result<T> {
+    field.ok_or_else(|| invalid(format!("Missing {}", name)))
+}
+
+fn check_quantity(function: &Function, quantity: Option<u16>, max: u16) -> Result<u16> {
+    let quantity = required(quantity, "quantity")?;
+    if quantity == 0 || quantity > max {
+        return Err(invalid(format!(
+            "{:?} quantity {} is out of 1 to {}",
+            function, quantity, max
+        )));
+    }
+    Ok(quantity)
+}
+
+fn invalid(message: String) -> ModbusError {
+    ModbusError::InvalidFrame(message)
+}
+
+#[cfg(test)]
+mod response_builder_test {
+    use crate::error::ModbusError;
+    use crate::frame::builder::ResponseBuilder;
+    use crate::frame::{Frame, Function};
+
+    #[test]
+    fn read_coils_test() {
+        let response_l = ResponseBuilder::new(0x01, Function::ReadCoils)
+            .byte_count(2)
+            .values(vec![0xCD, 0x01])
+            .build(&Frame::tcp())
+            .unwrap();
+        let response_r = Frame::tcp().read_coils_response(0x01, vec![0xCD, 0x01]);
+        assert_eq!(response_l, response_r);
+
+        let response = ResponseBuilder::new(0x01, Function::ReadCoils)
+            .byte_count(3)
+            .values(vec![0xCD, 0x01])
+            .build(&Frame::tcp());
+        assert!(matches!(response, Err(ModbusError::InvalidFrame(_))));
+    }
+
+    #[test]
+    fn invalid_fields_test() {
+        let frame = Frame::tcp();
+        let response = ResponseBuilder::new(0x01, Function::ReadMultipleHoldingRegisters)
+            .values(vec![0x00, 0x01, 0x02])
+            .build(&frame);
+        assert!(response.is_err());
+
+        let response = ResponseBuilder::new(0x01, Function::WriteSingleCoil)
+            .address(0x00AC)
+            .value(0x0001)
+            .build(&frame);
+        assert!(response.is_err());
+
+        let response = ResponseBuilder::new(0x01, Function::WriteMultipleHoldingRegisters)
+            .address(0x0000)
+            .quantity(0x007C)
+            .build(&frame);
+        assert!(response.is_err());
+
+        let response = ResponseBuilder::new(0x01, Function::WriteMultipleCoils)
+            .address(0x0000)
+            .build(&frame);
+        assert!(response.is_err());
+    }
+}
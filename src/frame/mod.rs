@@ -2,12 +2,25 @@ use std::collections::HashMap;
 use std::io::ErrorKind;
 use std::sync::Mutex;
 
+use bytes::{Bytes, BytesMut};
+
+use crate::error::ModbusError;
 use crate::frame::request::*;
 use crate::frame::response::*;
 
+pub mod builder;
 pub mod request;
 pub mod response;
 
+/// MEI type of Read Device Identification, encapsulated in function code 0x2B
+pub(crate) const MEI_READ_DEVICE_IDENTIFICATION: u8 = 0x0E;
+
+/// Most values a Read FIFO Queue response may carry
+pub(crate) const MAX_FIFO_COUNT: u16 = 31;
+
+/// Reference type leading each sub-request and sub-response of Read File Record
+pub(crate) const FILE_RECORD_REFERENCE_TYPE: u8 = 0x06;
+
 /// Modbus Frame
 #[derive(Debug)]
 pub struct Frame {
@@ -241,12 +254,245 @@ impl Frame {
         Request::WriteMultipleHoldingRegisters(head, request_body)
     }
 
+    /// Create a write multiple holding registers request from register values (Function Code: 0x10)
+    ///
+    /// The values are packed as big endian bytes while iterating, without collecting them first.
+    ///
+    /// * `unit_id` -  Server address
+    /// * `address` - Address of first holding registers to write
+    /// * `values` - New values of holding registers
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use easy_modbus::Frame;
+    /// let request =
+    ///     Frame::tcp().write_multiple_holding_registers_request_iter(0x0B, 0x0012, [0x0B0A, 0xC102]);
+    /// ```
+    pub fn write_multiple_holding_registers_request_iter(
+        &self,
+        unit_id: u8,
+        address: u16,
+        values: impl IntoIterator<Item = u16>,
+    ) -> Request {
+        let values = values.into_iter();
+        let mut bytes = Vec::with_capacity(values.size_hint().0 * 2);
+        for value in values {
+            bytes.extend_from_slice(&value.to_be_bytes());
+        }
+        self.write_multiple_holding_registers_request(unit_id, address, bytes)
+    }
+
+    /// Create a get comm event counter request (Function Code: 0x0B)
+    ///
+    /// * `unit_id` -  Server address
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use easy_modbus::Frame;
+    /// let request = Frame::rtu().get_comm_event_counter_request(0x0B);
+    /// ```
+    pub fn get_comm_event_counter_request(&self, unit_id: u8) -> Request {
+        let function = Function::GetCommEventCounter;
+        let request_body = GetCommEventCounterRequest::new();
+        let head = self.head(unit_id, function, request_body.len(), false);
+        Request::GetCommEventCounter(head, request_body)
+    }
+
+    /// Create a report server id request (Function Code: 0x11)
+    ///
+    /// * `unit_id` -  Server address
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use easy_modbus::Frame;
+    /// let request = Frame::rtu().report_server_id_request(0x0B);
+    /// ```
+    pub fn report_server_id_request(&self, unit_id: u8) -> Request {
+        let function = Function::ReportServerId;
+        let request_body = ReportServerIdRequest::new();
+        let head = self.head(unit_id, function, request_body.len(), false);
+        Request::ReportServerId(head, request_body)
+    }
+
+    /// Create a mask write register request (Function Code: 0x16)
+    ///
+    /// * `unit_id` -  Server address
+    /// * `address` - Address of holding register to modify
+    /// * `and_mask` - Bits of the current value to keep
+    /// * `or_mask` - Bits to set among the ones not kept
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use easy_modbus::Frame;
+    /// let request = Frame::tcp().mask_write_register_request(0x01, 0x0004, 0x00F2, 0x0025);
+    /// ```
+    pub fn mask_write_register_request(
+        &self,
+        unit_id: u8,
+        address: u16,
+        and_mask: u16,
+        or_mask: u16,
+    ) -> Request {
+        let function = Function::MaskWriteRegister;
+        let request_body = MaskWriteRegisterRequest::new(address, and_mask, or_mask);
+        let head = self.head(unit_id, function, request_body.len(), false);
+        Request::MaskWriteRegister(head, request_body)
+    }
+
+    /// Create a read/write multiple registers request (Function Code: 0x17)
+    ///
+    /// The server writes the registers before reading, within one transaction.
+    ///
+    /// * `unit_id` -  Server address
+    /// * `read_address` - Address of first holding register to read
+    /// * `read_number` - Number of holding registers to read
+    /// * `write_address` - Address of first holding register to write
+    /// * `values` - New values of holding registers
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use easy_modbus::Frame;
+    /// let request = Frame::tcp().read_write_multiple_registers_request(
+    ///     0x01,
+    ///     0x0003,
+    ///     0x0006,
+    ///     0x000E,
+    ///     vec![0x00, 0xFF, 0x00, 0xFF, 0x00, 0xFF],
+    /// );
+    /// ```
+    pub fn read_write_multiple_registers_request(
+        &self,
+        unit_id: u8,
+        read_address: u16,
+        read_number: u16,
+        write_address: u16,
+        values: Vec<u8>,
+    ) -> Request {
+        let function = Function::ReadWriteMultipleRegisters;
+        let request_body = ReadWriteMultipleRegistersRequest::new(
+            read_address,
+            read_number,
+            write_address,
+            values,
+        );
+        let head = self.head(unit_id, function, request_body.len(), false);
+        Request::ReadWriteMultipleRegisters(head, request_body)
+    }
+
+    /// Create a read device identification request (Function Code: 0x2B / MEI Type: 0x0E)
+    ///
+    /// * `unit_id` -  Server address
+    /// * `read_device_id_code` - 0x01 basic, 0x02 regular, 0x03 extended or 0x04 one object
+    /// * `object_id` - Object to start reading at
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use easy_modbus::Frame;
+    /// let request = Frame::tcp().read_device_identification_request(0x01, 0x01, 0x00);
+    /// ```
+    pub fn read_device_identification_request(
+        &self,
+        unit_id: u8,
+        read_device_id_code: u8,
+        object_id: u8,
+    ) -> Request {
+        let function = Function::ReadDeviceIdentification;
+        let request_body = ReadDeviceIdentificationRequest::new(read_device_id_code, object_id);
+        let head = self.head(unit_id, function, request_body.len(), false);
+        Request::ReadDeviceIdentification(head, request_body)
+    }
+
+    /// Create a read exception status request (Function Code: 0x07)
+    ///
+    /// Serial line only.
+    ///
+    /// * `unit_id` -  Server address
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use easy_modbus::Frame;
+    /// let request = Frame::rtu().read_exception_status_request(0x11);
+    /// ```
+    pub fn read_exception_status_request(&self, unit_id: u8) -> Request {
+        let function = Function::ReadExceptionStatus;
+        let request_body = ReadExceptionStatusRequest::new();
+        let head = self.head(unit_id, function, request_body.len(), false);
+        Request::ReadExceptionStatus(head, request_body)
+    }
+
+    /// Create a diagnostics request (Function Code: 0x08)
+    ///
+    /// Serial line only.
+    ///
+    /// * `unit_id` -  Server address
+    /// * `sub_function` - Sub-function code, see [`DiagnosticsSubFunction`]
+    /// * `data` - Data of the sub-function, e.g. the value to echo for Return Query Data
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use easy_modbus::{DiagnosticsSubFunction, Frame};
+    /// let sub_function = DiagnosticsSubFunction::ReturnQueryData.to_code();
+    /// let request = Frame::rtu().diagnostics_request(0x11, sub_function, 0xA537);
+    /// ```
+    pub fn diagnostics_request(&self, unit_id: u8, sub_function: u16, data: u16) -> Request {
+        let function = Function::Diagnostics;
+        let request_body = DiagnosticsRequest::new(sub_function, data);
+        let head = self.head(unit_id, function, request_body.len(), false);
+        Request::Diagnostics(head, request_body)
+    }
+
+    /// Create a read FIFO queue request (Function Code: 0x18)
+    ///
+    /// * `unit_id` -  Server address
+    /// * `fifo_pointer_address` - Address of the FIFO queue
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use easy_modbus::Frame;
+    /// let request = Frame::tcp().read_fifo_queue_request(0x01, 0x04DE);
+    /// ```
+    pub fn read_fifo_queue_request(&self, unit_id: u8, fifo_pointer_address: u16) -> Request {
+        let function = Function::ReadFifoQueue;
+        let request_body = ReadFifoQueueRequest::new(fifo_pointer_address);
+        let head = self.head(unit_id, function, request_body.len(), false);
+        Request::ReadFifoQueue(head, request_body)
+    }
+
+    /// Create a read file record request (Function Code: 0x14)
+    ///
+    /// * `unit_id` -  Server address
+    /// * `records` - Records to read, at most 35
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use easy_modbus::{FileRecord, Frame};
+    /// let records = vec![FileRecord::new(0x0004, 0x0001, 0x0002)];
+    /// let request = Frame::tcp().read_file_record_request(0x01, records);
+    /// ```
+    pub fn read_file_record_request(&self, unit_id: u8, records: Vec<FileRecord>) -> Request {
+        let function = Function::ReadFileRecord;
+        let request_body = ReadFileRecordRequest::new(records);
+        let head = self.head(unit_id, function, request_body.len(), false);
+        Request::ReadFileRecord(head, request_body)
+    }
+
     /// Create a read coils response (Function Code: 0x01)
     ///
     /// * `unit_id` -  Server address
     /// * `values` - Coil input values, Values of each coil input is binary (0 for off, 1 for on).
-    /// First requested coil input is as least significant bit of first byte in reply. If number of
-    /// coils inputs is not a multiple of 8, most significant bits of last byte will be stuffed zeros.
+    ///   First requested coil input is as least significant bit of first byte in reply. If number
+    ///   of coils inputs is not a multiple of 8, most significant bits of last byte will be stuffed
+    ///   zeros.
     ///
     /// # Examples
     ///
@@ -261,6 +507,28 @@ impl Frame {
         Response::ReadCoils(head, response_body)
     }
 
+    /// Create a read coils response from coil states (Function Code: 0x01)
+    ///
+    /// The states are packed eight to a byte, the first in the least significant bit, and the
+    /// last byte is padded with zeros.
+    ///
+    /// * `unit_id` -  Server address
+    /// * `values` - Coil states
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use easy_modbus::Frame;
+    /// let response = Frame::tcp().read_coils_response_bool(0x0B, &[true, false, true]);
+    /// ```
+    pub fn read_coils_response_bool(&self, unit_id: u8, values: &[bool]) -> Response {
+        let mut bytes = vec![0; values.len().div_ceil(8)];
+        for (i, _) in values.iter().enumerate().filter(|(_, &value)| value) {
+            bytes[i / 8] |= 0x01 << (i % 8);
+        }
+        self.read_coils_response(unit_id, bytes)
+    }
+
     /// Create a read discrete response (Function Code: 0x02)
     ///
     /// * `unit_id` - Server address
@@ -297,6 +565,27 @@ impl Frame {
         Response::ReadMultipleHoldingRegisters(head, response_body)
     }
 
+    /// Create a read holding register response from register values (Function Code: 0x03)
+    ///
+    /// The values are packed as big endian bytes.
+    ///
+    /// * `unit_id` - Server address
+    /// * `values` - Register values
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use easy_modbus::Frame;
+    /// let response = Frame::tcp().read_holding_register_response_u16(0x0B, &[0xAE41, 0x5652]);
+    /// ```
+    pub fn read_holding_register_response_u16(&self, unit_id: u8, values: &[u16]) -> Response {
+        let bytes = values
+            .iter()
+            .flat_map(|value| value.to_be_bytes())
+            .collect();
+        self.read_holding_register_response(unit_id, bytes)
+    }
+
     /// Create a read input register response (Function Code: 0x04)
     ///
     /// * `unit_id` - Server address
@@ -405,78 +694,444 @@ impl Frame {
         Response::WriteMultipleHoldingRegisters(head, response_body)
     }
 
-    /// Create a exception response
+    /// Create a get comm event counter response (Function Code: 0x0B)
     ///
     /// * `unit_id` - Server address
-    /// * `function` - Modbus Function enum
-    /// * `exception` - Modbus Exception enum
+    /// * `status` - 0xFFFF while a previous command is still processed, 0x0000 otherwise
+    /// * `event_count` - Number of successfully completed messages
     ///
     /// # Examples
     ///
     /// ```
-    /// use easy_modbus::{Exception, Frame, Function};
-    /// let response = Frame::tcp().exception_response(
-    ///     0x0A,
-    ///     Function::ReadCoils,
-    ///     Exception::IllegalDataAddress,
-    /// );
+    /// use easy_modbus::Frame;
+    /// let response = Frame::rtu().get_comm_event_counter_response(0x0B, 0x0000, 0x0108);
     /// ```
-    pub fn exception_response(
+    pub fn get_comm_event_counter_response(
         &self,
         unit_id: u8,
-        function: Function,
-        exception: Exception,
+        status: u16,
+        event_count: u16,
     ) -> Response {
-        let response_body = ExceptionResponse::new(exception);
-        let head = self.head(unit_id, function, response_body.len(), true);
-        Response::Exception(head, response_body)
+        let function = Function::GetCommEventCounter;
+        let response_body = GetCommEventCounterResponse::new(status, event_count);
+        let head = self.head(unit_id, function, response_body.len(), false);
+        Response::GetCommEventCounter(head, response_body)
     }
 
-    /// Build modbus message head
-    fn head(&self, uid: u8, function: Function, body_length: u16, is_exception: bool) -> Head {
-        Head::new(
-            self.get_tid(uid),
-            uid,
-            function,
-            body_length,
-            self.version,
-            is_exception,
-        )
+    /// Create a report server id response (Function Code: 0x11)
+    ///
+    /// * `unit_id` - Server address
+    /// * `server_id` - Device specific server id
+    /// * `run_indicator_status` - 0x00 for off, 0xFF for on
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use easy_modbus::Frame;
+    /// let response = Frame::rtu().report_server_id_response(0x0B, vec![0x2A], 0xFF);
+    /// ```
+    pub fn report_server_id_response(
+        &self,
+        unit_id: u8,
+        server_id: Vec<u8>,
+        run_indicator_status: u8,
+    ) -> Response {
+        let function = Function::ReportServerId;
+        let response_body = ReportServerIdResponse::new(server_id, run_indicator_status);
+        let head = self.head(unit_id, function, response_body.len(), false);
+        Response::ReportServerId(head, response_body)
     }
 
-    /// Get tid by uid from tid_map
-    fn get_tid(&self, unit_id: u8) -> u16 {
-        if self.version == Version::Rtu {
-            return 0;
-        }
+    /// Create a mask write register response (Function Code: 0x16)
+    ///
+    /// * `unit_id` - Server address
+    /// * `address` - Address of holding register modified
+    /// * `and_mask` - Bits of the previous value kept
+    /// * `or_mask` - Bits set among the ones not kept
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use easy_modbus::Frame;
+    /// let response = Frame::tcp().mask_write_register_response(0x01, 0x0004, 0x00F2, 0x0025);
+    /// ```
+    pub fn mask_write_register_response(
+        &self,
+        unit_id: u8,
+        address: u16,
+        and_mask: u16,
+        or_mask: u16,
+    ) -> Response {
+        let function = Function::MaskWriteRegister;
+        let response_body = MaskWriteRegisterResponse::new(address, and_mask, or_mask);
+        let head = self.head(unit_id, function, response_body.len(), false);
+        Response::MaskWriteRegister(head, response_body)
+    }
 
-        let mut map = self.tid_map.lock().unwrap();
-        let value = match map.get(&unit_id) {
-            None => 1,
-            Some(v) => {
-                if v < &0xFFFF {
-                    v + 1
-                } else {
-                    1
-                }
-            }
-        };
-        map.insert(unit_id, value);
-        value
+    /// Create a read/write multiple registers response (Function Code: 0x17)
+    ///
+    /// * `unit_id` - Server address
+    /// * `values` - Values of the registers read
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use easy_modbus::Frame;
+    /// let response = Frame::tcp().read_write_multiple_registers_response(0x01, vec![0x00, 0xFE]);
+    /// ```
+    pub fn read_write_multiple_registers_response(&self, unit_id: u8, values: Vec<u8>) -> Response {
+        let function = Function::ReadWriteMultipleRegisters;
+        let response_body = ReadWriteMultipleRegistersResponse::new(values);
+        let head = self.head(unit_id, function, response_body.len(), false);
+        Response::ReadWriteMultipleRegisters(head, response_body)
     }
-}
 
-/// Protocol versions
-///
-/// Versions of the Modbus protocol exist for serial ports, and for Ethernet and other protocols
-/// that support the Internet protocol suite. BUT NOW JUST SUPPORT **TCP** AND **RTU**.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub enum Version {
-    Tcp,
-    Rtu,
-}
+    /// Create a read device identification response (Function Code: 0x2B / MEI Type: 0x0E)
+    ///
+    /// * `unit_id` - Server address
+    /// * `read_device_id_code` - Same as in the request
+    /// * `conformity_level` - Identification conformity level of the device
+    /// * `next_object_id` - Object to continue at when not all objects fit, `None` otherwise
+    /// * `objects` - Object ids and their values
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use easy_modbus::Frame;
+    /// let objects = vec![(0x00, b"Company".to_vec()), (0x01, b"Product".to_vec())];
+    /// let response = Frame::tcp().read_device_identification_response(0x01, 0x01, 0x01, None, objects);
+    /// ```
+    pub fn read_device_identification_response(
+        &self,
+        unit_id: u8,
+        read_device_id_code: u8,
+        conformity_level: u8,
+        next_object_id: Option<u8>,
+        objects: Vec<(u8, Vec<u8>)>,
+    ) -> Response {
+        let function = Function::ReadDeviceIdentification;
+        let response_body = ReadDeviceIdentificationResponse::new(
+            read_device_id_code,
+            conformity_level,
+            next_object_id,
+            objects,
+        );
+        let head = self.head(unit_id, function, response_body.len(), false);
+        Response::ReadDeviceIdentification(head, response_body)
+    }
 
-#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    /// Create a read exception status response (Function Code: 0x07)
+    ///
+    /// * `unit_id` - Server address
+    /// * `output_data` - States of the eight device specific exception status outputs
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use easy_modbus::Frame;
+    /// let response = Frame::rtu().read_exception_status_response(0x11, 0x6D);
+    /// ```
+    pub fn read_exception_status_response(&self, unit_id: u8, output_data: u8) -> Response {
+        let function = Function::ReadExceptionStatus;
+        let response_body = ReadExceptionStatusResponse::new(output_data);
+        let head = self.head(unit_id, function, response_body.len(), false);
+        Response::ReadExceptionStatus(head, response_body)
+    }
+
+    /// Create a diagnostics response (Function Code: 0x08)
+    ///
+    /// * `unit_id` - Server address
+    /// * `sub_function` - Sub-function code of the request
+    /// * `data` - Data of the sub-function, e.g. a counter or the echoed query data
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use easy_modbus::Frame;
+    /// let response = Frame::rtu().diagnostics_response(0x11, 0x0000, 0xA537);
+    /// ```
+    pub fn diagnostics_response(&self, unit_id: u8, sub_function: u16, data: u16) -> Response {
+        let function = Function::Diagnostics;
+        let response_body = DiagnosticsResponse::new(sub_function, data);
+        let head = self.head(unit_id, function, response_body.len(), false);
+        Response::Diagnostics(head, response_body)
+    }
+
+    /// Create a read FIFO queue response (Function Code: 0x18)
+    ///
+    /// * `unit_id` - Server address
+    /// * `values` - Queued values, oldest first, at most 31
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use easy_modbus::Frame;
+    /// let response = Frame::tcp().read_fifo_queue_response(0x01, vec![0x01B8, 0x1284]);
+    /// ```
+    pub fn read_fifo_queue_response(&self, unit_id: u8, values: Vec<u16>) -> Response {
+        let function = Function::ReadFifoQueue;
+        let response_body = ReadFifoQueueResponse::new(values);
+        let head = self.head(unit_id, function, response_body.len(), false);
+        Response::ReadFifoQueue(head, response_body)
+    }
+
+    /// Create a read file record response (Function Code: 0x14)
+    ///
+    /// * `unit_id` - Server address
+    /// * `records` - Registers of each record read, in the order of the request
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use easy_modbus::Frame;
+    /// let records = vec![vec![0x0DFE, 0x0020], vec![0x33CD, 0x0040]];
+    /// let response = Frame::tcp().read_file_record_response(0x01, records);
+    /// ```
+    pub fn read_file_record_response(&self, unit_id: u8, records: Vec<Vec<u16>>) -> Response {
+        let function = Function::ReadFileRecord;
+        let response_body = ReadFileRecordResponse::new(records);
+        let head = self.head(unit_id, function, response_body.len(), false);
+        Response::ReadFileRecord(head, response_body)
+    }
+
+    /// Create a write single coil response echoing the request (Function Code: 0x05)
+    ///
+    /// * `unit_id` - Server address
+    /// * `request` - Received write single coil request
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use easy_modbus::{Frame, Request};
+    /// let frame = Frame::tcp();
+    /// if let Request::WriteSingleCoil(head, body) =
+    ///     frame.write_single_coil_request(0x01, 0x00BF, 0xFF00)
+    /// {
+    ///     let response = frame.write_single_coil_response_from_request(head.uid(), &body);
+    /// }
+    /// ```
+    pub fn write_single_coil_response_from_request(
+        &self,
+        unit_id: u8,
+        request: &WriteSingleCoilRequest,
+    ) -> Response {
+        self.write_single_coil_response(unit_id, request.coil_address, request.value)
+    }
+
+    /// Create a write single holding register response echoing the request (Function Code: 0x06)
+    ///
+    /// * `unit_id` - Server address
+    /// * `request` - Received write single holding register request
+    pub fn write_single_holding_register_response_from_request(
+        &self,
+        unit_id: u8,
+        request: &WriteSingleHoldingRegisterRequest,
+    ) -> Response {
+        self.write_single_holding_register_response(
+            unit_id,
+            request.register_address,
+            request.value,
+        )
+    }
+
+    /// Create a write multiple coils response echoing the request (Function Code: 0x0F)
+    ///
+    /// * `unit_id` - Server address
+    /// * `request` - Received write multiple coils request
+    pub fn write_multiple_coils_response_from_request(
+        &self,
+        unit_id: u8,
+        request: &WriteMultipleCoilsRequest,
+    ) -> Response {
+        self.write_multiple_coils_response(unit_id, request.first_address, request.coils_number)
+    }
+
+    /// Create a write multiple holding registers response echoing the request
+    /// (Function Code: 0x10)
+    ///
+    /// * `unit_id` - Server address
+    /// * `request` - Received write multiple holding registers request
+    pub fn write_multiple_holding_registers_response_from_request(
+        &self,
+        unit_id: u8,
+        request: &WriteMultipleHoldingRegistersRequest,
+    ) -> Response {
+        self.write_multiple_holding_registers_response(
+            unit_id,
+            request.first_address,
+            request.registers_number,
+        )
+    }
+
+    /// Create a mask write register response echoing the request (Function Code: 0x16)
+    ///
+    /// * `unit_id` - Server address
+    /// * `request` - Received mask write register request
+    pub fn mask_write_register_response_from_request(
+        &self,
+        unit_id: u8,
+        request: &MaskWriteRegisterRequest,
+    ) -> Response {
+        self.mask_write_register_response(
+            unit_id,
+            request.reference_address,
+            request.and_mask,
+            request.or_mask,
+        )
+    }
+
+    /// Create a diagnostics response echoing the request (Function Code: 0x08)
+    ///
+    /// Answers Return Query Data, whose response carries the data of the request unchanged.
+    ///
+    /// * `unit_id` - Server address
+    /// * `request` - Received diagnostics request
+    pub fn diagnostics_response_from_request(
+        &self,
+        unit_id: u8,
+        request: &DiagnosticsRequest,
+    ) -> Response {
+        self.diagnostics_response(unit_id, request.sub_function, request.data)
+    }
+
+    /// Create a exception response
+    ///
+    /// * `unit_id` - Server address
+    /// * `function` - Modbus Function enum
+    /// * `exception` - Modbus Exception enum
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use easy_modbus::{Exception, Frame, Function};
+    /// let response = Frame::tcp().exception_response(
+    ///     0x0A,
+    ///     Function::ReadCoils,
+    ///     Exception::IllegalDataAddress,
+    /// );
+    /// ```
+    pub fn exception_response(
+        &self,
+        unit_id: u8,
+        function: Function,
+        exception: Exception,
+    ) -> Response {
+        let response_body = ExceptionResponse::new(exception);
+        let head = self.head(unit_id, function, response_body.len(), true);
+        Response::Exception(head, response_body)
+    }
+
+    /// Build modbus message head
+    fn head(&self, uid: u8, function: Function, body_length: u16, is_exception: bool) -> Head {
+        Head::new(
+            self.get_tid(uid),
+            uid,
+            function,
+            body_length,
+            self.version,
+            is_exception,
+        )
+    }
+
+    /// Get tid by uid from tid_map
+    fn get_tid(&self, unit_id: u8) -> u16 {
+        if self.version == Version::Rtu {
+            return 0;
+        }
+
+        let mut map = self.tid_map.lock().unwrap();
+        let value = match map.get(&unit_id) {
+            Some(v) if v < &0xFFFF => v + 1,
+            _ => 1,
+        };
+        map.insert(unit_id, value);
+        value
+    }
+}
+
+/// Common view of requests and responses
+///
+/// Lets generic code, e.g. a proxy, handle frames of either direction uniformly.
+///
+/// # Examples
+///
+/// ```
+/// use easy_modbus::{Frame, Function, ModbusFrame};
+///
+/// let frame = Frame::tcp();
+/// let frames: Vec<Box<dyn ModbusFrame>> = vec![
+///     Box::new(frame.read_coils_request(0x01, 0x02, 0x08)),
+///     Box::new(frame.read_coils_response(0x01, vec![0x00])),
+/// ];
+/// for frame in &frames {
+///     assert_eq!(frame.function(), Function::ReadCoils);
+/// }
+/// ```
+pub trait ModbusFrame {
+    /// Server address(Tcp) or Slave address(Rtu)
+    fn unit_id(&self) -> u8;
+
+    /// Function of the request, or of the request answered
+    fn function(&self) -> Function;
+
+    /// Protocol version the frame is encoded for
+    fn version(&self) -> Version;
+
+    /// Encode the frame, with its MBAP header or CRC
+    fn to_bytes(&self) -> Bytes;
+}
+
+impl ModbusFrame for Request {
+    fn unit_id(&self) -> u8 {
+        self.head().uid
+    }
+
+    fn function(&self) -> Function {
+        self.head().function
+    }
+
+    fn version(&self) -> Version {
+        self.head().version
+    }
+
+    fn to_bytes(&self) -> Bytes {
+        let mut buf = BytesMut::new();
+        request_to_bytesmut(self.clone(), &mut buf);
+        buf.freeze()
+    }
+}
+
+impl ModbusFrame for Response {
+    fn unit_id(&self) -> u8 {
+        self.head().uid
+    }
+
+    fn function(&self) -> Function {
+        self.head().function
+    }
+
+    fn version(&self) -> Version {
+        self.head().version
+    }
+
+    fn to_bytes(&self) -> Bytes {
+        let mut buf = BytesMut::new();
+        response_to_bytesmut(self.clone(), &mut buf);
+        buf.freeze()
+    }
+}
+
+/// Protocol versions
+///
+/// Versions of the Modbus protocol exist for serial ports, and for Ethernet and other protocols
+/// that support the Internet protocol suite. BUT NOW JUST SUPPORT **TCP** AND **RTU**.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Version {
+    Tcp,
+    Rtu,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Head {
     /// Transaction Identifier
     pub(crate) tid: u16,
@@ -500,6 +1155,22 @@ pub struct Head {
     pub(crate) is_exception: bool,
 }
 
+/// Modbus Application Protocol header of a TCP frame
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MbapHeader {
+    /// Transaction Identifier
+    pub transaction_id: u16,
+
+    /// Protocol Identifier
+    pub protocol_id: u16,
+
+    /// Number of following bytes, unit identifier included
+    pub length: u16,
+
+    /// Server address
+    pub unit_id: u8,
+}
+
 /// Exception types
 #[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub enum Exception {
@@ -518,21 +1189,52 @@ pub enum Exception {
     /// Value is not accepted by server
     IllegalDataValue,
 
-    /// Code 5
+    /// Code 4
     ///
     /// Unrecoverable error occurred while server was attempting to perform requested action
     SlaveDeviceFailure,
 
-    /// Code 6
+    /// Code 5
     ///
     /// Server has accepted request and is processing it, but a long duration of time is required.
     /// This response is returned to prevent a timeout error from occurring in the client. client
     /// can next issue a Poll Program Complete message to determine whether processing is completed
     Acknowledge,
+
+    /// Code 6
+    ///
+    /// Server is engaged in processing a long duration program command, client should retransmit
+    /// the message later
+    ServerDeviceBusy,
+
+    /// Code 7
+    ///
+    /// Server cannot perform the program function received in the query
+    NegativeAcknowledge,
+
+    /// Code 8
+    ///
+    /// Server detected a parity error in its memory while reading a file record
+    MemoryParityError,
+
+    /// Code 10
+    ///
+    /// Gateway could not reach the target device, or got back a response that does not answer
+    /// the request
+    GatewayPathUnavailable,
+
+    /// Code 11
+    ///
+    /// No response was obtained from the target device behind the gateway
+    GatewayTargetDeviceFailedToRespond,
+
+    /// Any code without a variant of its own
+    Unknown(u8),
 }
 
 impl Exception {
-    pub(crate) fn to_code(&self) -> u8 {
+    /// Get the exception code as sent on the wire
+    pub fn to_code(&self) -> u8 {
         use Exception::*;
         match self {
             IllegalFunction => 0x01,
@@ -540,22 +1242,47 @@ impl Exception {
             IllegalDataValue => 0x03,
             SlaveDeviceFailure => 0x04,
             Acknowledge => 0x05,
+            ServerDeviceBusy => 0x06,
+            NegativeAcknowledge => 0x07,
+            MemoryParityError => 0x08,
+            GatewayPathUnavailable => 0x0A,
+            GatewayTargetDeviceFailedToRespond => 0x0B,
+            Unknown(code) => *code,
         }
     }
-    pub(crate) fn from_code(code: u8) -> Option<Exception> {
+
+    /// Interpret an exception code received from a device
+    ///
+    /// Fails with [`ModbusError::InvalidExceptionCode`] for a code the specification does not
+    /// define.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use easy_modbus::Exception;
+    /// assert_eq!(Exception::from_code(0x02).unwrap(), Exception::IllegalDataAddress);
+    /// assert!(Exception::from_code(0x0C).is_err());
+    /// ```
+    pub fn from_code(code: u8) -> Result<Exception, ModbusError> {
         use Exception::*;
         let exception = match code {
-            0x01 => IllegalDataValue,
+            0x01 => IllegalFunction,
             0x02 => IllegalDataAddress,
             0x03 => IllegalDataValue,
             0x04 => SlaveDeviceFailure,
             0x05 => Acknowledge,
+            0x06 => ServerDeviceBusy,
+            0x07 => NegativeAcknowledge,
+            0x08 => MemoryParityError,
+            0x0A => GatewayPathUnavailable,
+            0x0B => GatewayTargetDeviceFailedToRespond,
             _ => {
-                return None;
+                return Err(ModbusError::InvalidExceptionCode(code));
             }
         };
-        Some(exception)
+        Ok(exception)
     }
+
     pub(crate) fn as_error_kind(&self) -> ErrorKind {
         use Exception::*;
         match self {
@@ -564,6 +1291,12 @@ impl Exception {
             IllegalDataValue => ErrorKind::InvalidData,
             SlaveDeviceFailure => ErrorKind::Interrupted,
             Acknowledge => ErrorKind::WouldBlock,
+            ServerDeviceBusy => ErrorKind::ResourceBusy,
+            NegativeAcknowledge => ErrorKind::Other,
+            MemoryParityError => ErrorKind::InvalidData,
+            GatewayPathUnavailable => ErrorKind::NotConnected,
+            GatewayTargetDeviceFailedToRespond => ErrorKind::TimedOut,
+            Unknown(_) => ErrorKind::Other,
         }
     }
 }
@@ -579,6 +1312,15 @@ pub enum Function {
     WriteSingleHoldingRegister,
     WriteMultipleCoils,
     WriteMultipleHoldingRegisters,
+    GetCommEventCounter,
+    ReportServerId,
+    MaskWriteRegister,
+    ReadWriteMultipleRegisters,
+    ReadDeviceIdentification,
+    ReadExceptionStatus,
+    Diagnostics,
+    ReadFifoQueue,
+    ReadFileRecord,
 }
 
 trait Length {
@@ -597,6 +1339,165 @@ impl Function {
             WriteSingleHoldingRegister => 0x06,
             WriteMultipleCoils => 0x0F,
             WriteMultipleHoldingRegisters => 0x10,
+            GetCommEventCounter => 0x0B,
+            ReportServerId => 0x11,
+            MaskWriteRegister => 0x16,
+            ReadWriteMultipleRegisters => 0x17,
+            ReadDeviceIdentification => 0x2B,
+            ReadExceptionStatus => 0x07,
+            Diagnostics => 0x08,
+            ReadFifoQueue => 0x18,
+            ReadFileRecord => 0x14,
+        }
+    }
+
+    /// Get the name of the function as given by the Modbus specification, for logging
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use easy_modbus::Function;
+    /// assert_eq!(Function::ReadMultipleHoldingRegisters.name(), "Read Holding Registers");
+    /// ```
+    pub fn name(&self) -> &'static str {
+        use Function::*;
+        match self {
+            ReadCoils => "Read Coils",
+            ReadDiscreteInputs => "Read Discrete Inputs",
+            ReadMultipleHoldingRegisters => "Read Holding Registers",
+            ReadInputRegisters => "Read Input Registers",
+            WriteSingleCoil => "Write Single Coil",
+            WriteSingleHoldingRegister => "Write Single Register",
+            WriteMultipleCoils => "Write Multiple Coils",
+            WriteMultipleHoldingRegisters => "Write Multiple Registers",
+            GetCommEventCounter => "Get Comm Event Counter",
+            ReportServerId => "Report Server ID",
+            MaskWriteRegister => "Mask Write Register",
+            ReadWriteMultipleRegisters => "Read/Write Multiple Registers",
+            ReadDeviceIdentification => "Read Device Identification",
+            ReadExceptionStatus => "Read Exception Status",
+            Diagnostics => "Diagnostics",
+            ReadFifoQueue => "Read FIFO Queue",
+            ReadFileRecord => "Read File Record",
+        }
+    }
+
+    /// Whether the function only writes coils or registers
+    ///
+    /// Only such writes may be broadcast over RTU.
+    pub fn is_write(&self) -> bool {
+        use Function::*;
+        matches!(
+            self,
+            WriteSingleCoil
+                | WriteSingleHoldingRegister
+                | WriteMultipleCoils
+                | WriteMultipleHoldingRegisters
+                | MaskWriteRegister
+        )
+    }
+}
+
+/// Sub-function of a Diagnostics (0x08) request
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DiagnosticsSubFunction {
+    /// `0x0000`, echo the data of the request as a loopback test
+    ReturnQueryData,
+
+    /// `0x0001`, restart the serial line port, clearing the communication event log with
+    /// data `0xFF00`
+    RestartCommunicationsOption,
+
+    /// `0x0002`
+    ReturnDiagnosticRegister,
+
+    /// `0x0004`, stop answering until the communications are restarted
+    ForceListenOnlyMode,
+
+    /// `0x000A`, clear all counters and the diagnostic register
+    ClearCountersAndDiagnosticRegister,
+
+    /// `0x000B`
+    ReturnBusMessageCount,
+
+    /// `0x000C`
+    ReturnBusCommunicationErrorCount,
+
+    /// `0x000D`
+    ReturnBusExceptionErrorCount,
+
+    /// `0x000E`
+    ReturnServerMessageCount,
+
+    /// `0x000F`
+    ReturnServerNoResponseCount,
+
+    /// `0x0010`
+    ReturnServerNakCount,
+
+    /// `0x0011`
+    ReturnServerBusyCount,
+
+    /// `0x0012`
+    ReturnBusCharacterOverrunCount,
+
+    /// `0x0014`
+    ClearOverrunCounterAndFlag,
+
+    /// Any other sub-function, e.g. a reserved or device specific one
+    Unknown(u16),
+}
+
+impl DiagnosticsSubFunction {
+    /// Read a sub-function code
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use easy_modbus::DiagnosticsSubFunction;
+    /// let sub_function = DiagnosticsSubFunction::from_code(0x000A);
+    /// assert_eq!(sub_function, DiagnosticsSubFunction::ClearCountersAndDiagnosticRegister);
+    /// ```
+    pub fn from_code(code: u16) -> DiagnosticsSubFunction {
+        use DiagnosticsSubFunction::*;
+        match code {
+            0x0000 => ReturnQueryData,
+            0x0001 => RestartCommunicationsOption,
+            0x0002 => ReturnDiagnosticRegister,
+            0x0004 => ForceListenOnlyMode,
+            0x000A => ClearCountersAndDiagnosticRegister,
+            0x000B => ReturnBusMessageCount,
+            0x000C => ReturnBusCommunicationErrorCount,
+            0x000D => ReturnBusExceptionErrorCount,
+            0x000E => ReturnServerMessageCount,
+            0x000F => ReturnServerNoResponseCount,
+            0x0010 => ReturnServerNakCount,
+            0x0011 => ReturnServerBusyCount,
+            0x0012 => ReturnBusCharacterOverrunCount,
+            0x0014 => ClearOverrunCounterAndFlag,
+            code => Unknown(code),
+        }
+    }
+
+    /// Get the sub-function code
+    pub fn to_code(&self) -> u16 {
+        use DiagnosticsSubFunction::*;
+        match self {
+            ReturnQueryData => 0x0000,
+            RestartCommunicationsOption => 0x0001,
+            ReturnDiagnosticRegister => 0x0002,
+            ForceListenOnlyMode => 0x0004,
+            ClearCountersAndDiagnosticRegister => 0x000A,
+            ReturnBusMessageCount => 0x000B,
+            ReturnBusCommunicationErrorCount => 0x000C,
+            ReturnBusExceptionErrorCount => 0x000D,
+            ReturnServerMessageCount => 0x000E,
+            ReturnServerNoResponseCount => 0x000F,
+            ReturnServerNakCount => 0x0010,
+            ReturnServerBusyCount => 0x0011,
+            ReturnBusCharacterOverrunCount => 0x0012,
+            ClearOverrunCounterAndFlag => 0x0014,
+            Unknown(code) => *code,
         }
     }
 }
@@ -628,6 +1529,112 @@ impl Head {
     pub fn uid(&self) -> u8 {
         self.uid
     }
+
+    /// Get the transaction identifier, 0 for RTU frames
+    pub fn get_transaction_id(&self) -> &u16 {
+        &self.tid
+    }
+
+    /// Get the unit identifier (TCP) or slave address (RTU)
+    pub fn get_unit_id(&self) -> &u8 {
+        &self.uid
+    }
+
+    /// Get the function, see [`Head::is_exception`] for the exception bit
+    pub fn get_function(&self) -> &Function {
+        &self.function
+    }
+
+    /// Get whether the frame is TCP or RTU
+    pub fn get_version(&self) -> &Version {
+        &self.version
+    }
+
+    /// Get the function without the exception bit, whether the frame is an exception or not
+    ///
+    /// E.g. to build a retry of the request an exception response answers.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use easy_modbus::{Exception, Frame, Function};
+    /// let response = Frame::tcp().exception_response(
+    ///     0x01,
+    ///     Function::ReadMultipleHoldingRegisters,
+    ///     Exception::IllegalDataAddress,
+    /// );
+    /// assert_eq!(response.head().base_function(), Function::ReadMultipleHoldingRegisters);
+    /// ```
+    pub fn base_function(&self) -> Function {
+        self.function.clone()
+    }
+
+    /// Whether the function code carries the exception bit `0x80`
+    pub fn is_exception(&self) -> bool {
+        self.is_exception
+    }
+
+    /// Set or clear the exception bit of the function code
+    ///
+    /// The length is left as is, adjust it with [`Head::body_length`] when the body changes.
+    pub fn set_exception(&mut self, is_exception: bool) {
+        self.is_exception = is_exception;
+    }
+
+    /// Get the MBAP header, `None` for RTU frames
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use easy_modbus::Frame;
+    /// let request = Frame::tcp().read_coils_request(0x01, 0x02, 0x08);
+    /// let mbap = request.head().mbap_header().unwrap();
+    /// assert_eq!(mbap.transaction_id, 0x0001);
+    /// assert_eq!(mbap.length, 0x0006);
+    /// ```
+    pub fn mbap_header(&self) -> Option<MbapHeader> {
+        match self.version {
+            Version::Tcp => Some(MbapHeader {
+                transaction_id: self.tid,
+                protocol_id: self.pid,
+                length: self.length,
+                unit_id: self.uid,
+            }),
+            Version::Rtu => None,
+        }
+    }
+}
+
+#[test]
+fn test_head_base_function() {
+    let response = Frame::tcp().exception_response(
+        0x01,
+        Function::ReadMultipleHoldingRegisters,
+        Exception::IllegalDataAddress,
+    );
+    let mut head = response.head();
+    assert_eq!(head.base_function(), Function::ReadMultipleHoldingRegisters);
+    assert!(head.is_exception());
+
+    head.set_exception(false);
+    assert_eq!(head.base_function(), Function::ReadMultipleHoldingRegisters);
+    assert!(!head.is_exception());
+    assert_eq!(BytesMut::from(head)[7], 0x03);
+}
+
+#[test]
+fn test_head_getters() {
+    let response = Frame::tcp().read_coils_response(0x0B, vec![0x01]);
+    let head = response.get_head();
+    assert_eq!(head.get_transaction_id(), &0x0001);
+    assert_eq!(head.get_unit_id(), &0x0B);
+    assert_eq!(head.get_function(), &Function::ReadCoils);
+    assert_eq!(head.get_version(), &Version::Tcp);
+    assert!(!head.is_exception());
+
+    let response = Frame::rtu().read_coils_response(0x0B, vec![0x01]);
+    assert_eq!(response.get_head().get_transaction_id(), &0x0000);
+    assert_eq!(response.get_head().get_version(), &Version::Rtu);
 }
 
 #[test]
@@ -644,3 +1651,183 @@ fn test_head() {
     };
     assert_eq!(head_l, head_r);
 }
+
+#[test]
+fn test_response_from_request() {
+    let request = WriteSingleCoilRequest::new(0x00BF, 0xFF00);
+    let response_l = Frame::tcp().write_single_coil_response_from_request(0x01, &request);
+    let response_r = Frame::tcp().write_single_coil_response(0x01, 0x00BF, 0xFF00);
+    assert_eq!(response_l, response_r);
+
+    let request = WriteSingleHoldingRegisterRequest::new(0x0004, 0xABCD);
+    let response_l =
+        Frame::tcp().write_single_holding_register_response_from_request(0x01, &request);
+    let response_r = Frame::tcp().write_single_holding_register_response(0x01, 0x0004, 0xABCD);
+    assert_eq!(response_l, response_r);
+
+    let request = WriteMultipleCoilsRequest::new(0x001B, 0x0009, vec![0x4D, 0x01]);
+    let response_l = Frame::tcp().write_multiple_coils_response_from_request(0x01, &request);
+    let response_r = Frame::tcp().write_multiple_coils_response(0x01, 0x001B, 0x0009);
+    assert_eq!(response_l, response_r);
+
+    let request = WriteMultipleHoldingRegistersRequest::new(0x0012, vec![0x0B, 0x0A, 0xC1, 0x02]);
+    let response_l =
+        Frame::tcp().write_multiple_holding_registers_response_from_request(0x01, &request);
+    let response_r = Frame::tcp().write_multiple_holding_registers_response(0x01, 0x0012, 0x0002);
+    assert_eq!(response_l, response_r);
+}
+
+#[test]
+fn test_write_multiple_holding_registers_request_iter() {
+    let values = [0x000A_u16, 0x0102, 0xFFFF];
+    let request_l = Frame::tcp().write_multiple_holding_registers_request_iter(
+        0x01,
+        0x0012,
+        values.iter().copied(),
+    );
+    let request_r = Frame::tcp().write_multiple_holding_registers_request(
+        0x01,
+        0x0012,
+        vec![0x00, 0x0A, 0x01, 0x02, 0xFF, 0xFF],
+    );
+    assert_eq!(request_l, request_r);
+}
+
+#[test]
+fn test_modbus_frame() {
+    let frame = Frame::tcp();
+    let frames: Vec<Box<dyn ModbusFrame>> = vec![
+        Box::new(frame.read_coils_request(0x01, 0x0002, 0x0008)),
+        Box::new(frame.read_coils_response(0x01, vec![0x00])),
+    ];
+    let bytes: Vec<Vec<u8>> = frames
+        .iter()
+        .map(|frame| frame.to_bytes().to_vec())
+        .collect();
+    assert_eq!(
+        bytes,
+        vec![
+            vec![0x00, 0x01, 0x00, 0x00, 0x00, 0x06, 0x01, 0x01, 0x00, 0x02, 0x00, 0x08],
+            vec![0x00, 0x02, 0x00, 0x00, 0x00, 0x04, 0x01, 0x01, 0x01, 0x00],
+        ]
+    );
+    assert!(frames.iter().all(|frame| frame.unit_id() == 0x01
+        && frame.function() == Function::ReadCoils
+        && frame.version() == Version::Tcp));
+}
+
+#[test]
+fn test_function_name() {
+    assert_eq!(Function::ReadCoils.name(), "Read Coils");
+    assert_eq!(
+        Function::WriteMultipleHoldingRegisters.name(),
+        "Write Multiple Registers"
+    );
+    assert_eq!(Function::ReportServerId.name(), "Report Server ID");
+    assert_eq!(
+        Function::ReadExceptionStatus.name(),
+        "Read Exception Status"
+    );
+    assert_eq!(Function::Diagnostics.name(), "Diagnostics");
+    assert_eq!(Function::ReadFifoQueue.name(), "Read FIFO Queue");
+}
+
+#[test]
+fn test_diagnostics_sub_function() {
+    for code in 0x0000..=0x0020 {
+        assert_eq!(DiagnosticsSubFunction::from_code(code).to_code(), code);
+    }
+    assert_eq!(
+        DiagnosticsSubFunction::from_code(0x0003),
+        DiagnosticsSubFunction::Unknown(0x0003)
+    );
+}
+
+#[test]
+fn test_exception_code() {
+    for code in (0x01..=0x08).chain(0x0A..=0x0B) {
+        assert_eq!(Exception::from_code(code).unwrap().to_code(), code);
+    }
+    for code in [0x00, 0x09, 0x0C, 0xFF] {
+        assert!(matches!(
+            Exception::from_code(code),
+            Err(ModbusError::InvalidExceptionCode(c)) if c == code
+        ));
+    }
+}
+
+#[test]
+fn test_response_exception() {
+    let frame = Frame::tcp();
+    let response =
+        frame.exception_response(0x01, Function::ReadCoils, Exception::IllegalDataAddress);
+    assert!(response.is_exception());
+    assert_eq!(
+        response.as_exception(),
+        Some(&Exception::IllegalDataAddress)
+    );
+
+    let response = frame.read_coils_response(0x01, vec![0x01]);
+    assert!(!response.is_exception());
+    assert_eq!(response.as_exception(), None);
+}
+
+#[test]
+fn test_typed_read_responses() {
+    let response_l = Frame::tcp().read_holding_register_response_u16(0x0B, &[0xAE41, 0x5652]);
+    let response_r =
+        Frame::tcp().read_holding_register_response(0x0B, vec![0xAE, 0x41, 0x56, 0x52]);
+    assert_eq!(response_l, response_r);
+
+    let states = [
+        true, false, true, true, false, false, true, true, true, false,
+    ];
+    let response_l = Frame::tcp().read_coils_response_bool(0x0B, &states);
+    let response_r = Frame::tcp().read_coils_response(0x0B, vec![0b1100_1101, 0b0000_0001]);
+    assert_eq!(response_l, response_r);
+}
+
+#[test]
+fn test_broadcast() {
+    assert!(Function::WriteMultipleCoils.is_write());
+    assert!(!Function::ReadCoils.is_write());
+
+    let frame = Frame::rtu();
+    assert!(frame
+        .write_single_coil_request(0x00, 0x00AC, 0xFF00)
+        .is_broadcast());
+    assert!(!frame
+        .write_single_coil_request(0x01, 0x00AC, 0xFF00)
+        .is_broadcast());
+    assert!(!frame
+        .read_coils_request(0x00, 0x0000, 0x0008)
+        .is_broadcast());
+    let frame = Frame::tcp();
+    assert!(!frame
+        .write_single_coil_request(0x00, 0x00AC, 0xFF00)
+        .is_broadcast());
+}
+
+#[test]
+fn test_verify_payload_checksum() {
+    use crate::util::PayloadChecksum;
+
+    let registers = [0x1234, 0x00FF, 0xF000, 0x0333];
+    let response = Frame::tcp().read_holding_register_response_u16(0x0B, &registers);
+    let body = match response {
+        Response::ReadMultipleHoldingRegisters(_, body) => body,
+        _ => unreachable!(),
+    };
+    assert!(body.verify_payload_checksum(3, PayloadChecksum::Sum));
+    assert!(!body.verify_payload_checksum(3, PayloadChecksum::Xor));
+    assert!(!body.verify_payload_checksum(0, PayloadChecksum::Sum));
+    assert!(!body.verify_payload_checksum(4, PayloadChecksum::Sum));
+
+    let registers = [0x1234, 0x0F0F, 0x1D3B];
+    let response = Frame::tcp().read_holding_register_response_u16(0x0B, &registers);
+    let body = match response {
+        Response::ReadMultipleHoldingRegisters(_, body) => body,
+        _ => unreachable!(),
+    };
+    assert!(body.verify_payload_checksum(2, PayloadChecksum::Xor));
+}
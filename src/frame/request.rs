@@ -3,10 +3,10 @@ use std::fmt::Formatter;
 
 use bytes::{BufMut, BytesMut};
 
-use crate::frame::Version::Rtu;
-use crate::util::crc;
+use crate::frame::Version::{self, Rtu, Tcp};
+use crate::util::crc::{Checksum, ModbusCrc};
 
-use super::{Head, Length};
+use super::{DiagnosticsSubFunction, Head, Length, MbapHeader, FILE_RECORD_REFERENCE_TYPE};
 
 /// Modbus Request
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -19,52 +19,132 @@ pub enum Request {
     WriteSingleHoldingRegister(Head, WriteSingleHoldingRegisterRequest),
     WriteMultipleCoils(Head, WriteMultipleCoilsRequest),
     WriteMultipleHoldingRegisters(Head, WriteMultipleHoldingRegistersRequest),
+    GetCommEventCounter(Head, GetCommEventCounterRequest),
+    ReportServerId(Head, ReportServerIdRequest),
+    MaskWriteRegister(Head, MaskWriteRegisterRequest),
+    ReadWriteMultipleRegisters(Head, ReadWriteMultipleRegistersRequest),
+    ReadDeviceIdentification(Head, ReadDeviceIdentificationRequest),
+    ReadExceptionStatus(Head, ReadExceptionStatusRequest),
+    Diagnostics(Head, DiagnosticsRequest),
+    ReadFifoQueue(Head, ReadFifoQueueRequest),
+    ReadFileRecord(Head, ReadFileRecordRequest),
 }
 
 impl Request {
+    /// Append the frames of `requests` to `dst`, each with its own MBAP header or CRC
+    ///
+    /// Lets several requests go out in a single write.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bytes::BytesMut;
+    /// use easy_modbus::{Frame, Request};
+    ///
+    /// let frame = Frame::tcp();
+    /// let requests = vec![
+    ///     frame.read_coils_request(0x01, 0x02, 0x08),
+    ///     frame.read_input_registers_request(0x01, 0x00, 0x01),
+    /// ];
+    /// let mut dst = BytesMut::new();
+    /// Request::encode_batch(&requests, &mut dst);
+    /// assert_eq!(dst.len(), 24);
+    /// ```
+    pub fn encode_batch(requests: &[Request], dst: &mut BytesMut) {
+        for request in requests {
+            request_to_bytesmut(request.clone(), dst);
+        }
+    }
+
     pub fn head(&self) -> Head {
+        self.get_head().clone()
+    }
+
+    /// Borrow the head of the request
+    pub fn get_head(&self) -> &Head {
         match self {
-            Request::ReadCoils(head, _) => head.clone(),
-            Request::ReadDiscreteInputs(head, _) => head.clone(),
-            Request::ReadMultipleHoldingRegisters(head, _) => head.clone(),
-            Request::ReadInputRegisters(head, _) => head.clone(),
-            Request::WriteSingleCoil(head, _) => head.clone(),
-            Request::WriteSingleHoldingRegister(head, _) => head.clone(),
-            Request::WriteMultipleCoils(head, _) => head.clone(),
-            Request::WriteMultipleHoldingRegisters(head, _) => head.clone(),
-        }
-    }
-
-    pub fn set_head(&mut self, mut new_head: Head) {
-        unsafe {
-            match self {
-                Request::ReadCoils(head, _) => {
-                    std::ptr::swap(head as *mut Head, &mut new_head as *mut Head)
-                }
-                Request::ReadDiscreteInputs(head, _) => {
-                    std::ptr::swap(head as *mut Head, &mut new_head as *mut Head)
-                }
-                Request::ReadMultipleHoldingRegisters(head, _) => {
-                    std::ptr::swap(head as *mut Head, &mut new_head as *mut Head)
-                }
-                Request::ReadInputRegisters(head, _) => {
-                    std::ptr::swap(head as *mut Head, &mut new_head as *mut Head)
-                }
-                Request::WriteSingleCoil(head, _) => {
-                    std::ptr::swap(head as *mut Head, &mut new_head as *mut Head)
-                }
-                Request::WriteSingleHoldingRegister(head, _) => {
-                    std::ptr::swap(head as *mut Head, &mut new_head as *mut Head)
-                }
-                Request::WriteMultipleCoils(head, _) => {
-                    std::ptr::swap(head as *mut Head, &mut new_head as *mut Head)
-                }
-                Request::WriteMultipleHoldingRegisters(head, _) => {
-                    std::ptr::swap(head as *mut Head, &mut new_head as *mut Head)
-                }
-            }
+            Request::ReadCoils(head, _) => head,
+            Request::ReadDiscreteInputs(head, _) => head,
+            Request::ReadMultipleHoldingRegisters(head, _) => head,
+            Request::ReadInputRegisters(head, _) => head,
+            Request::WriteSingleCoil(head, _) => head,
+            Request::WriteSingleHoldingRegister(head, _) => head,
+            Request::WriteMultipleCoils(head, _) => head,
+            Request::WriteMultipleHoldingRegisters(head, _) => head,
+            Request::GetCommEventCounter(head, _) => head,
+            Request::ReportServerId(head, _) => head,
+            Request::MaskWriteRegister(head, _) => head,
+            Request::ReadWriteMultipleRegisters(head, _) => head,
+            Request::ReadDeviceIdentification(head, _) => head,
+            Request::ReadExceptionStatus(head, _) => head,
+            Request::Diagnostics(head, _) => head,
+            Request::ReadFifoQueue(head, _) => head,
+            Request::ReadFileRecord(head, _) => head,
         }
     }
+
+    fn head_mut(&mut self) -> &mut Head {
+        match self {
+            Request::ReadCoils(head, _) => head,
+            Request::ReadDiscreteInputs(head, _) => head,
+            Request::ReadMultipleHoldingRegisters(head, _) => head,
+            Request::ReadInputRegisters(head, _) => head,
+            Request::WriteSingleCoil(head, _) => head,
+            Request::WriteSingleHoldingRegister(head, _) => head,
+            Request::WriteMultipleCoils(head, _) => head,
+            Request::WriteMultipleHoldingRegisters(head, _) => head,
+            Request::GetCommEventCounter(head, _) => head,
+            Request::ReportServerId(head, _) => head,
+            Request::MaskWriteRegister(head, _) => head,
+            Request::ReadWriteMultipleRegisters(head, _) => head,
+            Request::ReadDeviceIdentification(head, _) => head,
+            Request::ReadExceptionStatus(head, _) => head,
+            Request::Diagnostics(head, _) => head,
+            Request::ReadFifoQueue(head, _) => head,
+            Request::ReadFileRecord(head, _) => head,
+        }
+    }
+
+    /// Whether the request is an RTU write to unit 0, which every slave obeys and none answers
+    pub fn is_broadcast(&self) -> bool {
+        let head = self.head();
+        head.version == Rtu && head.uid == 0x00 && head.function.is_write()
+    }
+
+    /// Convert the request to RTU, keeping its unit id, function and data
+    ///
+    /// The MBAP header is dropped and the encoder closes the frame with a CRC instead, e.g. for
+    /// a gateway forwarding TCP requests to a serial line.
+    pub fn to_rtu(&self) -> Request {
+        self.with_version(Rtu, 0)
+    }
+
+    /// Convert the request to TCP with transaction id `tid`, keeping its unit id, function and
+    /// data
+    ///
+    /// The encoder prefixes the frame with a MBAP header instead of closing it with a CRC.
+    pub fn to_tcp(&self, tid: u16) -> Request {
+        self.with_version(Tcp, tid)
+    }
+
+    fn with_version(&self, version: Version, tid: u16) -> Request {
+        let mut head = self.head();
+        head.version = version;
+        head.tid = tid;
+        let mut request = self.clone();
+        request.set_head(head);
+        request
+    }
+
+    /// Get the MBAP header, `None` for RTU requests
+    pub fn mbap_header(&self) -> Option<MbapHeader> {
+        self.head().mbap_header()
+    }
+
+    /// Replace the head of the request, e.g. to rewrite its transaction id or unit id
+    pub fn set_head(&mut self, head: Head) {
+        *self.head_mut() = head;
+    }
 }
 
 impl fmt::Display for Request {
@@ -401,6 +481,336 @@ impl WriteMultipleHoldingRegistersRequest {
     }
 }
 
+/// Function Code `0x0B`
+///
+/// The request carries no data beyond the function code.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct GetCommEventCounterRequest;
+
+impl Length for GetCommEventCounterRequest {
+    fn len(&self) -> u16 {
+        0
+    }
+}
+
+impl GetCommEventCounterRequest {
+    pub(crate) fn new() -> GetCommEventCounterRequest {
+        GetCommEventCounterRequest
+    }
+}
+
+/// Function Code `0x11`
+///
+/// The request carries no data beyond the function code.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ReportServerIdRequest;
+
+impl Length for ReportServerIdRequest {
+    fn len(&self) -> u16 {
+        0
+    }
+}
+
+impl ReportServerIdRequest {
+    pub(crate) fn new() -> ReportServerIdRequest {
+        ReportServerIdRequest
+    }
+}
+
+/// Function Code `0x16`
+///
+/// The register becomes `(current & and_mask) | (or_mask & !and_mask)`.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MaskWriteRegisterRequest {
+    /// Address of holding register to modify
+    pub(crate) reference_address: u16,
+
+    /// Bits of the current value to keep
+    pub(crate) and_mask: u16,
+
+    /// Bits to set among the ones not kept
+    pub(crate) or_mask: u16,
+}
+
+impl Length for MaskWriteRegisterRequest {
+    fn len(&self) -> u16 {
+        6
+    }
+}
+
+impl MaskWriteRegisterRequest {
+    pub(crate) fn new(
+        reference_address: u16,
+        and_mask: u16,
+        or_mask: u16,
+    ) -> MaskWriteRegisterRequest {
+        MaskWriteRegisterRequest {
+            reference_address,
+            and_mask,
+            or_mask,
+        }
+    }
+
+    pub fn get_reference_address(&self) -> &u16 {
+        &self.reference_address
+    }
+
+    pub fn get_and_mask(&self) -> &u16 {
+        &self.and_mask
+    }
+
+    pub fn get_or_mask(&self) -> &u16 {
+        &self.or_mask
+    }
+}
+
+/// Function Code `0x17`
+///
+/// The server writes the registers before reading, within one transaction.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ReadWriteMultipleRegistersRequest {
+    /// Address of first holding register to read
+    pub(crate) read_address: u16,
+
+    /// Number of holding registers to read, up to 125
+    pub(crate) read_number: u16,
+
+    /// Address of first holding register to write
+    pub(crate) write_address: u16,
+
+    /// Number of holding registers to write, up to 121
+    pub(crate) write_number: u16,
+
+    /// Number of bytes of register value to follow
+    pub(crate) bytes_number: u8,
+
+    /// New values of holding registers
+    pub(crate) values: Vec<u8>,
+}
+
+impl Length for ReadWriteMultipleRegistersRequest {
+    fn len(&self) -> u16 {
+        9 + self.values.len() as u16
+    }
+}
+
+impl ReadWriteMultipleRegistersRequest {
+    pub(crate) fn new(
+        read_address: u16,
+        read_number: u16,
+        write_address: u16,
+        values: Vec<u8>,
+    ) -> ReadWriteMultipleRegistersRequest {
+        ReadWriteMultipleRegistersRequest {
+            read_address,
+            read_number,
+            write_address,
+            write_number: values.len() as u16 / 2,
+            bytes_number: values.len() as u8,
+            values,
+        }
+    }
+
+    pub fn get_read_address(&self) -> &u16 {
+        &self.read_address
+    }
+
+    pub fn get_read_number(&self) -> &u16 {
+        &self.read_number
+    }
+
+    pub fn get_write_address(&self) -> &u16 {
+        &self.write_address
+    }
+
+    pub fn get_write_number(&self) -> &u16 {
+        &self.write_number
+    }
+
+    pub fn get_bytes_number(&self) -> &u8 {
+        &self.bytes_number
+    }
+
+    pub fn get_values(&self) -> &Vec<u8> {
+        &self.values
+    }
+}
+
+/// Function Code `0x2B`, MEI Type `0x0E`
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ReadDeviceIdentificationRequest {
+    /// Access type
+    ///
+    /// 0x01 basic, 0x02 regular and 0x03 extended stream access, 0x04 for one specific object
+    pub(crate) read_device_id_code: u8,
+
+    /// Object to start reading at
+    pub(crate) object_id: u8,
+}
+
+impl Length for ReadDeviceIdentificationRequest {
+    fn len(&self) -> u16 {
+        3
+    }
+}
+
+impl ReadDeviceIdentificationRequest {
+    pub(crate) fn new(read_device_id_code: u8, object_id: u8) -> ReadDeviceIdentificationRequest {
+        ReadDeviceIdentificationRequest {
+            read_device_id_code,
+            object_id,
+        }
+    }
+
+    pub fn get_read_device_id_code(&self) -> &u8 {
+        &self.read_device_id_code
+    }
+
+    pub fn get_object_id(&self) -> &u8 {
+        &self.object_id
+    }
+}
+
+/// Function Code `0x07`
+///
+/// The request carries no data beyond the function code.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ReadExceptionStatusRequest;
+
+impl Length for ReadExceptionStatusRequest {
+    fn len(&self) -> u16 {
+        0
+    }
+}
+
+impl ReadExceptionStatusRequest {
+    pub(crate) fn new() -> ReadExceptionStatusRequest {
+        ReadExceptionStatusRequest
+    }
+}
+
+/// Function Code `0x08`
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DiagnosticsRequest {
+    /// Test to run, see [`DiagnosticsSubFunction`]
+    pub(crate) sub_function: u16,
+
+    /// Data of the test, e.g. the value to echo for Return Query Data
+    pub(crate) data: u16,
+}
+
+impl Length for DiagnosticsRequest {
+    fn len(&self) -> u16 {
+        4
+    }
+}
+
+impl DiagnosticsRequest {
+    pub(crate) fn new(sub_function: u16, data: u16) -> DiagnosticsRequest {
+        DiagnosticsRequest { sub_function, data }
+    }
+
+    pub fn get_sub_function(&self) -> &u16 {
+        &self.sub_function
+    }
+
+    pub fn get_data(&self) -> &u16 {
+        &self.data
+    }
+
+    /// Get the sub-function, telling the common ones apart
+    pub fn sub_function(&self) -> DiagnosticsSubFunction {
+        DiagnosticsSubFunction::from_code(self.sub_function)
+    }
+}
+
+/// Function Code `0x18`
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ReadFifoQueueRequest {
+    /// Address of the FIFO queue
+    pub(crate) fifo_pointer_address: u16,
+}
+
+impl Length for ReadFifoQueueRequest {
+    fn len(&self) -> u16 {
+        2
+    }
+}
+
+impl ReadFifoQueueRequest {
+    pub(crate) fn new(fifo_pointer_address: u16) -> ReadFifoQueueRequest {
+        ReadFifoQueueRequest {
+            fifo_pointer_address,
+        }
+    }
+
+    pub fn get_fifo_pointer_address(&self) -> &u16 {
+        &self.fifo_pointer_address
+    }
+}
+
+/// Record of a file to read, see [`ReadFileRecordRequest`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FileRecord {
+    /// Number of the file
+    pub(crate) file_number: u16,
+
+    /// Number of the first register of the record within the file, up to 9999
+    pub(crate) record_number: u16,
+
+    /// Number of registers of the record
+    pub(crate) record_length: u16,
+}
+
+impl FileRecord {
+    /// Create the record of `record_length` registers from `record_number` on in file
+    /// `file_number`
+    pub fn new(file_number: u16, record_number: u16, record_length: u16) -> FileRecord {
+        FileRecord {
+            file_number,
+            record_number,
+            record_length,
+        }
+    }
+
+    pub fn get_file_number(&self) -> &u16 {
+        &self.file_number
+    }
+
+    pub fn get_record_number(&self) -> &u16 {
+        &self.record_number
+    }
+
+    pub fn get_record_length(&self) -> &u16 {
+        &self.record_length
+    }
+}
+
+/// Function Code `0x14`
+///
+/// Each record is read by a sub-request of its own, answered by a sub-response.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ReadFileRecordRequest {
+    /// Records to read
+    pub(crate) records: Vec<FileRecord>,
+}
+
+impl Length for ReadFileRecordRequest {
+    fn len(&self) -> u16 {
+        1 + self.records.len() as u16 * 7
+    }
+}
+
+impl ReadFileRecordRequest {
+    pub(crate) fn new(records: Vec<FileRecord>) -> ReadFileRecordRequest {
+        ReadFileRecordRequest { records }
+    }
+
+    pub fn get_records(&self) -> &Vec<FileRecord> {
+        &self.records
+    }
+}
+
 impl From<ReadCoilsRequest> for BytesMut {
     fn from(request: ReadCoilsRequest) -> Self {
         let mut buf = BytesMut::new();
@@ -477,59 +887,230 @@ impl From<WriteMultipleHoldingRegistersRequest> for BytesMut {
     }
 }
 
+impl From<GetCommEventCounterRequest> for BytesMut {
+    fn from(_: GetCommEventCounterRequest) -> Self {
+        BytesMut::new()
+    }
+}
+
+impl From<ReportServerIdRequest> for BytesMut {
+    fn from(_: ReportServerIdRequest) -> Self {
+        BytesMut::new()
+    }
+}
+
+impl From<MaskWriteRegisterRequest> for BytesMut {
+    fn from(request: MaskWriteRegisterRequest) -> Self {
+        let mut buf = BytesMut::new();
+        buf.put_u16(request.reference_address);
+        buf.put_u16(request.and_mask);
+        buf.put_u16(request.or_mask);
+        buf
+    }
+}
+
+impl From<ReadWriteMultipleRegistersRequest> for BytesMut {
+    fn from(request: ReadWriteMultipleRegistersRequest) -> Self {
+        let mut buf = BytesMut::new();
+        buf.put_u16(request.read_address);
+        buf.put_u16(request.read_number);
+        buf.put_u16(request.write_address);
+        buf.put_u16(request.write_number);
+        buf.put_u8(request.bytes_number);
+        buf.put_slice(request.values.as_slice());
+        buf
+    }
+}
+
+impl From<ReadDeviceIdentificationRequest> for BytesMut {
+    fn from(request: ReadDeviceIdentificationRequest) -> Self {
+        let mut buf = BytesMut::new();
+        buf.put_u8(super::MEI_READ_DEVICE_IDENTIFICATION);
+        buf.put_u8(request.read_device_id_code);
+        buf.put_u8(request.object_id);
+        buf
+    }
+}
+
+impl From<ReadExceptionStatusRequest> for BytesMut {
+    fn from(_: ReadExceptionStatusRequest) -> Self {
+        BytesMut::new()
+    }
+}
+
+impl From<DiagnosticsRequest> for BytesMut {
+    fn from(request: DiagnosticsRequest) -> Self {
+        let mut buf = BytesMut::new();
+        buf.put_u16(request.sub_function);
+        buf.put_u16(request.data);
+        buf
+    }
+}
+
+impl From<ReadFifoQueueRequest> for BytesMut {
+    fn from(request: ReadFifoQueueRequest) -> Self {
+        let mut buf = BytesMut::new();
+        buf.put_u16(request.fifo_pointer_address);
+        buf
+    }
+}
+
+impl From<ReadFileRecordRequest> for BytesMut {
+    fn from(request: ReadFileRecordRequest) -> Self {
+        let mut buf = BytesMut::new();
+        buf.put_u8(request.records.len() as u8 * 7);
+        for record in request.records {
+            buf.put_u8(FILE_RECORD_REFERENCE_TYPE);
+            buf.put_u16(record.file_number);
+            buf.put_u16(record.record_number);
+            buf.put_u16(record.record_length);
+        }
+        buf
+    }
+}
+
 pub(crate) fn request_to_bytesmut(item: Request, dst: &mut BytesMut) {
+    request_to_bytesmut_with(item, dst, &ModbusCrc)
+}
+
+/// Append `item` to `dst`, closing a RTU frame with `checksum`
+pub(crate) fn request_to_bytesmut_with(item: Request, dst: &mut BytesMut, checksum: &dyn Checksum) {
+    let start = dst.len();
     let version;
     match item {
         Request::ReadCoils(head, body) => {
-            version = head.version.clone();
+            version = head.version;
             dst.put(BytesMut::from(head));
             dst.put(BytesMut::from(body));
         }
         Request::ReadDiscreteInputs(head, body) => {
-            version = head.version.clone();
+            version = head.version;
             dst.put(BytesMut::from(head));
             dst.put(BytesMut::from(body));
         }
         Request::ReadMultipleHoldingRegisters(head, body) => {
-            version = head.version.clone();
+            version = head.version;
             dst.put(BytesMut::from(head));
             dst.put(BytesMut::from(body));
         }
         Request::ReadInputRegisters(head, body) => {
-            version = head.version.clone();
+            version = head.version;
             dst.put(BytesMut::from(head));
             dst.put(BytesMut::from(body));
         }
         Request::WriteSingleCoil(head, body) => {
-            version = head.version.clone();
+            version = head.version;
             dst.put(BytesMut::from(head));
             dst.put(BytesMut::from(body));
         }
         Request::WriteSingleHoldingRegister(head, body) => {
-            version = head.version.clone();
+            version = head.version;
             dst.put(BytesMut::from(head));
             dst.put(BytesMut::from(body));
         }
         Request::WriteMultipleCoils(head, body) => {
-            version = head.version.clone();
+            version = head.version;
             dst.put(BytesMut::from(head));
             dst.put(BytesMut::from(body));
         }
         Request::WriteMultipleHoldingRegisters(head, body) => {
-            version = head.version.clone();
+            version = head.version;
+            dst.put(BytesMut::from(head));
+            dst.put(BytesMut::from(body));
+        }
+        Request::GetCommEventCounter(head, body) => {
+            version = head.version;
+            dst.put(BytesMut::from(head));
+            dst.put(BytesMut::from(body));
+        }
+        Request::ReportServerId(head, body) => {
+            version = head.version;
+            dst.put(BytesMut::from(head));
+            dst.put(BytesMut::from(body));
+        }
+        Request::MaskWriteRegister(head, body) => {
+            version = head.version;
+            dst.put(BytesMut::from(head));
+            dst.put(BytesMut::from(body));
+        }
+        Request::ReadWriteMultipleRegisters(head, body) => {
+            version = head.version;
+            dst.put(BytesMut::from(head));
+            dst.put(BytesMut::from(body));
+        }
+        Request::ReadDeviceIdentification(head, body) => {
+            version = head.version;
+            dst.put(BytesMut::from(head));
+            dst.put(BytesMut::from(body));
+        }
+        Request::ReadExceptionStatus(head, body) => {
+            version = head.version;
+            dst.put(BytesMut::from(head));
+            dst.put(BytesMut::from(body));
+        }
+        Request::Diagnostics(head, body) => {
+            version = head.version;
+            dst.put(BytesMut::from(head));
+            dst.put(BytesMut::from(body));
+        }
+        Request::ReadFifoQueue(head, body) => {
+            version = head.version;
+            dst.put(BytesMut::from(head));
+            dst.put(BytesMut::from(body));
+        }
+        Request::ReadFileRecord(head, body) => {
+            version = head.version;
             dst.put(BytesMut::from(head));
             dst.put(BytesMut::from(body));
         }
     };
     if Rtu == version {
-        dst.put_u16(crc::compute(&dst.to_vec()));
+        dst.put_u16(checksum.compute(&dst[start..]));
     }
 }
 
 #[cfg(test)]
 mod request_test {
+    use bytes::BytesMut;
+    #[cfg(feature = "codec")]
+    use tokio_util::codec::Decoder;
+
+    #[cfg(feature = "codec")]
+    use crate::codec::{RtuServerCodec, TcpServerCodec};
     use crate::frame::request::*;
-    use crate::frame::Length;
+    use crate::frame::{Frame, Length, Version};
+
+    #[test]
+    #[cfg(feature = "codec")]
+    fn test_encode_batch() {
+        let frame = Frame::tcp();
+        let requests = vec![
+            frame.read_coils_request(0x01, 0x0002, 0x0008),
+            frame.write_single_holding_register_request(0x01, 0x0001, 0x0003),
+            frame.read_multiple_holding_registers_request(0x02, 0x0000, 0x0002),
+        ];
+        let mut buf = BytesMut::new();
+        Request::encode_batch(&requests, &mut buf);
+        let mut decoded = Vec::new();
+        while let Some(request) = TcpServerCodec.decode(&mut buf).unwrap() {
+            decoded.push(request);
+        }
+        assert_eq!(decoded, requests);
+
+        let frame = Frame::rtu();
+        let requests = vec![
+            frame.read_coils_request(0x01, 0x0002, 0x0008),
+            frame.write_single_holding_register_request(0x01, 0x0001, 0x0003),
+            frame.read_multiple_holding_registers_request(0x02, 0x0000, 0x0002),
+        ];
+        let mut buf = BytesMut::new();
+        Request::encode_batch(&requests, &mut buf);
+        let mut decoded = Vec::new();
+        while let Some(request) = RtuServerCodec.decode(&mut buf).unwrap() {
+            decoded.push(request);
+        }
+        assert_eq!(decoded, requests);
+    }
 
     #[test]
     fn test_read_coils_request() {
@@ -622,4 +1203,111 @@ mod request_test {
         assert_eq!(request_l, request_r);
         assert_eq!(request_l.len(), 7);
     }
+
+    #[test]
+    #[cfg(feature = "codec")]
+    fn test_mask_write_register_request() {
+        let request_l = MaskWriteRegisterRequest::new(0x04, 0x00F2, 0x0025);
+        let request_r = MaskWriteRegisterRequest {
+            reference_address: 0x04,
+            and_mask: 0x00F2,
+            or_mask: 0x0025,
+        };
+        assert_eq!(request_l, request_r);
+        assert_eq!(request_l.len(), 6);
+
+        for frame in [Frame::tcp(), Frame::rtu()] {
+            let request = frame.mask_write_register_request(0x01, 0x0004, 0x00F2, 0x0025);
+            let mut buf = BytesMut::new();
+            Request::encode_batch(std::slice::from_ref(&request), &mut buf);
+            let decoded = match request.head().version {
+                Version::Tcp => TcpServerCodec.decode(&mut buf).unwrap(),
+                Version::Rtu => RtuServerCodec.decode(&mut buf).unwrap(),
+            };
+            assert_eq!(decoded, Some(request));
+        }
+    }
+
+    #[test]
+    fn test_encode_without_codec() {
+        let tcp = Frame::tcp().read_coils_request(0x0B, 0x001D, 0x001F);
+        let rtu = Frame::rtu().read_coils_request(0x0B, 0x001D, 0x001F);
+        assert_eq!(tcp.head().version, Version::Tcp);
+        assert_eq!(rtu.head().version, Version::Rtu);
+
+        let mut buf = BytesMut::new();
+        Request::encode_batch(&[tcp, rtu], &mut buf);
+        let tcp_bytes = [
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x06, 0x0B, 0x01, 0x00, 0x1D, 0x00, 0x1F,
+        ];
+        let rtu_bytes = [0x0B, 0x01, 0x00, 0x1D, 0x00, 0x1F, 0xED, 0x6E];
+        assert_eq!(&buf[..12], &tcp_bytes);
+        assert_eq!(&buf[12..], &rtu_bytes);
+    }
+
+    #[test]
+    fn test_set_head() {
+        let frame = Frame::tcp();
+        let requests = vec![
+            frame.read_coils_request(0x01, 0x0002, 0x0008),
+            frame.write_single_coil_request(0x01, 0x00AC, 0xFF00),
+            frame.read_fifo_queue_request(0x01, 0x04DE),
+        ];
+        for mut request in requests {
+            let original = request.clone();
+            let mut head = request.get_head().clone();
+            head.tid = 0x1234;
+            head.uid = 0x22;
+            request.set_head(head.clone());
+            assert_eq!(request.get_head(), &head);
+            assert_eq!(request.head().function, original.head().function);
+
+            request.set_head(original.head());
+            assert_eq!(request, original);
+        }
+    }
+
+    #[test]
+    fn test_convert_version() {
+        let request = Frame::tcp().read_coils_request(0x0B, 0x001D, 0x001F);
+        let rtu = request.to_rtu();
+        assert_eq!(rtu, Frame::rtu().read_coils_request(0x0B, 0x001D, 0x001F));
+        assert_eq!(rtu.mbap_header(), None);
+        assert_eq!(rtu.to_string(), "0B 01 00 1D 00 1F ED 6E");
+
+        let tcp = rtu.to_tcp(request.head().tid);
+        assert_eq!(tcp, request);
+        match tcp {
+            Request::ReadCoils(_, body) => {
+                assert_eq!(body.first_address, 0x001D);
+                assert_eq!(body.coils_number, 0x001F);
+            }
+            request => panic!("unexpected request {:?}", request),
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "codec")]
+    fn test_read_write_multiple_registers_request() {
+        let request_l =
+            ReadWriteMultipleRegistersRequest::new(0x03, 0x06, 0x0E, vec![0x00, 0xFF, 0x00, 0xFF]);
+        let request_r = ReadWriteMultipleRegistersRequest {
+            read_address: 0x03,
+            read_number: 0x06,
+            write_address: 0x0E,
+            write_number: 0x02,
+            bytes_number: 0x04,
+            values: vec![0x00, 0xFF, 0x00, 0xFF],
+        };
+        assert_eq!(request_l, request_r);
+        assert_eq!(request_l.len(), 13);
+
+        let frame = Frame::rtu();
+        let request =
+            frame.read_write_multiple_registers_request(0x0B, 0x03, 0x06, 0x0E, vec![0x00, 0xFF]);
+        let mut buf = BytesMut::new();
+        Request::encode_batch(std::slice::from_ref(&request), &mut buf);
+        assert_eq!(RtuServerCodec.decode(&mut buf).unwrap(), Some(request));
+        assert!(buf.is_empty());
+    }
 }
@@ -1,13 +1,15 @@
+use std::collections::BTreeMap;
 use std::fmt;
 use std::fmt::Formatter;
 
 use bytes::{BufMut, BytesMut};
 
+use crate::error::{ModbusError, Result};
 use crate::frame::Version::Rtu;
-use crate::frame::{Exception, Version};
-use crate::util::crc;
+use crate::frame::{DiagnosticsSubFunction, Exception, Version};
+use crate::util::{crc, map_range, PayloadChecksum, WordOrder};
 
-use super::{Head, Length};
+use super::{Head, Length, MbapHeader};
 
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Response {
@@ -19,43 +21,112 @@ pub enum Response {
     WriteSingleHoldingRegister(Head, WriteSingleHoldingRegisterResponse),
     WriteMultipleCoils(Head, WriteMultipleCoilsResponse),
     WriteMultipleHoldingRegisters(Head, WriteMultipleHoldingRegistersResponse),
+    GetCommEventCounter(Head, GetCommEventCounterResponse),
+    ReportServerId(Head, ReportServerIdResponse),
+    MaskWriteRegister(Head, MaskWriteRegisterResponse),
+    ReadWriteMultipleRegisters(Head, ReadWriteMultipleRegistersResponse),
+    ReadDeviceIdentification(Head, ReadDeviceIdentificationResponse),
+    ReadExceptionStatus(Head, ReadExceptionStatusResponse),
+    Diagnostics(Head, DiagnosticsResponse),
+    ReadFifoQueue(Head, ReadFifoQueueResponse),
+    ReadFileRecord(Head, ReadFileRecordResponse),
     Exception(Head, ExceptionResponse),
 }
 
 impl Response {
-    pub fn set_head(&mut self, mut new_head: Head) {
-        unsafe {
-            match self {
-                Response::ReadCoils(head, _) => {
-                    std::ptr::swap(head as *mut Head, &mut new_head as *mut Head)
-                }
-                Response::ReadDiscreteInputs(head, _) => {
-                    std::ptr::swap(head as *mut Head, &mut new_head as *mut Head)
-                }
-                Response::ReadMultipleHoldingRegisters(head, _) => {
-                    std::ptr::swap(head as *mut Head, &mut new_head as *mut Head)
-                }
-                Response::ReadInputRegisters(head, _) => {
-                    std::ptr::swap(head as *mut Head, &mut new_head as *mut Head)
-                }
-                Response::WriteSingleCoil(head, _) => {
-                    std::ptr::swap(head as *mut Head, &mut new_head as *mut Head)
-                }
-                Response::WriteSingleHoldingRegister(head, _) => {
-                    std::ptr::swap(head as *mut Head, &mut new_head as *mut Head)
-                }
-                Response::WriteMultipleCoils(head, _) => {
-                    std::ptr::swap(head as *mut Head, &mut new_head as *mut Head)
-                }
-                Response::WriteMultipleHoldingRegisters(head, _) => {
-                    std::ptr::swap(head as *mut Head, &mut new_head as *mut Head)
-                }
-                Response::Exception(head, _) => {
-                    std::ptr::swap(head as *mut Head, &mut new_head as *mut Head)
-                }
-            }
+    pub fn head(&self) -> Head {
+        self.get_head().clone()
+    }
+
+    /// Borrow the head of the response
+    pub fn get_head(&self) -> &Head {
+        match self {
+            Response::ReadCoils(head, _) => head,
+            Response::ReadDiscreteInputs(head, _) => head,
+            Response::ReadMultipleHoldingRegisters(head, _) => head,
+            Response::ReadInputRegisters(head, _) => head,
+            Response::WriteSingleCoil(head, _) => head,
+            Response::WriteSingleHoldingRegister(head, _) => head,
+            Response::WriteMultipleCoils(head, _) => head,
+            Response::WriteMultipleHoldingRegisters(head, _) => head,
+            Response::GetCommEventCounter(head, _) => head,
+            Response::ReportServerId(head, _) => head,
+            Response::MaskWriteRegister(head, _) => head,
+            Response::ReadWriteMultipleRegisters(head, _) => head,
+            Response::ReadDeviceIdentification(head, _) => head,
+            Response::ReadExceptionStatus(head, _) => head,
+            Response::Diagnostics(head, _) => head,
+            Response::ReadFifoQueue(head, _) => head,
+            Response::ReadFileRecord(head, _) => head,
+            Response::Exception(head, _) => head,
+        }
+    }
+
+    fn head_mut(&mut self) -> &mut Head {
+        match self {
+            Response::ReadCoils(head, _) => head,
+            Response::ReadDiscreteInputs(head, _) => head,
+            Response::ReadMultipleHoldingRegisters(head, _) => head,
+            Response::ReadInputRegisters(head, _) => head,
+            Response::WriteSingleCoil(head, _) => head,
+            Response::WriteSingleHoldingRegister(head, _) => head,
+            Response::WriteMultipleCoils(head, _) => head,
+            Response::WriteMultipleHoldingRegisters(head, _) => head,
+            Response::GetCommEventCounter(head, _) => head,
+            Response::ReportServerId(head, _) => head,
+            Response::MaskWriteRegister(head, _) => head,
+            Response::ReadWriteMultipleRegisters(head, _) => head,
+            Response::ReadDeviceIdentification(head, _) => head,
+            Response::ReadExceptionStatus(head, _) => head,
+            Response::Diagnostics(head, _) => head,
+            Response::ReadFifoQueue(head, _) => head,
+            Response::ReadFileRecord(head, _) => head,
+            Response::Exception(head, _) => head,
+        }
+    }
+
+    /// Convert the response to RTU, keeping its unit id, function and data
+    pub fn to_rtu(&self) -> Response {
+        self.with_version(Version::Rtu, 0)
+    }
+
+    /// Convert the response to TCP with transaction id `tid`, keeping its unit id, function and
+    /// data, e.g. for a gateway answering a TCP client with what a serial device sent
+    pub fn to_tcp(&self, tid: u16) -> Response {
+        self.with_version(Version::Tcp, tid)
+    }
+
+    fn with_version(&self, version: Version, tid: u16) -> Response {
+        let mut head = self.head();
+        head.version = version;
+        head.tid = tid;
+        let mut response = self.clone();
+        response.set_head(head);
+        response
+    }
+
+    /// Get the MBAP header, `None` for RTU responses
+    pub fn mbap_header(&self) -> Option<MbapHeader> {
+        self.head().mbap_header()
+    }
+
+    /// Whether the server answered with an exception
+    pub fn is_exception(&self) -> bool {
+        matches!(self, Response::Exception(_, _))
+    }
+
+    /// Get the exception the server answered with, `None` for a normal response
+    pub fn as_exception(&self) -> Option<&Exception> {
+        match self {
+            Response::Exception(_, body) => Some(&body.exception),
+            _ => None,
         }
     }
+
+    /// Replace the head of the response, e.g. to rewrite its transaction id or unit id
+    pub fn set_head(&mut self, head: Head) {
+        *self.head_mut() = head;
+    }
 }
 
 impl fmt::Display for Response {
@@ -110,6 +181,63 @@ impl ReadCoilsResponse {
     pub fn get_values(&self) -> &Vec<u8> {
         &self.values
     }
+
+    /// Unpack the states of the first `count` coils, the first coil being the least significant
+    /// bit of the first byte
+    ///
+    /// The padding bits of the last byte are left out. Coils past the end of the response read
+    /// as off, so exactly `count` states are returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use easy_modbus::{Frame, Response};
+    ///
+    /// let response = Frame::tcp().read_coils_response(0x01, vec![0b0000_0101]);
+    /// if let Response::ReadCoils(_, body) = response {
+    ///     assert_eq!(body.get_coils(3), vec![true, false, true]);
+    /// }
+    /// ```
+    pub fn get_coils(&self, count: u16) -> Vec<bool> {
+        bits(&self.values, count)
+    }
+
+    /// Get the address of the first coil on among the first `quantity` coils, read from
+    /// `base_addr` on, `None` if all are off or its address would be past `0xFFFF`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use easy_modbus::{Frame, Response};
+    ///
+    /// let response = Frame::tcp().read_coils_response(0x01, vec![0b0000_0100]);
+    /// if let Response::ReadCoils(_, body) = response {
+    ///     assert_eq!(body.first_set(10, 8), Some(12));
+    /// }
+    /// ```
+    pub fn first_set(&self, base_addr: u16, quantity: u16) -> Option<u16> {
+        let (index, byte) = self
+            .values
+            .iter()
+            .enumerate()
+            .find(|(_, &byte)| byte != 0)?;
+        let offset = index * 8 + byte.trailing_zeros() as usize;
+        if offset >= quantity as usize {
+            return None;
+        }
+        base_addr.checked_add(offset as u16)
+    }
+}
+
+/// Unpack the first `count` bits of `values`, least significant bit first, off past the end
+fn bits(values: &[u8], count: u16) -> Vec<bool> {
+    (0..count as usize)
+        .map(|i| {
+            values
+                .get(i / 8)
+                .is_some_and(|byte| byte >> (i % 8) & 0x01 == 0x01)
+        })
+        .collect()
 }
 
 /// Function Code `0x02`
@@ -148,6 +276,107 @@ impl ReadDiscreteInputsResponse {
     pub fn get_values(&self) -> &Vec<u8> {
         &self.values
     }
+
+    /// Unpack the states of the first `count` inputs, the first input being the least
+    /// significant bit of the first byte
+    ///
+    /// The padding bits of the last byte are left out. Inputs past the end of the response read
+    /// as off, so exactly `count` states are returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use easy_modbus::{Frame, Response};
+    ///
+    /// let response = Frame::tcp().read_discrete_response(0x01, vec![0b0000_0010]);
+    /// if let Response::ReadDiscreteInputs(_, body) = response {
+    ///     assert_eq!(body.get_inputs(3), vec![false, true, false]);
+    /// }
+    /// ```
+    pub fn get_inputs(&self, count: u16) -> Vec<bool> {
+        bits(&self.values, count)
+    }
+
+    /// Compare the inputs with a previous reading, returning the address and new state of each
+    /// input that changed
+    ///
+    /// `previous` holds the states of the inputs starting at `base_addr`; inputs past its end,
+    /// including the padding bits of the last byte, are not compared.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use easy_modbus::{Frame, Response};
+    ///
+    /// let response = Frame::tcp().read_discrete_response(0x01, vec![0b0000_0010]);
+    /// if let Response::ReadDiscreteInputs(_, body) = response {
+    ///     let changes = body.changes_from(&[false, false, false], 10);
+    ///     assert_eq!(changes, vec![(11, true)]);
+    /// }
+    /// ```
+    pub fn changes_from(&self, previous: &[bool], base_addr: u16) -> Vec<(u16, bool)> {
+        previous
+            .iter()
+            .take(self.values.len() * 8)
+            .enumerate()
+            .filter_map(|(i, &before)| {
+                let now = self.values[i / 8] >> (i % 8) & 0x01 == 0x01;
+                (now != before).then(|| (base_addr.wrapping_add(i as u16), now))
+            })
+            .collect()
+    }
+
+    /// Map the address of each of the `quantity` inputs starting at `base_addr` to its state
+    ///
+    /// Handy to merge readings of several ranges. The padding bits of the last byte are left
+    /// out, as are inputs past the end of the response.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use easy_modbus::{Frame, Response};
+    ///
+    /// let response = Frame::tcp().read_discrete_response(0x01, vec![0b0000_0010]);
+    /// if let Response::ReadDiscreteInputs(_, body) = response {
+    ///     let inputs = body.as_map(10, 2);
+    ///     assert_eq!(inputs.get(&11), Some(&true));
+    /// }
+    /// ```
+    pub fn as_map(&self, base_addr: u16, quantity: u16) -> BTreeMap<u16, bool> {
+        (0..(quantity as usize).min(self.values.len() * 8))
+            .map(|i| {
+                let state = self.values[i / 8] >> (i % 8) & 0x01 == 0x01;
+                (base_addr.wrapping_add(i as u16), state)
+            })
+            .collect()
+    }
+
+    /// Compress the states of the first `quantity` inputs into runs of `(state, count)`
+    ///
+    /// Shrinks mostly uniform readings, e.g. to forward them over a constrained link. The
+    /// padding bits of the last byte are left out, as are inputs past the end of the response.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use easy_modbus::{Frame, Response};
+    ///
+    /// let response = Frame::tcp().read_discrete_response(0x01, vec![0b0001_1111]);
+    /// if let Response::ReadDiscreteInputs(_, body) = response {
+    ///     assert_eq!(body.run_length_encode(8), vec![(true, 5), (false, 3)]);
+    /// }
+    /// ```
+    pub fn run_length_encode(&self, quantity: u16) -> Vec<(bool, u16)> {
+        let mut runs: Vec<(bool, u16)> = Vec::new();
+        for i in 0..(quantity as usize).min(self.values.len() * 8) {
+            let state = self.values[i / 8] >> (i % 8) & 0x01 == 0x01;
+            match runs.last_mut() {
+                Some((last, count)) if *last == state => *count += 1,
+                _ => runs.push((state, 1)),
+            }
+        }
+        runs
+    }
 }
 
 /// Function Code `0x03`
@@ -182,6 +411,407 @@ impl ReadMultipleHoldingRegistersResponse {
     pub fn get_values(&self) -> &Vec<u8> {
         &self.values
     }
+
+    /// Get the values as registers, pairing big endian bytes
+    ///
+    /// A trailing odd byte, which no valid response carries, is ignored.
+    pub fn get_registers(&self) -> Vec<u16> {
+        registers(&self.values)
+    }
+
+    /// Check the register at `checksum_index` against the checksum of the other registers
+    ///
+    /// For devices appending an application level checksum to the data they return. `false`
+    /// if there is no register at `checksum_index`.
+    pub fn verify_payload_checksum(
+        &self,
+        checksum_index: usize,
+        algorithm: PayloadChecksum,
+    ) -> bool {
+        let mut registers = self.get_registers();
+        if checksum_index >= registers.len() {
+            return false;
+        }
+        let checksum = registers.remove(checksum_index);
+        algorithm.compute(&registers) == checksum
+    }
+
+    /// Read an unsigned 32-bit value from the registers `index` and `index + 1`
+    ///
+    /// `None` if fewer than two registers are available from `index`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use easy_modbus::util::WordOrder;
+    /// use easy_modbus::{Frame, Response};
+    ///
+    /// let values = vec![0xCC, 0xDD, 0xAA, 0xBB];
+    /// let response = Frame::tcp().read_holding_register_response(0x01, values);
+    /// if let Response::ReadMultipleHoldingRegisters(_, body) = response {
+    ///     assert_eq!(body.get_u32(0, WordOrder::BigEndianSwap), Some(0xAABBCCDD));
+    ///     assert_eq!(body.get_u32(1, WordOrder::BigEndianSwap), None);
+    /// }
+    /// ```
+    pub fn get_u32(&self, index: usize, order: WordOrder) -> Option<u32> {
+        double_register(&self.values, index, order).map(u32::from_be_bytes)
+    }
+
+    /// Read a float from the registers `index` and `index + 1`
+    ///
+    /// `None` if fewer than two registers are available from `index`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use easy_modbus::util::WordOrder;
+    /// use easy_modbus::{Frame, Response};
+    ///
+    /// let values = vec![0x00, 0x00, 0x3F, 0x80];
+    /// let response = Frame::tcp().read_holding_register_response(0x01, values);
+    /// if let Response::ReadMultipleHoldingRegisters(_, body) = response {
+    ///     assert_eq!(body.get_f32(0, WordOrder::BigEndianSwap), Some(1.0));
+    /// }
+    /// ```
+    pub fn get_f32(&self, index: usize, order: WordOrder) -> Option<f32> {
+        double_register(&self.values, index, order).map(f32::from_be_bytes)
+    }
+
+    /// Read a signed 32-bit value from two registers and scale it to engineering units
+    ///
+    /// Returns `raw * scale + offset`, where `raw` is the two's complement value of the
+    /// registers `index` and `index + 1` arranged by `order`. `None` if fewer than two
+    /// registers are available from `index`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use easy_modbus::util::WordOrder;
+    /// use easy_modbus::{Frame, Response};
+    ///
+    /// let values = vec![0xFF, 0xFF, 0xCF, 0xC7];
+    /// let response = Frame::tcp().read_holding_register_response(0x01, values);
+    /// if let Response::ReadMultipleHoldingRegisters(_, body) = response {
+    ///     let value = body.get_i32_eng(0, WordOrder::BigEndian, 0.01, 0.0).unwrap();
+    ///     assert!((value + 123.45).abs() < 1e-9);
+    ///     assert_eq!(body.get_i32_eng(1, WordOrder::BigEndian, 0.01, 0.0), None);
+    /// }
+    /// ```
+    pub fn get_i32_eng(
+        &self,
+        index: usize,
+        order: WordOrder,
+        scale: f64,
+        offset: f64,
+    ) -> Option<f64> {
+        let bytes = double_register(&self.values, index, order)?;
+        Some(i32::from_be_bytes(bytes) as f64 * scale + offset)
+    }
+
+    /// Read a float from two registers, `None` when it is NaN or equals `sentinel`
+    ///
+    /// Devices often report missing data with a sentinel value, which should not pass as a
+    /// reading. An all-ones register pair decodes to NaN. `None` is also returned when fewer
+    /// than two registers are available from `index`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use easy_modbus::util::WordOrder;
+    /// use easy_modbus::{Frame, Response};
+    ///
+    /// let values = vec![0x3F, 0x80, 0x00, 0x00, 0xC4, 0x79, 0xC0, 0x00];
+    /// let response = Frame::tcp().read_holding_register_response(0x01, values);
+    /// if let Response::ReadMultipleHoldingRegisters(_, body) = response {
+    ///     assert_eq!(body.get_f32_opt(0, WordOrder::BigEndian, -999.0), Some(1.0));
+    ///     assert_eq!(body.get_f32_opt(2, WordOrder::BigEndian, -999.0), None);
+    /// }
+    /// ```
+    pub fn get_f32_opt(&self, index: usize, order: WordOrder, sentinel: f32) -> Option<f32> {
+        f32_opt(&self.values, index, order, sentinel)
+    }
+
+    /// Map register `index` linearly from `in_min..=in_max` onto `out_min..=out_max`
+    ///
+    /// See [`map_range`]. `None` if register `index` is not available.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use easy_modbus::{Frame, Response};
+    ///
+    /// let response = Frame::tcp().read_holding_register_response(0x01, vec![0x0F, 0xFF]);
+    /// if let Response::ReadMultipleHoldingRegisters(_, body) = response {
+    ///     assert_eq!(body.get_mapped(0, 0, 4095, 0.0, 100.0), Some(100.0));
+    ///     assert_eq!(body.get_mapped(1, 0, 4095, 0.0, 100.0), None);
+    /// }
+    /// ```
+    pub fn get_mapped(
+        &self,
+        index: usize,
+        in_min: u16,
+        in_max: u16,
+        out_min: f64,
+        out_max: f64,
+    ) -> Option<f64> {
+        let raw = register(&self.values, index)?;
+        Some(map_range(raw, in_min, in_max, out_min, out_max))
+    }
+
+    /// Pair the raw value of each register with its value scaled by `scales[index]`
+    ///
+    /// Meant for showing raw and engineering values side by side. Registers past the end of
+    /// `scales` are scaled by 1.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use easy_modbus::{Frame, Response};
+    ///
+    /// let response = Frame::tcp().read_holding_register_response(0x01, vec![0x00, 0xFA]);
+    /// if let Response::ReadMultipleHoldingRegisters(_, body) = response {
+    ///     assert_eq!(body.dual_view(&[0.1]), vec![(250, 25.0)]);
+    /// }
+    /// ```
+    pub fn dual_view(&self, scales: &[f64]) -> Vec<(u16, f64)> {
+        self.values
+            .chunks_exact(2)
+            .enumerate()
+            .map(|(index, bytes)| {
+                let raw = u16::from_be_bytes([bytes[0], bytes[1]]);
+                let scale = scales.get(index).copied().unwrap_or(1.0);
+                (raw, raw as f64 * scale)
+            })
+            .collect()
+    }
+
+    /// List plausible readings of the registers, to explore the data of an unknown device
+    ///
+    /// Every register reads as [`Interpretation::U16`] and [`Interpretation::I16`]. Every
+    /// aligned pair, starting at an even index, also reads big endian as
+    /// [`Interpretation::U32`], [`Interpretation::I32`] and, when finite,
+    /// [`Interpretation::F32`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use easy_modbus::{Frame, Interpretation, Response};
+    ///
+    /// let response = Frame::tcp().read_holding_register_response(0x01, vec![0xFF, 0xFE]);
+    /// if let Response::ReadMultipleHoldingRegisters(_, body) = response {
+    ///     assert!(body
+    ///         .interpret()
+    ///         .contains(&Interpretation::I16 { index: 0, value: -2 }));
+    /// }
+    /// ```
+    pub fn interpret(&self) -> Vec<Interpretation> {
+        let mut interpretations = Vec::new();
+        for (index, bytes) in self.values.chunks_exact(2).enumerate() {
+            let bytes = [bytes[0], bytes[1]];
+            interpretations.push(Interpretation::U16 {
+                index,
+                value: u16::from_be_bytes(bytes),
+            });
+            interpretations.push(Interpretation::I16 {
+                index,
+                value: i16::from_be_bytes(bytes),
+            });
+        }
+        for (pair, bytes) in self.values.chunks_exact(4).enumerate() {
+            let index = pair * 2;
+            let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+            interpretations.push(Interpretation::U32 {
+                index,
+                value: u32::from_be_bytes(bytes),
+            });
+            interpretations.push(Interpretation::I32 {
+                index,
+                value: i32::from_be_bytes(bytes),
+            });
+            let value = f32::from_be_bytes(bytes);
+            if value.is_finite() {
+                interpretations.push(Interpretation::F32 { index, value });
+            }
+        }
+        interpretations
+    }
+
+    /// Decode the registers into values laid out by `format`, in the manner of struct-pack
+    ///
+    /// Each code reads the next registers:
+    ///
+    /// * `H` - `u16`, one register
+    /// * `h` - `i16`, one register
+    /// * `I` - `u32`, two registers
+    /// * `i` - `i32`, two registers
+    /// * `f` - `f32`, two registers
+    ///
+    /// `>` reads the codes that follow big endian, as in the Modbus specification and by
+    /// default, `<` little endian. Registers left over at the end are ignored. Fails with
+    /// [`ModbusError::InvalidFrame`] on an unknown code or when the registers run out.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use easy_modbus::{Frame, Response, Value};
+    ///
+    /// let values = vec![0x00, 0x07, 0xFF, 0xFE];
+    /// let response = Frame::tcp().read_holding_register_response(0x01, values);
+    /// if let Response::ReadMultipleHoldingRegisters(_, body) = response {
+    ///     assert_eq!(body.unpack("Hh").unwrap(), vec![Value::U16(7), Value::I16(-2)]);
+    /// }
+    /// ```
+    pub fn unpack(&self, format: &str) -> Result<Vec<Value>> {
+        let mut order = WordOrder::BigEndian;
+        let mut offset = 0;
+        let mut values = Vec::new();
+        for code in format.chars() {
+            let size = match code {
+                '>' => {
+                    order = WordOrder::BigEndian;
+                    continue;
+                }
+                '<' => {
+                    order = WordOrder::LittleEndian;
+                    continue;
+                }
+                'H' | 'h' => 2,
+                'I' | 'i' | 'f' => 4,
+                code => {
+                    return Err(ModbusError::InvalidFrame(format!(
+                        "Unknown format code '{}'",
+                        code
+                    )));
+                }
+            };
+            let bytes = self.values.get(offset..offset + size).ok_or_else(|| {
+                ModbusError::InvalidFrame(format!(
+                    "Format \"{}\" needs more than {} registers",
+                    format,
+                    self.values.len() / 2
+                ))
+            })?;
+            offset += size;
+            let value = if size == 2 {
+                let mut bytes = [bytes[0], bytes[1]];
+                if order == WordOrder::LittleEndian {
+                    bytes.reverse();
+                }
+                match code {
+                    'H' => Value::U16(u16::from_be_bytes(bytes)),
+                    _ => Value::I16(i16::from_be_bytes(bytes)),
+                }
+            } else {
+                let bytes = order.to_big_endian(bytes.try_into().unwrap());
+                match code {
+                    'I' => Value::U32(u32::from_be_bytes(bytes)),
+                    'i' => Value::I32(i32::from_be_bytes(bytes)),
+                    _ => Value::F32(f32::from_be_bytes(bytes)),
+                }
+            };
+            values.push(value);
+        }
+        Ok(values)
+    }
+
+    /// Decode the registers as consecutive values of a type picked at runtime, widened to `f64`
+    ///
+    /// Multi-register values are arranged by `order` as for 32-bit values: the swap orders
+    /// reverse the registers of a value, the little endian ones the bytes of each register.
+    /// Registers left over at the end are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use easy_modbus::util::WordOrder;
+    /// use easy_modbus::{Frame, NumericType, Response};
+    ///
+    /// let values = vec![0xFF, 0xFF, 0x00, 0x07];
+    /// let response = Frame::tcp().read_holding_register_response(0x01, values);
+    /// if let Response::ReadMultipleHoldingRegisters(_, body) = response {
+    ///     let values = body.get_numeric(NumericType::I16, WordOrder::BigEndian);
+    ///     assert_eq!(values, vec![-1.0, 7.0]);
+    /// }
+    /// ```
+    pub fn get_numeric(&self, ty: NumericType, order: WordOrder) -> Vec<f64> {
+        self.values
+            .chunks_exact(ty.registers() * 2)
+            .map(|bytes| {
+                let mut registers: Vec<[u8; 2]> = bytes
+                    .chunks_exact(2)
+                    .map(|register| [register[0], register[1]])
+                    .collect();
+                if matches!(order, WordOrder::BigEndianSwap | WordOrder::LittleEndian) {
+                    registers.reverse();
+                }
+                if matches!(order, WordOrder::LittleEndianSwap | WordOrder::LittleEndian) {
+                    registers.iter_mut().for_each(|register| register.reverse());
+                }
+                let bytes = registers.concat();
+                match ty {
+                    NumericType::U16 => u16::from_be_bytes([bytes[0], bytes[1]]) as f64,
+                    NumericType::I16 => i16::from_be_bytes([bytes[0], bytes[1]]) as f64,
+                    NumericType::U32 => u32::from_be_bytes(bytes.try_into().unwrap()) as f64,
+                    NumericType::I32 => i32::from_be_bytes(bytes.try_into().unwrap()) as f64,
+                    NumericType::F32 => f32::from_be_bytes(bytes.try_into().unwrap()) as f64,
+                    NumericType::F64 => f64::from_be_bytes(bytes.try_into().unwrap()),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Type of the values decoded by [`ReadMultipleHoldingRegistersResponse::get_numeric`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum NumericType {
+    /// One register
+    U16,
+
+    /// One register
+    I16,
+
+    /// Two registers
+    U32,
+
+    /// Two registers
+    I32,
+
+    /// Two registers
+    F32,
+
+    /// Four registers
+    F64,
+}
+
+impl NumericType {
+    /// Number of registers a value spans
+    pub fn registers(&self) -> usize {
+        match self {
+            NumericType::U16 | NumericType::I16 => 1,
+            NumericType::U32 | NumericType::I32 | NumericType::F32 => 2,
+            NumericType::F64 => 4,
+        }
+    }
+}
+
+/// A value decoded by [`ReadMultipleHoldingRegistersResponse::unpack`]
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub enum Value {
+    U16(u16),
+    I16(i16),
+    U32(u32),
+    I32(i32),
+    F32(f32),
+}
+
+/// A plausible reading of one or two registers, starting at register `index`
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub enum Interpretation {
+    U16 { index: usize, value: u16 },
+    I16 { index: usize, value: i16 },
+    U32 { index: usize, value: u32 },
+    I32 { index: usize, value: i32 },
+    F32 { index: usize, value: f32 },
 }
 
 /// Function Code `0x04`
@@ -216,142 +846,632 @@ impl ReadInputRegistersResponse {
     pub fn get_values(&self) -> &Vec<u8> {
         &self.values
     }
+
+    /// Get the values as registers, pairing big endian bytes
+    ///
+    /// A trailing odd byte, which no valid response carries, is ignored.
+    pub fn get_registers(&self) -> Vec<u16> {
+        registers(&self.values)
+    }
+
+    /// Read a complex phasor stored as two `f32` (real, imaginary) in four registers
+    ///
+    /// `index` is the register index of the real part, the imaginary part follows it. `None`
+    /// if fewer than four registers are available from `index`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use easy_modbus::util::WordOrder;
+    /// use easy_modbus::{Frame, Response};
+    ///
+    /// let values = vec![0x3F, 0x80, 0x00, 0x00, 0xBF, 0x80, 0x00, 0x00];
+    /// let response = Frame::tcp().read_input_register_response(0x01, values);
+    /// if let Response::ReadInputRegisters(_, body) = response {
+    ///     assert_eq!(body.get_complex(0, WordOrder::BigEndian), Some((1.0, -1.0)));
+    ///     assert_eq!(body.get_complex(1, WordOrder::BigEndian), None);
+    /// }
+    /// ```
+    pub fn get_complex(&self, index: usize, order: WordOrder) -> Option<(f32, f32)> {
+        let real = double_register(&self.values, index, order)?;
+        let imag = double_register(&self.values, index + 2, order)?;
+        Some((f32::from_be_bytes(real), f32::from_be_bytes(imag)))
+    }
+
+    /// Read a float from two registers, `None` when it is NaN or equals `sentinel`
+    ///
+    /// See [`ReadMultipleHoldingRegistersResponse::get_f32_opt`].
+    pub fn get_f32_opt(&self, index: usize, order: WordOrder, sentinel: f32) -> Option<f32> {
+        f32_opt(&self.values, index, order, sentinel)
+    }
+
+    /// Map register `index` linearly from `in_min..=in_max` onto `out_min..=out_max`
+    ///
+    /// See [`map_range`]. `None` if register `index` is not available.
+    pub fn get_mapped(
+        &self,
+        index: usize,
+        in_min: u16,
+        in_max: u16,
+        out_min: f64,
+        out_max: f64,
+    ) -> Option<f64> {
+        let raw = register(&self.values, index)?;
+        Some(map_range(raw, in_min, in_max, out_min, out_max))
+    }
+}
+
+/// Get register `index` of `values`, `None` past the last whole register
+fn register(values: &[u8], index: usize) -> Option<u16> {
+    let bytes = values.get(index * 2..index * 2 + 2)?;
+    Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+/// Get all whole registers of `values`
+fn registers(values: &[u8]) -> Vec<u16> {
+    values
+        .chunks_exact(2)
+        .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+        .collect()
+}
+
+/// Get registers `index` and `index + 1` of `values` in big endian order
+fn double_register(values: &[u8], index: usize, order: WordOrder) -> Option<[u8; 4]> {
+    let bytes = values.get(index * 2..index * 2 + 4)?;
+    Some(order.to_big_endian(bytes.try_into().unwrap()))
+}
+
+/// Read a float from registers `index` and `index + 1`, discarding NaN and `sentinel`
+///
+/// A missing register reads as no value at all.
+fn f32_opt(values: &[u8], index: usize, order: WordOrder, sentinel: f32) -> Option<f32> {
+    let bytes = double_register(values, index, order)?;
+    let value = f32::from_be_bytes(bytes);
+    if value.is_nan() || value == sentinel {
+        return None;
+    }
+    Some(value)
+}
+
+/// Function Code `0x05`
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct WriteSingleCoilResponse {
+    /// Address of coil
+    pub(crate) coil_address: u16,
+
+    /// Value to write
+    ///
+    /// 0 (0x0000) for off, 65,280 (0xFF00) for on
+    pub(crate) value: u16,
+}
+
+impl Length for WriteSingleCoilResponse {
+    fn len(&self) -> u16 {
+        4
+    }
+}
+
+impl WriteSingleCoilResponse {
+    pub(crate) fn new(coil_address: u16, value: u16) -> WriteSingleCoilResponse {
+        WriteSingleCoilResponse {
+            coil_address,
+            value,
+        }
+    }
+
+    pub fn get_coil_address(&self) -> &u16 {
+        &self.coil_address
+    }
+
+    pub fn get_value(&self) -> &u16 {
+        &self.value
+    }
+}
+
+/// Function Code `0x06`
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct WriteSingleHoldingRegisterResponse {
+    /// Address of holding register to write
+    pub(crate) register_address: u16,
+
+    /// Value to write
+    pub(crate) value: u16,
+}
+
+impl Length for WriteSingleHoldingRegisterResponse {
+    fn len(&self) -> u16 {
+        4
+    }
+}
+
+impl WriteSingleHoldingRegisterResponse {
+    pub(crate) fn new(register_address: u16, value: u16) -> WriteSingleHoldingRegisterResponse {
+        WriteSingleHoldingRegisterResponse {
+            register_address,
+            value,
+        }
+    }
+
+    pub fn get_register_address(&self) -> &u16 {
+        &self.register_address
+    }
+
+    pub fn get_value(&self) -> &u16 {
+        &self.value
+    }
+
+    /// Get the echoed value scaled to engineering units, `value * scale`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use easy_modbus::{Frame, Response};
+    ///
+    /// let response = Frame::tcp().write_single_holding_register_response(0x01, 0x0001, 250);
+    /// if let Response::WriteSingleHoldingRegister(_, body) = response {
+    ///     assert_eq!(body.get_scaled(0.1), 25.0);
+    /// }
+    /// ```
+    pub fn get_scaled(&self, scale: f64) -> f64 {
+        self.value as f64 * scale
+    }
+}
+
+/// Function Code `0x0F`
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct WriteMultipleCoilsResponse {
+    /// Address of the first coil
+    pub(crate) first_address: u16,
+
+    /// Number of coils
+    pub(crate) coils_number: u16,
+}
+
+impl Length for WriteMultipleCoilsResponse {
+    fn len(&self) -> u16 {
+        4
+    }
+}
+
+impl WriteMultipleCoilsResponse {
+    pub(crate) fn new(first_address: u16, coils_number: u16) -> WriteMultipleCoilsResponse {
+        WriteMultipleCoilsResponse {
+            first_address,
+            coils_number,
+        }
+    }
+
+    pub fn get_first_address(&self) -> &u16 {
+        &self.first_address
+    }
+
+    pub fn get_coils_number(&self) -> &u16 {
+        &self.coils_number
+    }
+}
+
+/// Function Code `0x10`
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct WriteMultipleHoldingRegistersResponse {
+    /// Address of first written holding register
+    pub(crate) first_address: u16,
+
+    /// Number of written holding registers
+    pub(crate) registers_number: u16,
+}
+
+impl Length for WriteMultipleHoldingRegistersResponse {
+    fn len(&self) -> u16 {
+        4
+    }
+}
+
+impl WriteMultipleHoldingRegistersResponse {
+    pub(crate) fn new(
+        first_address: u16,
+        registers_number: u16,
+    ) -> WriteMultipleHoldingRegistersResponse {
+        WriteMultipleHoldingRegistersResponse {
+            first_address,
+            registers_number,
+        }
+    }
+
+    pub fn get_first_address(&self) -> &u16 {
+        &self.first_address
+    }
+
+    pub fn get_registers_number(&self) -> &u16 {
+        &self.registers_number
+    }
+}
+
+/// Function Code `0x0B`
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct GetCommEventCounterResponse {
+    /// Status word
+    ///
+    /// 0xFFFF while a previously issued program command is still being processed, 0x0000
+    /// otherwise.
+    pub(crate) status: u16,
+
+    /// Number of successfully completed messages
+    pub(crate) event_count: u16,
+}
+
+impl Length for GetCommEventCounterResponse {
+    fn len(&self) -> u16 {
+        4
+    }
+}
+
+impl GetCommEventCounterResponse {
+    pub(crate) fn new(status: u16, event_count: u16) -> GetCommEventCounterResponse {
+        GetCommEventCounterResponse {
+            status,
+            event_count,
+        }
+    }
+
+    pub fn get_status(&self) -> &u16 {
+        &self.status
+    }
+
+    pub fn get_event_count(&self) -> &u16 {
+        &self.event_count
+    }
+
+    /// Whether the device is still processing a previously issued program command
+    pub fn is_busy(&self) -> bool {
+        self.status == 0xFFFF
+    }
+}
+
+/// Function Code `0x11`
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ReportServerIdResponse {
+    /// Number of bytes of server id and run indicator status to follow
+    pub(crate) byte_count: u8,
+
+    /// Device specific server id
+    pub(crate) server_id: Vec<u8>,
+
+    /// Run indicator status
+    ///
+    /// 0x00 for off, 0xFF for on
+    pub(crate) run_indicator_status: u8,
+}
+
+impl Length for ReportServerIdResponse {
+    fn len(&self) -> u16 {
+        2 + self.server_id.len() as u16
+    }
+}
+
+impl ReportServerIdResponse {
+    pub(crate) fn new(server_id: Vec<u8>, run_indicator_status: u8) -> ReportServerIdResponse {
+        ReportServerIdResponse {
+            byte_count: server_id.len() as u8 + 1,
+            server_id,
+            run_indicator_status,
+        }
+    }
+
+    pub fn get_byte_count(&self) -> &u8 {
+        &self.byte_count
+    }
+
+    pub fn get_server_id(&self) -> &Vec<u8> {
+        &self.server_id
+    }
+
+    pub fn get_run_indicator_status(&self) -> &u8 {
+        &self.run_indicator_status
+    }
+
+    /// Get the run indicator status as a [`RunStatus`]
+    pub fn get_run_status(&self) -> RunStatus {
+        RunStatus::from_byte(self.run_indicator_status)
+    }
+}
+
+/// Run indicator status of a Report Server ID response
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RunStatus {
+    /// `0xFF`
+    On,
+
+    /// `0x00`
+    Off,
+
+    /// Any other byte, outside of the specification
+    Unknown(u8),
+}
+
+impl RunStatus {
+    /// Read a run indicator status byte
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use easy_modbus::RunStatus;
+    /// assert_eq!(RunStatus::from_byte(0xFF), RunStatus::On);
+    /// ```
+    pub fn from_byte(byte: u8) -> RunStatus {
+        match byte {
+            0xFF => RunStatus::On,
+            0x00 => RunStatus::Off,
+            byte => RunStatus::Unknown(byte),
+        }
+    }
+}
+
+/// Function Code `0x16`
+///
+/// Echoes the request.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MaskWriteRegisterResponse {
+    /// Address of holding register modified
+    pub(crate) reference_address: u16,
+
+    /// Bits of the previous value kept
+    pub(crate) and_mask: u16,
+
+    /// Bits set among the ones not kept
+    pub(crate) or_mask: u16,
+}
+
+impl Length for MaskWriteRegisterResponse {
+    fn len(&self) -> u16 {
+        6
+    }
+}
+
+impl MaskWriteRegisterResponse {
+    pub(crate) fn new(
+        reference_address: u16,
+        and_mask: u16,
+        or_mask: u16,
+    ) -> MaskWriteRegisterResponse {
+        MaskWriteRegisterResponse {
+            reference_address,
+            and_mask,
+            or_mask,
+        }
+    }
+
+    pub fn get_reference_address(&self) -> &u16 {
+        &self.reference_address
+    }
+
+    pub fn get_and_mask(&self) -> &u16 {
+        &self.and_mask
+    }
+
+    pub fn get_or_mask(&self) -> &u16 {
+        &self.or_mask
+    }
+}
+
+/// Function Code `0x17`
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ReadWriteMultipleRegistersResponse {
+    /// Number of bytes of register values to follow
+    pub(crate) bytes_number: u8,
+
+    /// Values of the registers read
+    pub(crate) values: Vec<u8>,
+}
+
+impl Length for ReadWriteMultipleRegistersResponse {
+    fn len(&self) -> u16 {
+        1 + self.values.len() as u16
+    }
+}
+
+impl ReadWriteMultipleRegistersResponse {
+    pub(crate) fn new(values: Vec<u8>) -> ReadWriteMultipleRegistersResponse {
+        let bytes_number = values.len() as u8;
+        ReadWriteMultipleRegistersResponse {
+            bytes_number,
+            values,
+        }
+    }
+
+    pub fn get_bytes_number(&self) -> &u8 {
+        &self.bytes_number
+    }
+
+    pub fn get_values(&self) -> &Vec<u8> {
+        &self.values
+    }
 }
 
-/// Function Code `0x05`
+/// Function Code `0x2B`, MEI Type `0x0E`
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct WriteSingleCoilResponse {
-    /// Address of coil
-    pub(crate) coil_address: u16,
+pub struct ReadDeviceIdentificationResponse {
+    /// Same as in the request
+    pub(crate) read_device_id_code: u8,
 
-    /// Value to write
-    ///
-    /// 0 (0x0000) for off, 65,280 (0xFF00) for on
-    pub(crate) value: u16,
+    /// Identification conformity level of the device
+    pub(crate) conformity_level: u8,
+
+    /// Whether more objects follow in another transaction
+    pub(crate) more_follows: bool,
+
+    /// Object to continue at when more objects follow, 0x00 otherwise
+    pub(crate) next_object_id: u8,
+
+    /// Object ids and their values
+    pub(crate) objects: Vec<(u8, Vec<u8>)>,
 }
 
-impl Length for WriteSingleCoilResponse {
+impl Length for ReadDeviceIdentificationResponse {
     fn len(&self) -> u16 {
-        4
+        let objects_len: usize = self.objects.iter().map(|(_, value)| 2 + value.len()).sum();
+        6 + objects_len as u16
     }
 }
 
-impl WriteSingleCoilResponse {
-    pub(crate) fn new(coil_address: u16, value: u16) -> WriteSingleCoilResponse {
-        WriteSingleCoilResponse {
-            coil_address,
-            value,
+impl ReadDeviceIdentificationResponse {
+    pub(crate) fn new(
+        read_device_id_code: u8,
+        conformity_level: u8,
+        next_object_id: Option<u8>,
+        objects: Vec<(u8, Vec<u8>)>,
+    ) -> ReadDeviceIdentificationResponse {
+        ReadDeviceIdentificationResponse {
+            read_device_id_code,
+            conformity_level,
+            more_follows: next_object_id.is_some(),
+            next_object_id: next_object_id.unwrap_or(0x00),
+            objects,
         }
     }
 
-    pub fn get_coil_address(&self) -> &u16 {
-        &self.coil_address
+    pub fn get_read_device_id_code(&self) -> &u8 {
+        &self.read_device_id_code
     }
 
-    pub fn get_value(&self) -> &u16 {
-        &self.value
+    pub fn get_conformity_level(&self) -> &u8 {
+        &self.conformity_level
+    }
+
+    pub fn get_more_follows(&self) -> &bool {
+        &self.more_follows
+    }
+
+    pub fn get_next_object_id(&self) -> &u8 {
+        &self.next_object_id
+    }
+
+    pub fn get_objects(&self) -> &Vec<(u8, Vec<u8>)> {
+        &self.objects
     }
 }
 
-/// Function Code `0x06`
+/// Function Code `0x07`
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct WriteSingleHoldingRegisterResponse {
-    /// Address of holding register to write
-    pub(crate) register_address: u16,
-
-    /// Value to write
-    pub(crate) value: u16,
+pub struct ReadExceptionStatusResponse {
+    /// States of the eight exception status outputs, the first in the least significant bit
+    ///
+    /// What each output reports is device specific.
+    pub(crate) output_data: u8,
 }
 
-impl Length for WriteSingleHoldingRegisterResponse {
+impl Length for ReadExceptionStatusResponse {
     fn len(&self) -> u16 {
-        4
+        1
     }
 }
 
-impl WriteSingleHoldingRegisterResponse {
-    pub(crate) fn new(register_address: u16, value: u16) -> WriteSingleHoldingRegisterResponse {
-        WriteSingleHoldingRegisterResponse {
-            register_address,
-            value,
-        }
-    }
-
-    pub fn get_register_address(&self) -> &u16 {
-        &self.register_address
+impl ReadExceptionStatusResponse {
+    pub(crate) fn new(output_data: u8) -> ReadExceptionStatusResponse {
+        ReadExceptionStatusResponse { output_data }
     }
 
-    pub fn get_value(&self) -> &u16 {
-        &self.value
+    pub fn get_output_data(&self) -> &u8 {
+        &self.output_data
     }
 }
 
-/// Function Code `0x0F`
+/// Function Code `0x08`
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct WriteMultipleCoilsResponse {
-    /// Address of the first coil
-    pub(crate) first_address: u16,
+pub struct DiagnosticsResponse {
+    /// Same as in the request
+    pub(crate) sub_function: u16,
 
-    /// Number of coils
-    pub(crate) coils_number: u16,
+    /// Result of the test, e.g. a counter, or the data of the request echoed
+    pub(crate) data: u16,
 }
 
-impl Length for WriteMultipleCoilsResponse {
+impl Length for DiagnosticsResponse {
     fn len(&self) -> u16 {
         4
     }
 }
 
-impl WriteMultipleCoilsResponse {
-    pub(crate) fn new(first_address: u16, coils_number: u16) -> WriteMultipleCoilsResponse {
-        WriteMultipleCoilsResponse {
-            first_address,
-            coils_number,
-        }
+impl DiagnosticsResponse {
+    pub(crate) fn new(sub_function: u16, data: u16) -> DiagnosticsResponse {
+        DiagnosticsResponse { sub_function, data }
     }
 
-    pub fn get_first_address(&self) -> &u16 {
-        &self.first_address
+    pub fn get_sub_function(&self) -> &u16 {
+        &self.sub_function
     }
 
-    pub fn get_coils_number(&self) -> &u16 {
-        &self.coils_number
+    pub fn get_data(&self) -> &u16 {
+        &self.data
+    }
+
+    /// Get the sub-function, telling the common ones apart
+    pub fn sub_function(&self) -> DiagnosticsSubFunction {
+        DiagnosticsSubFunction::from_code(self.sub_function)
     }
 }
 
-/// Function Code `0x10`
+/// Function Code `0x18`
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct WriteMultipleHoldingRegistersResponse {
-    /// Address of first written holding register
-    pub(crate) first_address: u16,
+pub struct ReadFifoQueueResponse {
+    /// Number of bytes of FIFO count and values to follow
+    pub(crate) byte_count: u16,
 
-    /// Number of written holding registers
-    pub(crate) registers_number: u16,
+    /// Number of values in the queue, at most 31
+    pub(crate) fifo_count: u16,
+
+    /// Queued values, oldest first
+    pub(crate) values: Vec<u16>,
 }
 
-impl Length for WriteMultipleHoldingRegistersResponse {
+impl Length for ReadFifoQueueResponse {
     fn len(&self) -> u16 {
-        4
+        4 + self.values.len() as u16 * 2
     }
 }
 
-impl WriteMultipleHoldingRegistersResponse {
-    pub(crate) fn new(
-        first_address: u16,
-        registers_number: u16,
-    ) -> WriteMultipleHoldingRegistersResponse {
-        WriteMultipleHoldingRegistersResponse {
-            first_address,
-            registers_number,
+impl ReadFifoQueueResponse {
+    pub(crate) fn new(values: Vec<u16>) -> ReadFifoQueueResponse {
+        ReadFifoQueueResponse {
+            byte_count: 2 + values.len() as u16 * 2,
+            fifo_count: values.len() as u16,
+            values,
         }
     }
 
-    pub fn get_first_address(&self) -> &u16 {
-        &self.first_address
+    pub fn get_byte_count(&self) -> &u16 {
+        &self.byte_count
     }
 
-    pub fn get_registers_number(&self) -> &u16 {
-        &self.registers_number
+    pub fn get_fifo_count(&self) -> &u16 {
+        &self.fifo_count
+    }
+
+    pub fn get_values(&self) -> &Vec<u16> {
+        &self.values
+    }
+}
+
+/// Function Code `0x14`
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ReadFileRecordResponse {
+    /// Registers of each record read, in the order of the request
+    pub(crate) records: Vec<Vec<u16>>,
+}
+
+impl Length for ReadFileRecordResponse {
+    fn len(&self) -> u16 {
+        let data_len: usize = self.records.iter().map(|record| 2 + record.len() * 2).sum();
+        1 + data_len as u16
+    }
+}
+
+impl ReadFileRecordResponse {
+    pub(crate) fn new(records: Vec<Vec<u16>>) -> ReadFileRecordResponse {
+        ReadFileRecordResponse { records }
+    }
+
+    pub fn get_records(&self) -> &Vec<Vec<u16>> {
+        &self.records
     }
 }
 
@@ -374,6 +1494,11 @@ impl ExceptionResponse {
     pub fn get_exception(&self) -> &Exception {
         &self.exception
     }
+
+    /// Get the exception code as sent on the wire
+    pub fn raw_code(&self) -> u8 {
+        self.exception.to_code()
+    }
 }
 
 impl From<ReadCoilsResponse> for BytesMut {
@@ -448,6 +1573,102 @@ impl From<WriteMultipleHoldingRegistersResponse> for BytesMut {
     }
 }
 
+impl From<GetCommEventCounterResponse> for BytesMut {
+    fn from(response: GetCommEventCounterResponse) -> Self {
+        let mut buf = BytesMut::new();
+        buf.put_u16(response.status);
+        buf.put_u16(response.event_count);
+        buf
+    }
+}
+
+impl From<MaskWriteRegisterResponse> for BytesMut {
+    fn from(response: MaskWriteRegisterResponse) -> Self {
+        let mut buf = BytesMut::new();
+        buf.put_u16(response.reference_address);
+        buf.put_u16(response.and_mask);
+        buf.put_u16(response.or_mask);
+        buf
+    }
+}
+
+impl From<ReadWriteMultipleRegistersResponse> for BytesMut {
+    fn from(response: ReadWriteMultipleRegistersResponse) -> Self {
+        let mut buf = BytesMut::new();
+        buf.put_u8(response.bytes_number);
+        buf.put_slice(response.values.as_slice());
+        buf
+    }
+}
+
+impl From<ReportServerIdResponse> for BytesMut {
+    fn from(response: ReportServerIdResponse) -> Self {
+        let mut buf = BytesMut::new();
+        buf.put_u8(response.byte_count);
+        buf.put_slice(response.server_id.as_slice());
+        buf.put_u8(response.run_indicator_status);
+        buf
+    }
+}
+
+impl From<ReadDeviceIdentificationResponse> for BytesMut {
+    fn from(response: ReadDeviceIdentificationResponse) -> Self {
+        let mut buf = BytesMut::new();
+        buf.put_u8(super::MEI_READ_DEVICE_IDENTIFICATION);
+        buf.put_u8(response.read_device_id_code);
+        buf.put_u8(response.conformity_level);
+        buf.put_u8(if response.more_follows { 0xFF } else { 0x00 });
+        buf.put_u8(response.next_object_id);
+        buf.put_u8(response.objects.len() as u8);
+        for (id, value) in response.objects {
+            buf.put_u8(id);
+            buf.put_u8(value.len() as u8);
+            buf.put_slice(value.as_slice());
+        }
+        buf
+    }
+}
+
+impl From<ReadExceptionStatusResponse> for BytesMut {
+    fn from(response: ReadExceptionStatusResponse) -> Self {
+        let mut buf = BytesMut::new();
+        buf.put_u8(response.output_data);
+        buf
+    }
+}
+
+impl From<DiagnosticsResponse> for BytesMut {
+    fn from(response: DiagnosticsResponse) -> Self {
+        let mut buf = BytesMut::new();
+        buf.put_u16(response.sub_function);
+        buf.put_u16(response.data);
+        buf
+    }
+}
+
+impl From<ReadFifoQueueResponse> for BytesMut {
+    fn from(response: ReadFifoQueueResponse) -> Self {
+        let mut buf = BytesMut::new();
+        buf.put_u16(response.byte_count);
+        buf.put_u16(response.fifo_count);
+        response.values.iter().for_each(|&value| buf.put_u16(value));
+        buf
+    }
+}
+
+impl From<ReadFileRecordResponse> for BytesMut {
+    fn from(response: ReadFileRecordResponse) -> Self {
+        let mut buf = BytesMut::new();
+        buf.put_u8(response.len() as u8 - 1);
+        for record in response.records {
+            buf.put_u8(1 + record.len() as u8 * 2);
+            buf.put_u8(super::FILE_RECORD_REFERENCE_TYPE);
+            record.iter().for_each(|&value| buf.put_u16(value));
+        }
+        buf
+    }
+}
+
 impl From<ExceptionResponse> for BytesMut {
     fn from(response: ExceptionResponse) -> Self {
         let mut buf = BytesMut::new();
@@ -478,56 +1699,102 @@ impl From<Head> for BytesMut {
 }
 
 pub(crate) fn response_to_bytesmut(item: Response, dst: &mut BytesMut) {
+    let start = dst.len();
     let version;
     match item {
         Response::ReadCoils(head, body) => {
-            version = head.version.clone();
+            version = head.version;
             dst.put(BytesMut::from(head));
             dst.put(BytesMut::from(body));
         }
         Response::ReadDiscreteInputs(head, body) => {
-            version = head.version.clone();
+            version = head.version;
             dst.put(BytesMut::from(head));
             dst.put(BytesMut::from(body));
         }
         Response::ReadMultipleHoldingRegisters(head, body) => {
-            version = head.version.clone();
+            version = head.version;
             dst.put(BytesMut::from(head));
             dst.put(BytesMut::from(body));
         }
         Response::ReadInputRegisters(head, body) => {
-            version = head.version.clone();
+            version = head.version;
             dst.put(BytesMut::from(head));
             dst.put(BytesMut::from(body));
         }
         Response::WriteSingleCoil(head, body) => {
-            version = head.version.clone();
+            version = head.version;
             dst.put(BytesMut::from(head));
             dst.put(BytesMut::from(body));
         }
         Response::WriteSingleHoldingRegister(head, body) => {
-            version = head.version.clone();
+            version = head.version;
             dst.put(BytesMut::from(head));
             dst.put(BytesMut::from(body));
         }
         Response::WriteMultipleCoils(head, body) => {
-            version = head.version.clone();
+            version = head.version;
             dst.put(BytesMut::from(head));
             dst.put(BytesMut::from(body));
         }
         Response::WriteMultipleHoldingRegisters(head, body) => {
-            version = head.version.clone();
+            version = head.version;
+            dst.put(BytesMut::from(head));
+            dst.put(BytesMut::from(body));
+        }
+        Response::GetCommEventCounter(head, body) => {
+            version = head.version;
+            dst.put(BytesMut::from(head));
+            dst.put(BytesMut::from(body));
+        }
+        Response::ReportServerId(head, body) => {
+            version = head.version;
+            dst.put(BytesMut::from(head));
+            dst.put(BytesMut::from(body));
+        }
+        Response::MaskWriteRegister(head, body) => {
+            version = head.version;
+            dst.put(BytesMut::from(head));
+            dst.put(BytesMut::from(body));
+        }
+        Response::ReadWriteMultipleRegisters(head, body) => {
+            version = head.version;
+            dst.put(BytesMut::from(head));
+            dst.put(BytesMut::from(body));
+        }
+        Response::ReadDeviceIdentification(head, body) => {
+            version = head.version;
+            dst.put(BytesMut::from(head));
+            dst.put(BytesMut::from(body));
+        }
+        Response::ReadExceptionStatus(head, body) => {
+            version = head.version;
+            dst.put(BytesMut::from(head));
+            dst.put(BytesMut::from(body));
+        }
+        Response::Diagnostics(head, body) => {
+            version = head.version;
+            dst.put(BytesMut::from(head));
+            dst.put(BytesMut::from(body));
+        }
+        Response::ReadFifoQueue(head, body) => {
+            version = head.version;
+            dst.put(BytesMut::from(head));
+            dst.put(BytesMut::from(body));
+        }
+        Response::ReadFileRecord(head, body) => {
+            version = head.version;
             dst.put(BytesMut::from(head));
             dst.put(BytesMut::from(body));
         }
         Response::Exception(head, body) => {
-            version = head.version.clone();
+            version = head.version;
             dst.put(BytesMut::from(head));
             dst.put(BytesMut::from(body));
         }
     };
     if Rtu == version {
-        dst.put_u16(crc::compute(&dst.to_vec()));
+        dst.put_u16(crc::compute(&dst[start..]));
     }
 }
 
@@ -535,6 +1802,7 @@ pub(crate) fn response_to_bytesmut(item: Response, dst: &mut BytesMut) {
 mod response_test {
     use crate::frame::response::*;
     use crate::frame::{Exception, Length};
+    use crate::util::WordOrder;
 
     #[test]
     fn test_read_coils_response() {
@@ -548,6 +1816,36 @@ mod response_test {
         assert_eq!(response_l.len(), 5);
     }
 
+    #[test]
+    fn test_read_coils_get_coils() {
+        let response =
+            ReadCoilsResponse::new(vec![0b1100_1101, 0b0110_1011, 0b1011_0010, 0b0111_1111]);
+        let coils = response.get_coils(10);
+        assert_eq!(
+            coils,
+            vec![true, false, true, true, false, false, true, true, true, true]
+        );
+        assert!(!response.get_coils(32)[31]);
+        assert_eq!(response.get_coils(34).len(), 34);
+    }
+
+    #[test]
+    fn test_read_coils_first_set() {
+        let response = ReadCoilsResponse::new(vec![0b0000_0100]);
+        assert_eq!(response.first_set(10, 8), Some(12));
+        assert_eq!(response.first_set(10, 2), None);
+
+        let response = ReadCoilsResponse::new(vec![0x00, 0b1000_0000]);
+        assert_eq!(response.first_set(0x0100, 16), Some(0x010F));
+        assert_eq!(
+            ReadCoilsResponse::new(vec![0x00, 0x00]).first_set(0, 16),
+            None
+        );
+
+        let response = ReadCoilsResponse::new(vec![0x00, 0b0000_0010]);
+        assert_eq!(response.first_set(0xFFF8, 16), None);
+    }
+
     #[test]
     fn test_read_discrete_inputs_response() {
         let response_l = ReadDiscreteInputsResponse::new(vec![
@@ -576,6 +1874,261 @@ mod response_test {
         assert_eq!(response_l.len(), 7);
     }
 
+    #[test]
+    fn test_read_discrete_inputs_get_inputs() {
+        let response = ReadDiscreteInputsResponse::new(vec![
+            0b1010_1100,
+            0b1101_1011,
+            0b1111_1011,
+            0b0000_1101,
+        ]);
+        let inputs = response.get_inputs(22);
+        assert_eq!(
+            inputs[..8],
+            [false, false, true, true, false, true, false, true]
+        );
+        assert_eq!(inputs[8..10], [true, true]);
+        assert_eq!(inputs.len(), 22);
+    }
+
+    #[test]
+    fn test_read_discrete_inputs_changes_from() {
+        let response = ReadDiscreteInputsResponse::new(vec![0b0000_0010]);
+        assert_eq!(
+            response.changes_from(&[false, false, false], 10),
+            vec![(11, true)]
+        );
+
+        let response = ReadDiscreteInputsResponse::new(vec![0xFF, 0x01]);
+        let previous = vec![true; 10];
+        assert_eq!(
+            response.changes_from(&previous, 0xFFF8),
+            vec![(0x0001, false)]
+        );
+    }
+
+    #[test]
+    fn test_read_discrete_inputs_as_map() {
+        let response = ReadDiscreteInputsResponse::new(vec![0b1111_1101]);
+        let inputs = response.as_map(100, 3);
+        assert_eq!(
+            inputs.into_iter().collect::<Vec<_>>(),
+            vec![(100, true), (101, false), (102, true)]
+        );
+
+        assert_eq!(response.as_map(100, 16).len(), 8);
+    }
+
+    #[test]
+    fn test_read_discrete_inputs_run_length_encode() {
+        let response = ReadDiscreteInputsResponse::new(vec![0b0001_1111]);
+        assert_eq!(response.run_length_encode(8), vec![(true, 5), (false, 3)]);
+
+        let response = ReadDiscreteInputsResponse::new(vec![0xFF, 0b0000_0001]);
+        assert_eq!(response.run_length_encode(10), vec![(true, 9), (false, 1)]);
+        assert_eq!(response.run_length_encode(32), vec![(true, 9), (false, 7)]);
+        assert!(response.run_length_encode(0).is_empty());
+    }
+
+    #[test]
+    fn test_read_multiple_holding_registers_i32_eng() {
+        let response = ReadMultipleHoldingRegistersResponse::new(vec![0xFF, 0xFF, 0xCF, 0xC7]);
+        let value = response.get_i32_eng(0, WordOrder::BigEndian, 0.01, 0.0);
+        assert!((value.unwrap() - -123.45).abs() < 1e-9);
+        assert_eq!(
+            response.get_i32_eng(1, WordOrder::BigEndian, 0.01, 0.0),
+            None
+        );
+
+        let response = ReadMultipleHoldingRegistersResponse::new(vec![0xCF, 0xC7, 0xFF, 0xFF]);
+        let value = response.get_i32_eng(0, WordOrder::BigEndianSwap, 0.01, 0.0);
+        assert!((value.unwrap() - -123.45).abs() < 1e-9);
+        let value = response.get_i32_eng(0, WordOrder::BigEndianSwap, 0.01, 100.0);
+        assert!((value.unwrap() - -23.45).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_read_multiple_holding_registers_f32_opt() {
+        let response = ReadMultipleHoldingRegistersResponse::new(vec![
+            0x3F, 0x80, 0x00, 0x00, 0xC4, 0x79, 0xC0, 0x00, 0xFF, 0xFF, 0xFF, 0xFF,
+        ]);
+        let order = WordOrder::BigEndian;
+        assert_eq!(response.get_f32_opt(0, order, -999.0), Some(1.0));
+        assert_eq!(response.get_f32_opt(2, order, -999.0), None);
+        assert_eq!(response.get_f32_opt(4, order, -999.0), None);
+        assert_eq!(response.get_f32_opt(2, order, 0.0), Some(-999.0));
+        assert_eq!(response.get_f32_opt(5, order, -999.0), None);
+
+        let response = ReadMultipleHoldingRegistersResponse::new(vec![0x00, 0x00, 0x3F, 0x80]);
+        assert_eq!(
+            response.get_f32_opt(0, WordOrder::BigEndianSwap, -999.0),
+            Some(1.0)
+        );
+    }
+
+    #[test]
+    fn test_read_multiple_holding_registers_u32_f32() {
+        // 0x41200000 is 10.0
+        let values = vec![0x41, 0x20, 0x00, 0x00, 0x00, 0x00, 0x41, 0x20];
+        let response = ReadMultipleHoldingRegistersResponse::new(values);
+        assert_eq!(response.get_u32(0, WordOrder::BigEndian), Some(0x4120_0000));
+        assert_eq!(response.get_f32(0, WordOrder::BigEndian), Some(10.0));
+        assert_eq!(response.get_f32(2, WordOrder::BigEndianSwap), Some(10.0));
+        assert_eq!(response.get_u32(1, WordOrder::BigEndian), Some(0x0000_0000));
+        assert_eq!(response.get_u32(3, WordOrder::BigEndian), None);
+        assert_eq!(response.get_f32(4, WordOrder::LittleEndian), None);
+    }
+
+    #[test]
+    fn test_read_multiple_holding_registers_get_numeric() {
+        let response = ReadMultipleHoldingRegistersResponse::new(vec![0xFF, 0xFF, 0x00, 0x02]);
+        let unsigned = response.get_numeric(NumericType::U16, WordOrder::BigEndian);
+        let signed = response.get_numeric(NumericType::I16, WordOrder::BigEndian);
+        assert_eq!(unsigned, vec![65535.0, 2.0]);
+        assert_eq!(signed, vec![-1.0, 2.0]);
+
+        let values = response.get_numeric(NumericType::U32, WordOrder::BigEndianSwap);
+        assert_eq!(values, vec![0x0002_FFFF as f64]);
+        let values = response.get_numeric(NumericType::U16, WordOrder::LittleEndian);
+        assert_eq!(values, vec![65535.0, 512.0]);
+        assert!(response
+            .get_numeric(NumericType::F64, WordOrder::BigEndian)
+            .is_empty());
+
+        let values = 1.5f64.to_be_bytes().to_vec();
+        let response = ReadMultipleHoldingRegistersResponse::new(values.clone());
+        assert_eq!(
+            response.get_numeric(NumericType::F64, WordOrder::BigEndian),
+            vec![1.5]
+        );
+        let reversed = values.iter().rev().copied().collect();
+        let response = ReadMultipleHoldingRegistersResponse::new(reversed);
+        assert_eq!(
+            response.get_numeric(NumericType::F64, WordOrder::LittleEndian),
+            vec![1.5]
+        );
+    }
+
+    #[test]
+    fn test_get_registers() {
+        let response = ReadMultipleHoldingRegistersResponse::new(vec![0x00, 0xFA, 0x12, 0x34]);
+        assert_eq!(response.get_registers(), vec![0x00FA, 0x1234]);
+
+        let response = ReadInputRegistersResponse::new(vec![0x00, 0xFA, 0x12]);
+        assert_eq!(response.get_registers(), vec![0x00FA]);
+    }
+
+    #[test]
+    fn test_read_multiple_holding_registers_dual_view() {
+        let response = ReadMultipleHoldingRegistersResponse::new(vec![0x00, 0xFA, 0x01, 0x00]);
+        assert_eq!(
+            response.dual_view(&[0.1]),
+            vec![(250, 25.0), (0x0100, 256.0)]
+        );
+        assert!(response
+            .dual_view(&[])
+            .iter()
+            .all(|&(raw, scaled)| raw as f64 == scaled));
+    }
+
+    #[test]
+    fn test_read_multiple_holding_registers_interpret() {
+        let response = ReadMultipleHoldingRegistersResponse::new(vec![0x42, 0xF6, 0xE9, 0x79]);
+        let interpretations = response.interpret();
+        assert!(interpretations.contains(&Interpretation::U16 {
+            index: 0,
+            value: 0x42F6
+        }));
+        assert!(interpretations.contains(&Interpretation::I16 {
+            index: 1,
+            value: -5767
+        }));
+        assert!(interpretations.contains(&Interpretation::U32 {
+            index: 0,
+            value: 0x42F6_E979
+        }));
+        let float = interpretations
+            .iter()
+            .find_map(|interpretation| match interpretation {
+                Interpretation::F32 { index: 0, value } => Some(*value),
+                _ => None,
+            });
+        assert!((float.unwrap() - 123.46).abs() < 1e-2);
+
+        let response = ReadMultipleHoldingRegistersResponse::new(vec![0x7F, 0xC0, 0x00, 0x00]);
+        let interpretations = response.interpret();
+        assert_eq!(interpretations.len(), 6);
+        assert!(!interpretations
+            .iter()
+            .any(|interpretation| matches!(interpretation, Interpretation::F32 { .. })));
+    }
+
+    #[test]
+    fn test_report_server_id_run_status() {
+        assert_eq!(RunStatus::from_byte(0xFF), RunStatus::On);
+        assert_eq!(RunStatus::from_byte(0x00), RunStatus::Off);
+        assert_eq!(RunStatus::from_byte(0x42), RunStatus::Unknown(0x42));
+
+        let response = ReportServerIdResponse::new(vec![0x0A], 0xFF);
+        assert_eq!(response.get_run_status(), RunStatus::On);
+    }
+
+    #[test]
+    fn test_read_multiple_holding_registers_unpack() {
+        let response =
+            ReadMultipleHoldingRegistersResponse::new(vec![0x00, 0x2A, 0x42, 0xF6, 0xE9, 0x79]);
+        let values = response.unpack(">Hf").unwrap();
+        assert_eq!(values[0], Value::U16(42));
+        match values[1] {
+            Value::F32(value) => assert!((value - 123.456).abs() < 1e-3),
+            value => panic!("unexpected value {:?}", value),
+        }
+
+        let response =
+            ReadMultipleHoldingRegistersResponse::new(vec![0x2A, 0x00, 0x79, 0xE9, 0xF6, 0x42]);
+        assert_eq!(
+            response.unpack("<HI").unwrap(),
+            vec![Value::U16(42), Value::U32(0x42F6_E979)]
+        );
+
+        assert!(matches!(
+            response.unpack("HII"),
+            Err(ModbusError::InvalidFrame(_))
+        ));
+        assert!(matches!(
+            response.unpack("Hx"),
+            Err(ModbusError::InvalidFrame(_))
+        ));
+    }
+
+    #[test]
+    fn test_read_input_registers_mapped() {
+        let response = ReadInputRegistersResponse::new(vec![0x00, 0x00, 0x08, 0x00, 0x0F]);
+        assert_eq!(response.get_mapped(0, 0, 4096, 4.0, 20.0), Some(4.0));
+        assert_eq!(response.get_mapped(1, 0, 4096, 4.0, 20.0), Some(12.0));
+        assert_eq!(response.get_mapped(2, 0, 4096, 4.0, 20.0), None);
+    }
+
+    #[test]
+    fn test_read_input_registers_complex() {
+        let response =
+            ReadInputRegistersResponse::new(vec![0x3F, 0x80, 0x00, 0x00, 0xBF, 0x80, 0x00, 0x00]);
+        assert_eq!(
+            response.get_complex(0, WordOrder::BigEndian),
+            Some((1.0, -1.0))
+        );
+        assert_eq!(response.get_complex(1, WordOrder::BigEndian), None);
+
+        let response = ReadInputRegistersResponse::new(vec![
+            0x12, 0x34, 0x00, 0x00, 0x3F, 0x80, 0x00, 0x00, 0xBF, 0x80,
+        ]);
+        assert_eq!(
+            response.get_complex(1, WordOrder::BigEndianSwap),
+            Some((1.0, -1.0))
+        );
+        assert_eq!(response.get_complex(2, WordOrder::BigEndianSwap), None);
+    }
+
     #[test]
     fn test_read_input_register_response() {
         let response_l = ReadInputRegistersResponse::new(vec![0x0C, 0x00, 0x00, 0x00]);
@@ -607,6 +2160,9 @@ mod response_test {
         };
         assert_eq!(response_l, response_r);
         assert_eq!(response_l.len(), 4);
+
+        let response = WriteSingleHoldingRegisterResponse::new(0x01, 250);
+        assert_eq!(response.get_scaled(0.1), 25.0);
     }
 
     #[test]
@@ -631,6 +2187,17 @@ mod response_test {
         assert_eq!(response_l.len(), 4);
     }
 
+    #[test]
+    fn test_get_comm_event_counter_response() {
+        let response = GetCommEventCounterResponse::new(0xFFFF, 0x0108);
+        assert!(response.is_busy());
+        assert_eq!(*response.get_event_count(), 0x0108);
+        assert_eq!(response.len(), 4);
+
+        let response = GetCommEventCounterResponse::new(0x0000, 0x0108);
+        assert!(!response.is_busy());
+    }
+
     #[test]
     fn test_exception_response() {
         let response_l = ExceptionResponse::new(Exception::IllegalDataAddress);
@@ -640,4 +2207,12 @@ mod response_test {
         assert_eq!(response_l, response_r);
         assert_eq!(response_l.len(), 1);
     }
+
+    #[test]
+    fn test_exception_raw_code() {
+        let response = ExceptionResponse::new(Exception::IllegalDataAddress);
+        assert_eq!(response.raw_code(), 0x02);
+        let response = ExceptionResponse::new(Exception::Unknown(0x0C));
+        assert_eq!(response.raw_code(), 0x0C);
+    }
 }
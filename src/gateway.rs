@@ -0,0 +1,193 @@
+//! Gateway forwarding Modbus TCP requests to a RTU bus.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use tokio::net::TcpListener;
+//! use tokio_serial::SerialStream;
+//! use tokio_util::codec::Framed;
+//!
+//! use easy_modbus::client::Client;
+//! use easy_modbus::codec::TcpServerCodec;
+//! use easy_modbus::gateway::Gateway;
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let port = SerialStream::open(&tokio_serial::new("/dev/ttyUSB0", 9600))?;
+//!     let mut gateway = Gateway::new(Client::rtu(port));
+//!
+//!     let listener = TcpListener::bind("127.0.0.1:502").await?;
+//!     let (stream, _) = listener.accept().await?;
+//!     gateway.serve(Framed::new(stream, TcpServerCodec)).await?;
+//!     Ok(())
+//! }
+//! ```
+
+use std::time::Duration;
+
+use futures::{Sink, SinkExt, Stream, StreamExt};
+use tokio::time;
+
+use crate::client::{Client, Transport};
+use crate::error::{ModbusError, Result};
+use crate::frame::{request::Request, response::Response, Exception};
+use crate::server;
+
+/// Modbus gateway answering TCP requests with the responses of RTU devices
+#[derive(Debug)]
+pub struct Gateway<T> {
+    /// Client of the RTU bus the requests are forwarded to
+    client: Client<T>,
+
+    /// Time a device has to answer a forwarded request
+    timeout: Duration,
+}
+
+impl<T: Transport> Gateway<T> {
+    /// Create a gateway forwarding requests through `client`
+    ///
+    /// Devices have one second to answer, see [`Gateway::set_timeout`].
+    pub fn new(client: Client<T>) -> Self {
+        Gateway {
+            client,
+            timeout: Duration::from_secs(1),
+        }
+    }
+
+    /// Give up on a device that has not answered within `timeout` of the request
+    ///
+    /// The gateway stops waiting even if `client` has no timeout of its own, so that a silent
+    /// device cannot hold up the connection forever.
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Forward a TCP request to the RTU bus and build the TCP response from the answer
+    ///
+    /// A device failing to answer yields a [`Exception::GatewayTargetDeviceFailedToRespond`]
+    /// exception. An answer with another unit id or function than the request, e.g. corrupted
+    /// on the bus, yields a [`Exception::GatewayPathUnavailable`] exception instead of being
+    /// passed on.
+    ///
+    /// Writes to unit 0 are broadcast with [`Client::broadcast`] and get no response, as no
+    /// device answers them.
+    pub async fn forward(&mut self, request: Request) -> Option<Response> {
+        let head = request.head();
+        let request = request.to_rtu();
+        if request.is_broadcast() {
+            // Nobody is waiting for an answer, so a failed write has no one to be reported to
+            let _ = self.client.broadcast(request).await;
+            return None;
+        }
+
+        let response = match time::timeout(self.timeout, self.client.call(request)).await {
+            Ok(Ok(response)) => response,
+            Ok(Err(_)) | Err(_) => {
+                let exception = Exception::GatewayTargetDeviceFailedToRespond;
+                return Some(server::exception(head, exception));
+            }
+        };
+
+        let response_head = response.head();
+        if response_head.uid != head.uid || response_head.function != head.function {
+            return Some(server::exception(head, Exception::GatewayPathUnavailable));
+        }
+        Some(response.to_tcp(head.tid))
+    }
+
+    /// Forward requests from one framed TCP connection until it is closed
+    pub async fn serve<S>(&mut self, mut transport: S) -> Result<()>
+    where
+        S: Sink<Response, Error = ModbusError> + Stream<Item = Result<Request>> + Unpin,
+    {
+        while let Some(request) = transport.next().await {
+            if let Some(response) = self.forward(request?).await {
+                transport.send(response).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod gateway_test {
+    use std::time::Duration;
+
+    use futures::{SinkExt, StreamExt};
+    use tokio::io::duplex;
+    use tokio::sync::mpsc;
+    use tokio_util::codec::Framed;
+
+    use crate::client::Client;
+    use crate::codec::RtuServerCodec;
+    use crate::frame::{Exception, Frame, Function};
+    use crate::gateway::Gateway;
+
+    #[tokio::test]
+    async fn forward_test() {
+        let (client_io, device_io) = duplex(256);
+        tokio::spawn(async move {
+            let mut transport = Framed::new(device_io, RtuServerCodec);
+            let frame = Frame::rtu();
+            while let Some(Ok(request)) = transport.next().await {
+                let response = match request.head().function {
+                    Function::ReadCoils => frame.read_coils_response(0x0B, vec![0xCD, 0x6B]),
+                    // A response to another function, as if corrupted on the bus
+                    _ => frame.write_single_coil_response(0x0B, 0x00AC, 0xFF00),
+                };
+                transport.send(response).await.unwrap();
+            }
+        });
+        let mut gateway = Gateway::new(Client::rtu(client_io));
+
+        let frame = Frame::tcp();
+        let request = frame.read_coils_request(0x0B, 0x001D, 0x000F);
+        let tid = request.head().tid;
+        let response = gateway.forward(request).await.unwrap();
+        let mut expected = frame.read_coils_response(0x0B, vec![0xCD, 0x6B]);
+        let mut head = expected.head();
+        head.tid = tid;
+        expected.set_head(head);
+        assert_eq!(response, expected);
+
+        let request = frame.read_discrete_request(0x0B, 0x00C4, 0x0016);
+        let head = request.head();
+        let response = gateway.forward(request).await.unwrap();
+        assert_eq!(
+            response.as_exception(),
+            Some(&Exception::GatewayPathUnavailable)
+        );
+        assert_eq!(response.head().function, Function::ReadDiscreteInputs);
+        assert_eq!(response.head().tid, head.tid);
+    }
+    #[tokio::test(start_paused = true)]
+    async fn silent_device_test() {
+        let (client_io, device_io) = duplex(256);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let mut transport = Framed::new(device_io, RtuServerCodec);
+            while let Some(Ok(request)) = transport.next().await {
+                tx.send(request).unwrap();
+            }
+        });
+        let mut gateway = Gateway::new(Client::rtu(client_io));
+        gateway.set_timeout(Duration::from_millis(500));
+
+        let frame = Frame::tcp();
+        let request = frame.read_coils_request(0x0B, 0x001D, 0x000F);
+        let response = gateway.forward(request).await.unwrap();
+        assert_eq!(
+            response.as_exception(),
+            Some(&Exception::GatewayTargetDeviceFailedToRespond)
+        );
+        assert_eq!(response.head().function, Function::ReadCoils);
+        assert!(rx.recv().await.is_some());
+
+        // Nobody answers a broadcast, so the gateway does not wait for one
+        let request = frame.write_single_coil_request(0x00, 0x00AC, 0xFF00);
+        assert!(gateway.forward(request).await.is_none());
+        let broadcast = rx.recv().await.unwrap();
+        assert_eq!(broadcast.head().uid, 0x00);
+        assert_eq!(broadcast.head().function, Function::WriteSingleCoil);
+    }
+}
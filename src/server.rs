@@ -0,0 +1,256 @@
+//! Async server dispatching decoded requests to a handler.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use std::collections::HashSet;
+//!
+//! use tokio::net::TcpListener;
+//!
+//! use easy_modbus::server::Server;
+//! use easy_modbus::{Frame, Function, Request, Response};
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let mut server = Server::new(|request: Request| -> Response {
+//!         let frame = Frame::tcp();
+//!         frame.read_coils_response(request.head().uid(), vec![0x00, 0x01])
+//!     });
+//!     server.set_allowed_functions(HashSet::from([Function::ReadCoils]));
+//!
+//!     let listener = TcpListener::bind("127.0.0.1:502").await?;
+//!     server.serve_tcp(listener).await?;
+//!     Ok(())
+//! }
+//! ```
+
+use std::collections::HashSet;
+use std::io::Result;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::{Sink, SinkExt, Stream, StreamExt};
+use tokio::net::TcpListener;
+use tokio::time::timeout;
+use tokio_util::codec::Framed;
+
+use crate::codec::TcpServerCodec;
+use crate::error::ModbusError;
+use crate::frame::{
+    request::Request,
+    response::{ExceptionResponse, Response},
+    Exception, Function, Head,
+};
+
+/// Modbus server
+#[derive(Debug)]
+pub struct Server<H> {
+    /// Builds a response for every permitted request
+    handler: H,
+
+    /// Functions the handler may be called for, `None` permits every function
+    allowed_functions: Option<HashSet<Function>>,
+
+    /// Time a connection may stay without requests before it is closed, `None` keeps it open
+    idle_timeout: Option<Duration>,
+}
+
+impl<H> Server<H>
+where
+    H: Fn(Request) -> Response + Send + Sync + 'static,
+{
+    /// Create a server calling `handler` for each request
+    pub fn new(handler: H) -> Self {
+        Server {
+            handler,
+            allowed_functions: None,
+            idle_timeout: None,
+        }
+    }
+
+    /// Only permit the given functions
+    ///
+    /// Requests for any other function are answered with [`Exception::IllegalFunction`] without
+    /// reaching the handler, e.g. to expose a read-only server.
+    pub fn set_allowed_functions(&mut self, functions: HashSet<Function>) {
+        self.allowed_functions = Some(functions);
+    }
+
+    /// Close connections that send no request for `idle_timeout`
+    ///
+    /// Frees the resources of clients that went away without closing their connection. Off by
+    /// default.
+    pub fn set_idle_timeout(&mut self, idle_timeout: Duration) {
+        self.idle_timeout = Some(idle_timeout);
+    }
+
+    /// Build the response to a single request
+    ///
+    /// The response carries the transaction id of the request, whatever the handler set.
+    pub fn handle(&self, request: Request) -> Response {
+        let head = request.head();
+        if let Some(allowed) = &self.allowed_functions {
+            if !allowed.contains(&head.function) {
+                return exception(head, Exception::IllegalFunction);
+            }
+        }
+        let mut response = (self.handler)(request);
+        let mut response_head = response.head();
+        response_head.tid = head.tid;
+        response.set_head(response_head);
+        response
+    }
+
+    /// Serve requests from one framed connection until it is closed, or idle for longer than
+    /// the idle timeout
+    pub async fn serve<T>(&self, mut transport: T) -> Result<()>
+    where
+        T: Sink<Response, Error = ModbusError>
+            + Stream<Item = crate::error::Result<Request>>
+            + Unpin,
+    {
+        loop {
+            let request = match self.idle_timeout {
+                Some(idle_timeout) => match timeout(idle_timeout, transport.next()).await {
+                    Ok(request) => request,
+                    Err(_) => return Ok(()),
+                },
+                None => transport.next().await,
+            };
+            match request {
+                Some(request) => {
+                    let response = self.handle(request?);
+                    transport.send(response).await?;
+                }
+                None => return Ok(()),
+            }
+        }
+    }
+
+    /// Accept Modbus TCP connections and serve each of them on its own task
+    pub async fn serve_tcp(self, listener: TcpListener) -> Result<()> {
+        let server = Arc::new(self);
+        loop {
+            let (stream, _) = listener.accept().await?;
+            let server = server.clone();
+            tokio::spawn(async move {
+                let _ = server.serve(Framed::new(stream, TcpServerCodec)).await;
+            });
+        }
+    }
+}
+
+/// Build an exception response answering the request with `head`
+pub(crate) fn exception(head: Head, exception: Exception) -> Response {
+    let body = ExceptionResponse::new(exception);
+    let head = Head::new(head.tid, head.uid, head.function, 1, head.version, true);
+    Response::Exception(head, body)
+}
+
+#[cfg(test)]
+mod server_test {
+    use std::collections::HashSet;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use futures::{SinkExt, StreamExt};
+    use tokio::io::{duplex, AsyncReadExt};
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio::time::{sleep, Instant};
+    use tokio_util::codec::Framed;
+
+    use crate::client::Client;
+    use crate::codec::{TcpClientCodec, TcpServerCodec};
+    use crate::frame::{Exception, Frame, Function};
+    use crate::server::Server;
+    use crate::{Request, Response};
+
+    fn read_only_server() -> Server<impl Fn(Request) -> Response + Send + Sync + 'static> {
+        let mut server = Server::new(|request: Request| {
+            let frame = Frame::tcp();
+            match request {
+                Request::ReadCoils(head, _) => frame.read_coils_response(head.uid(), vec![0x01]),
+                request => frame.write_single_coil_response(request.head().uid(), 0x00, 0xFF00),
+            }
+        });
+        server.set_allowed_functions(HashSet::from([
+            Function::ReadCoils,
+            Function::ReadDiscreteInputs,
+            Function::ReadMultipleHoldingRegisters,
+            Function::ReadInputRegisters,
+        ]));
+        server
+    }
+
+    #[test]
+    fn allowed_functions_test() {
+        let server = read_only_server();
+
+        let response_l = server.handle(Frame::tcp().read_coils_request(0x01, 0x00, 0x08));
+        let response_r = Frame::tcp().read_coils_response(0x01, vec![0x01]);
+        assert_eq!(response_l, response_r);
+
+        let request = Frame::tcp().write_single_coil_request(0x01, 0x00, 0xFF00);
+        let response_l = server.handle(request);
+        let response_r = Frame::tcp().exception_response(
+            0x01,
+            Function::WriteSingleCoil,
+            Exception::IllegalFunction,
+        );
+        assert_eq!(response_l, response_r);
+    }
+
+    #[tokio::test]
+    async fn serve_tcp_test() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(read_only_server().serve_tcp(listener));
+
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut transport = Framed::new(stream, TcpClientCodec::default());
+        let frame = Frame::tcp();
+
+        let request = frame.write_single_coil_request(0x01, 0x00, 0xFF00);
+        transport.send(request).await.unwrap();
+        match transport.next().await.unwrap().unwrap() {
+            Response::Exception(_, body) => {
+                assert_eq!(body.get_exception(), &Exception::IllegalFunction)
+            }
+            response => panic!("unexpected response {:?}", response),
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn idle_timeout_test() {
+        let mut server = read_only_server();
+        server.set_idle_timeout(Duration::from_secs(10));
+        let server = Arc::new(server);
+
+        let (mut idle_io, server_io) = duplex(256);
+        let idle = {
+            let server = server.clone();
+            tokio::spawn(async move { server.serve(Framed::new(server_io, TcpServerCodec)).await })
+        };
+        let (active_io, server_io) = duplex(256);
+        let active = {
+            let server = server.clone();
+            tokio::spawn(async move { server.serve(Framed::new(server_io, TcpServerCodec)).await })
+        };
+
+        // A request every 6 seconds keeps the active connection open for 30 seconds
+        let start = Instant::now();
+        let mut client = Client::tcp(active_io);
+        for round in 1..=5 {
+            sleep(Duration::from_secs(6)).await;
+            let request = Frame::tcp().read_coils_request(0x01, 0x00, 0x08);
+            assert!(client.call(request).await.is_ok());
+            // The idle connection is closed between 6 and 12 seconds
+            assert_eq!(idle.is_finished(), round > 1);
+        }
+        assert!(!active.is_finished());
+
+        idle.await.unwrap().unwrap();
+        assert_eq!(idle_io.read(&mut [0; 8]).await.unwrap(), 0);
+        assert!(start.elapsed() >= Duration::from_secs(30));
+    }
+}
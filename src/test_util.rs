@@ -0,0 +1,272 @@
+//! Helpers to test clients against recorded device behavior.
+
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+
+use bytes::BytesMut;
+use tokio_util::codec::Decoder;
+
+use crate::codec::{RtuClientCodec, RtuServerCodec, TcpClientCodec, TcpServerCodec};
+use crate::error::{ModbusError, Result};
+use crate::frame::{request::Request, response::Response, Exception, Version};
+use crate::server::{self, Server};
+
+/// Mock server answering requests with the responses of a recorded session
+///
+/// The session is a text file with one frame per line, as printed by the `Display`
+/// implementations of [`Request`] and [`Response`]: a request line starts with `>` and is
+/// followed by a response line starting with `<`. Blank lines and lines starting with `#` are
+/// ignored. All frames use the protocol version given when loading the session, and RTU frames
+/// must carry a valid CRC.
+///
+/// ```text
+/// # Read two holding registers
+/// > 00 01 00 00 00 06 01 03 00 00 00 02
+/// < 00 01 00 00 00 07 01 03 04 00 0A 01 02
+/// ```
+///
+/// A request matches a recorded one with the same unit id, function and data, whatever its
+/// transaction id or protocol version. Matching requests get the recorded responses in order,
+/// the last one repeating once all were served. Requests matching none get an
+/// [`Exception::IllegalFunction`] response.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use tokio::net::TcpListener;
+///
+/// use easy_modbus::test_util::ReplayServer;
+/// use easy_modbus::Version;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let server = ReplayServer::from_log("session.log", Version::Tcp)?.into_server();
+///     let listener = TcpListener::bind("127.0.0.1:5020").await?;
+///     server.serve_tcp(listener).await?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct ReplayServer {
+    /// Recorded requests, converted to RTU, with their responses and whether they were served
+    exchanges: Mutex<Vec<(Request, Response, bool)>>,
+}
+
+impl ReplayServer {
+    /// Load a recorded session of `version` frames from the file at `path`
+    pub fn from_log(path: impl AsRef<Path>, version: Version) -> Result<ReplayServer> {
+        let log = fs::read_to_string(path)?;
+        let mut exchanges = Vec::new();
+        let mut request = None;
+        for (number, line) in log.lines().enumerate() {
+            let line = line.trim();
+            let invalid = |message: &str| {
+                ModbusError::InvalidFrame(format!("Line {}: {}", number + 1, message))
+            };
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(frame) = line.strip_prefix('>') {
+                if request.is_some() {
+                    return Err(invalid("request without response"));
+                }
+                let bytes = parse_hex(frame).ok_or_else(|| invalid("invalid hex bytes"))?;
+                request =
+                    Some(decode_request(bytes, version).ok_or_else(|| invalid("invalid request"))?);
+            } else if let Some(frame) = line.strip_prefix('<') {
+                let request = request
+                    .take()
+                    .ok_or_else(|| invalid("response without request"))?;
+                let bytes = parse_hex(frame).ok_or_else(|| invalid("invalid hex bytes"))?;
+                let response =
+                    decode_response(bytes, version).ok_or_else(|| invalid("invalid response"))?;
+                exchanges.push((request.to_rtu(), response, false));
+            } else {
+                return Err(invalid("expect a line starting with > or <"));
+            }
+        }
+        if request.is_some() {
+            return Err(ModbusError::InvalidFrame(
+                "Last request without response".to_string(),
+            ));
+        }
+        Ok(ReplayServer {
+            exchanges: Mutex::new(exchanges),
+        })
+    }
+
+    /// Build the response to a single request
+    pub fn handle(&self, request: Request) -> Response {
+        let head = request.head();
+        let key = request.to_rtu();
+        let mut exchanges = self.exchanges.lock().unwrap();
+        let mut exchange = None;
+        for recorded in exchanges
+            .iter_mut()
+            .filter(|(recorded, _, _)| *recorded == key)
+        {
+            let served = recorded.2;
+            exchange = Some(recorded);
+            if !served {
+                break;
+            }
+        }
+        let mut response = match exchange {
+            Some((_, response, served)) => {
+                *served = true;
+                response.clone()
+            }
+            None => return server::exception(head, Exception::IllegalFunction),
+        };
+
+        let mut response_head = response.head();
+        response_head.tid = head.tid;
+        response_head.version = head.version;
+        response.set_head(response_head);
+        response
+    }
+
+    /// Serve the recorded session, e.g. with [`Server::serve_tcp`]
+    pub fn into_server(self) -> Server<impl Fn(Request) -> Response + Send + Sync + 'static> {
+        Server::new(move |request| self.handle(request))
+    }
+}
+
+fn parse_hex(frame: &str) -> Option<BytesMut> {
+    frame
+        .split_whitespace()
+        .map(|byte| u8::from_str_radix(byte, 16).ok())
+        .collect::<Option<Vec<u8>>>()
+        .map(|bytes| BytesMut::from(&bytes[..]))
+}
+
+/// Decode a whole request frame of `version`
+fn decode_request(mut buf: BytesMut, version: Version) -> Option<Request> {
+    let request = match version {
+        Version::Tcp => TcpServerCodec.decode(&mut buf),
+        Version::Rtu => RtuServerCodec.decode(&mut buf),
+    };
+    match request {
+        Ok(Some(request)) if buf.is_empty() => Some(request),
+        _ => None,
+    }
+}
+
+/// Decode a whole response frame of `version`
+fn decode_response(mut buf: BytesMut, version: Version) -> Option<Response> {
+    let response = match version {
+        Version::Tcp => TcpClientCodec::default().decode(&mut buf),
+        Version::Rtu => RtuClientCodec::default().decode(&mut buf),
+    };
+    match response {
+        Ok(Some(response)) if buf.is_empty() => Some(response),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod replay_server_test {
+    use std::fs;
+
+    use tokio::io::duplex;
+    use tokio_util::codec::Framed;
+
+    use crate::client::Client;
+    use crate::codec::{RtuServerCodec, TcpServerCodec};
+    use crate::frame::{Exception, Frame, Version};
+    use crate::test_util::ReplayServer;
+    use crate::Response;
+
+    const SESSION: &str = "\
+# Read two holding registers, twice
+> 00 01 00 00 00 06 01 03 00 00 00 02
+< 00 01 00 00 00 07 01 03 04 00 0A 01 02
+> 00 02 00 00 00 06 01 03 00 00 00 02
+< 00 02 00 00 00 07 01 03 04 00 0B 01 03
+";
+
+    #[tokio::test]
+    async fn replay_test() {
+        let path = std::env::temp_dir().join(format!("replay_test_{}.log", std::process::id()));
+        fs::write(&path, SESSION).unwrap();
+        let server = ReplayServer::from_log(&path, Version::Tcp)
+            .unwrap()
+            .into_server();
+        fs::remove_file(&path).unwrap();
+
+        let (client_io, server_io) = duplex(256);
+        tokio::spawn(async move { server.serve(Framed::new(server_io, TcpServerCodec)).await });
+        let mut client = Client::tcp(client_io);
+        let frame = Frame::tcp();
+
+        let mut values = Vec::new();
+        for _ in 0..3 {
+            let request = frame.read_multiple_holding_registers_request(0x01, 0x0000, 0x0002);
+            match client.call(request).await.unwrap() {
+                Response::ReadMultipleHoldingRegisters(_, body) => values.push(body.values),
+                response => panic!("unexpected response {:?}", response),
+            }
+        }
+        assert_eq!(
+            values,
+            vec![
+                vec![0x00, 0x0A, 0x01, 0x02],
+                vec![0x00, 0x0B, 0x01, 0x03],
+                vec![0x00, 0x0B, 0x01, 0x03],
+            ]
+        );
+
+        let request = frame.read_multiple_holding_registers_request(0x01, 0x0010, 0x0002);
+        let response = client.call(request).await.unwrap();
+        assert_eq!(response.as_exception(), Some(&Exception::IllegalFunction));
+    }
+
+    #[test]
+    fn invalid_log_test() {
+        let path = std::env::temp_dir().join(format!("invalid_log_{}.log", std::process::id()));
+        fs::write(&path, "< 00 01 00 00 00 07 01 03 04 00 0A 01 02\n").unwrap();
+        let server = ReplayServer::from_log(&path, Version::Tcp);
+        fs::remove_file(&path).unwrap();
+        assert!(server.is_err());
+    }
+
+    #[tokio::test]
+    async fn rtu_replay_test() {
+        let path = std::env::temp_dir().join(format!("rtu_replay_{}.log", std::process::id()));
+        fs::write(
+            &path,
+            "> 01 03 00 00 00 02 C4 0B\n< 01 03 04 00 0A 01 02 5A 60\n",
+        )
+        .unwrap();
+        let server = ReplayServer::from_log(&path, Version::Rtu)
+            .unwrap()
+            .into_server();
+        fs::remove_file(&path).unwrap();
+
+        let (client_io, server_io) = duplex(256);
+        tokio::spawn(async move { server.serve(Framed::new(server_io, RtuServerCodec)).await });
+        let mut client = Client::rtu(client_io);
+
+        let request = Frame::rtu().read_multiple_holding_registers_request(0x01, 0x0000, 0x0002);
+        match client.call(request).await.unwrap() {
+            Response::ReadMultipleHoldingRegisters(_, body) => {
+                assert_eq!(body.values, vec![0x00, 0x0A, 0x01, 0x02])
+            }
+            response => panic!("unexpected response {:?}", response),
+        }
+    }
+
+    #[test]
+    fn rtu_crc_log_test() {
+        let path = std::env::temp_dir().join(format!("rtu_crc_log_{}.log", std::process::id()));
+        fs::write(
+            &path,
+            "> 01 03 00 00 00 02 C4 0C\n< 01 03 04 00 0A 01 02 5A 60\n",
+        )
+        .unwrap();
+        let server = ReplayServer::from_log(&path, Version::Rtu);
+        fs::remove_file(&path).unwrap();
+        assert!(server.is_err());
+    }
+}
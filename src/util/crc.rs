@@ -11,6 +11,52 @@
 //! assert_eq!(b, true);
 //! ```
 
+use std::fmt::Debug;
+
+/// Checksum closing a RTU frame
+///
+/// The RTU client codec computes and verifies frames through this trait, so a test can inject
+/// a stub, e.g. one producing deliberately wrong checksums.
+///
+/// # Examples
+///
+/// ```
+/// use easy_modbus::util::crc::Checksum;
+///
+/// #[derive(Debug)]
+/// struct Zero;
+///
+/// impl Checksum for Zero {
+///     fn compute(&self, _data: &[u8]) -> u16 {
+///         0x0000
+///     }
+/// }
+///
+/// assert!(!Zero.check(&[0x0B, 0x01, 0x00, 0x1D, 0x00, 0x1F], 0xED6E));
+///
+/// #[cfg(feature = "codec")]
+/// let codec = easy_modbus::codec::RtuClientCodec::with_checksum(Zero);
+/// ```
+pub trait Checksum: Debug + Send + Sync {
+    /// Compute the checksum of `data`, in the byte order sent on the wire
+    fn compute(&self, data: &[u8]) -> u16;
+
+    /// Verify `data` against a received checksum
+    fn check(&self, data: &[u8], crc: u16) -> bool {
+        self.compute(data) == crc
+    }
+}
+
+/// CRC-16 of the Modbus specification, the default [`Checksum`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ModbusCrc;
+
+impl Checksum for ModbusCrc {
+    fn compute(&self, data: &[u8]) -> u16 {
+        compute(data)
+    }
+}
+
 const CRC_TABLE: [u16; 256] = [
     0x0000, 0xC0C1, 0xC181, 0x0140, 0xC301, 0x03C0, 0x0280, 0xC241, 0xC601, 0x06C0, 0x0780, 0xC741,
     0x0500, 0xC5C1, 0xC481, 0x0440, 0xCC01, 0x0CC0, 0x0D80, 0xCD41, 0x0F00, 0xCFC1, 0xCE81, 0x0E40,
@@ -61,10 +107,24 @@ pub fn compute(data: &[u8]) -> u16 {
     for datum in data {
         crc = (crc >> 8) ^ CRC_TABLE[(crc ^ *datum as u16) as usize & 0xFF];
     }
-    crc = crc << 8 | crc >> 8;
+    crc = crc.rotate_left(8);
     crc
 }
 
+/// Append the CRC of `buf` to it, as sent on the wire to close a RTU frame
+///
+/// # Examples
+/// ```
+/// use easy_modbus::util::crc::append;
+/// let mut buf = vec![0x0B, 0x01, 0x00, 0x1D, 0x00, 0x1F];
+/// append(&mut buf);
+/// assert_eq!(buf, vec![0x0B, 0x01, 0x00, 0x1D, 0x00, 0x1F, 0xED, 0x6E]);
+/// ```
+pub fn append(buf: &mut Vec<u8>) {
+    let crc = compute(buf);
+    buf.extend_from_slice(&crc.to_be_bytes());
+}
+
 #[test]
 fn test_crc() {
     let data = vec![0x0B, 0x01, 0x00, 0x1D, 0x00, 0x1F];
@@ -74,3 +134,11 @@ fn test_crc() {
     assert_eq!(compute(&data), 0x2BE1);
     assert!(check(&data, 0x2BE1));
 }
+
+#[test]
+fn test_append() {
+    let mut buf = vec![0x0B, 0x01, 0x04, 0xCD, 0x6B, 0xB2, 0x7F];
+    append(&mut buf);
+    assert_eq!(&buf[7..], &[0x2B, 0xE1]);
+    assert!(check(&buf[..7], u16::from_be_bytes([buf[7], buf[8]])));
+}
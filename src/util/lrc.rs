@@ -0,0 +1,49 @@
+//! Utility for longitudinal redundancy check (LRC) calculate, closing Modbus ASCII frames.
+//!
+//! # Examples
+//! ```
+//! use easy_modbus::util::lrc::{check, compute};
+//! let data = vec![0x11, 0x03, 0x00, 0x6B, 0x00, 0x03];
+//! let lrc = compute(&data);
+//! assert_eq!(lrc, 0x7E);
+//!
+//! let b = check(&data, 0x7E);
+//! assert_eq!(b, true);
+//! ```
+
+/// Verify buffer and LRC
+///
+/// # Examples
+/// ```
+/// use easy_modbus::util::lrc::check;
+/// let data = vec![0x11, 0x03, 0x00, 0x6B, 0x00, 0x03];
+/// let foo = check(&data, 0x7E);
+/// ```
+pub fn check(data: &[u8], lrc: u8) -> bool {
+    compute(data) == lrc
+}
+
+/// A LRC Calculator, the two's complement of the sum of the bytes.
+///
+/// # Examples
+/// ```
+/// use easy_modbus::util::lrc::compute;
+/// let data = vec![0x11, 0x03, 0x00, 0x6B, 0x00, 0x03];
+/// let lrc = compute(&data);
+/// ```
+pub fn compute(data: &[u8]) -> u8 {
+    data.iter()
+        .fold(0u8, |sum, datum| sum.wrapping_add(*datum))
+        .wrapping_neg()
+}
+
+#[test]
+fn test_lrc() {
+    let data = vec![0x11, 0x03, 0x00, 0x6B, 0x00, 0x03];
+    assert_eq!(compute(&data), 0x7E);
+    assert!(check(&data, 0x7E));
+    let data = vec![0x11, 0x03, 0x06, 0x02, 0x2B, 0x00, 0x00, 0x00, 0x64];
+    assert_eq!(compute(&data), 0x55);
+    assert!(!check(&data, 0x56));
+    assert_eq!(compute(&[]), 0x00);
+}
@@ -1,4 +1,113 @@
 //! Utilities for Easy Modbus.
 
 pub mod crc;
+pub mod lrc;
+#[cfg(feature = "serial")]
+pub mod serial;
 
+#[cfg(feature = "serial")]
+pub use serial::{list_serial_ports, PortType, SerialPortInfo};
+
+/// Order of the four bytes of a 32-bit value spread across two registers
+///
+/// Named after the byte sequence on the wire for the value `0xAABBCCDD`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum WordOrder {
+    /// `AA BB CC DD`, as in the Modbus specification
+    BigEndian,
+
+    /// `DD CC BB AA`
+    LittleEndian,
+
+    /// `CC DD AA BB`, big endian registers with the low word first
+    BigEndianSwap,
+
+    /// `BB AA DD CC`, little endian registers with the high word first
+    LittleEndianSwap,
+}
+
+impl WordOrder {
+    /// Rearrange four bytes read from the wire into big endian order
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use easy_modbus::util::WordOrder;
+    /// let bytes = WordOrder::BigEndianSwap.to_big_endian([0xCC, 0xDD, 0xAA, 0xBB]);
+    /// assert_eq!(u32::from_be_bytes(bytes), 0xAABBCCDD);
+    /// ```
+    pub fn to_big_endian(self, bytes: [u8; 4]) -> [u8; 4] {
+        let [a, b, c, d] = bytes;
+        match self {
+            WordOrder::BigEndian => [a, b, c, d],
+            WordOrder::LittleEndian => [d, c, b, a],
+            WordOrder::BigEndianSwap => [c, d, a, b],
+            WordOrder::LittleEndianSwap => [b, a, d, c],
+        }
+    }
+}
+
+/// Application level checksum a device computes over the registers it returns
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PayloadChecksum {
+    /// Sum of the registers, wrapping around at 16 bits
+    Sum,
+
+    /// Exclusive or of the registers
+    Xor,
+}
+
+impl PayloadChecksum {
+    /// Compute the checksum of `registers`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use easy_modbus::util::PayloadChecksum;
+    /// assert_eq!(PayloadChecksum::Sum.compute(&[0xFFFF, 0x0002]), 0x0001);
+    /// assert_eq!(PayloadChecksum::Xor.compute(&[0x0F0F, 0x00FF]), 0x0FF0);
+    /// ```
+    pub fn compute(self, registers: &[u16]) -> u16 {
+        match self {
+            PayloadChecksum::Sum => registers.iter().fold(0, |sum, &r| sum.wrapping_add(r)),
+            PayloadChecksum::Xor => registers.iter().fold(0, |xor, &r| xor ^ r),
+        }
+    }
+}
+
+/// Map `raw` linearly from `in_min..=in_max` onto `out_min..=out_max`
+///
+/// Raw values outside the input range are extrapolated, not clamped.
+///
+/// # Examples
+///
+/// ```
+/// use easy_modbus::util::map_range;
+/// assert_eq!(map_range(4095, 0, 4095, 0.0, 100.0), 100.0);
+/// assert_eq!(map_range(4, 4, 20, -40.0, 85.0), -40.0);
+/// ```
+pub fn map_range(raw: u16, in_min: u16, in_max: u16, out_min: f64, out_max: f64) -> f64 {
+    let (raw, in_min, in_max) = (raw as f64, in_min as f64, in_max as f64);
+    out_min + (raw - in_min) * (out_max - out_min) / (in_max - in_min)
+}
+
+#[test]
+fn test_word_order() {
+    let value = 0xAABBCCDD;
+    let orders = [
+        (WordOrder::BigEndian, [0xAA, 0xBB, 0xCC, 0xDD]),
+        (WordOrder::LittleEndian, [0xDD, 0xCC, 0xBB, 0xAA]),
+        (WordOrder::BigEndianSwap, [0xCC, 0xDD, 0xAA, 0xBB]),
+        (WordOrder::LittleEndianSwap, [0xBB, 0xAA, 0xDD, 0xCC]),
+    ];
+    for (order, bytes) in orders {
+        assert_eq!(u32::from_be_bytes(order.to_big_endian(bytes)), value);
+    }
+}
+
+#[test]
+fn test_map_range() {
+    assert!((map_range(2048, 0, 4095, 0.0, 100.0) - 50.0).abs() < 0.05);
+    assert_eq!(map_range(0, 0, 4095, 0.0, 100.0), 0.0);
+    assert_eq!(map_range(20, 4, 20, 100.0, 0.0), 0.0);
+}
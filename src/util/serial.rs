@@ -0,0 +1,91 @@
+//! Discovery of the serial ports an RTU client may open.
+
+use tokio_serial::SerialPortType;
+
+/// A serial port of the system
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SerialPortInfo {
+    /// Name to open the port with, e.g. `/dev/ttyUSB0` or `COM4`
+    pub name: String,
+
+    /// How the port is connected
+    pub port_type: PortType,
+}
+
+/// How a serial port is connected
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum PortType {
+    /// USB adapter, identified by its vendor and product ids
+    Usb {
+        vendor_id: u16,
+        product_id: u16,
+        manufacturer: Option<String>,
+        product: Option<String>,
+        serial_number: Option<String>,
+    },
+
+    /// Permanent PCI port
+    Pci,
+
+    /// Bluetooth link
+    Bluetooth,
+
+    /// Connection the system does not tell
+    Unknown,
+}
+
+impl From<SerialPortType> for PortType {
+    fn from(port_type: SerialPortType) -> Self {
+        match port_type {
+            SerialPortType::UsbPort(info) => PortType::Usb {
+                vendor_id: info.vid,
+                product_id: info.pid,
+                manufacturer: info.manufacturer,
+                product: info.product,
+                serial_number: info.serial_number,
+            },
+            SerialPortType::PciPort => PortType::Pci,
+            SerialPortType::BluetoothPort => PortType::Bluetooth,
+            SerialPortType::Unknown => PortType::Unknown,
+        }
+    }
+}
+
+/// List the serial ports of the system, e.g. to let the user choose the one of an RTU bus
+///
+/// Returns no port when the system cannot enumerate them.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use easy_modbus::util::list_serial_ports;
+///
+/// for port in list_serial_ports() {
+///     println!("{}\t{:?}", port.name, port.port_type);
+/// }
+/// ```
+pub fn list_serial_ports() -> Vec<SerialPortInfo> {
+    tokio_serial::available_ports()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|port| SerialPortInfo {
+            name: port.port_name,
+            port_type: port.port_type.into(),
+        })
+        .collect()
+}
+
+#[test]
+fn test_list_serial_ports() {
+    for port in list_serial_ports() {
+        assert!(!port.name.is_empty());
+    }
+
+    let port_type = PortType::from(SerialPortType::PciPort);
+    let port = SerialPortInfo {
+        name: "/dev/ttyS0".to_string(),
+        port_type,
+    };
+    assert_eq!(port.name, "/dev/ttyS0");
+    assert_eq!(port.port_type, PortType::Pci);
+}